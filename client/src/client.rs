@@ -0,0 +1,353 @@
+use {
+    crate::{
+        error::{ClientError, Result},
+        types::*,
+    },
+    serde::{de::DeserializeOwned, Serialize},
+    serde_json::{json, Value},
+    solana_rpc_client_api::{
+        config::{RpcBlockConfig, RpcContextConfig, RpcEpochConfig},
+        response::{RpcInflationReward, RpcPrioritizationFee, RpcVersionInfo},
+    },
+    solana_sdk::{
+        clock::{Slot, UnixTimestamp},
+        commitment_config::CommitmentConfig,
+    },
+    std::sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Client of one Block Meta RPC endpoint
+pub struct BlockMetaRpcClient {
+    http: reqwest::Client,
+    url: String,
+    next_id: AtomicU64,
+}
+
+impl BlockMetaRpcClient {
+    /// `url` is the service root, e.g. `http://localhost:8899`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::new_with_client(url, reqwest::Client::new())
+    }
+
+    /// Use a preconfigured HTTP client, e.g. to set timeouts or default headers
+    pub fn new_with_client(url: impl Into<String>, http: reqwest::Client) -> Self {
+        Self {
+            http,
+            url: url.into().trim_end_matches('/').to_string(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn send<T: DeserializeOwned>(&self, path: &str, method: &str, params: Value) -> Result<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let response: Value = self
+            .http
+            .post(format!("{}{}", self.url, path))
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        parse_response(response)
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        self.send("", method, params).await
+    }
+
+    async fn call_v2<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        self.send("/v2", method, params).await
+    }
+
+    pub async fn get_health(&self) -> Result<String> {
+        self.call("getHealth", params(vec![])).await
+    }
+
+    pub async fn get_version(&self) -> Result<RpcVersionInfo> {
+        self.call("getVersion", params(vec![])).await
+    }
+
+    pub async fn get_slot(&self, config: Option<RpcContextConfig>) -> Result<Slot> {
+        self.call("getSlot", params(vec![to_value(config)])).await
+    }
+
+    pub async fn get_block_height(&self, config: Option<RpcContextConfig>) -> Result<u64> {
+        self.call("getBlockHeight", params(vec![to_value(config)])).await
+    }
+
+    pub async fn get_transaction_count(&self, config: Option<RpcContextConfig>) -> Result<u64> {
+        self.call("getTransactionCount", params(vec![to_value(config)]))
+            .await
+    }
+
+    pub async fn get_first_available_block(&self) -> Result<Slot> {
+        self.call("getFirstAvailableBlock", params(vec![])).await
+    }
+
+    /// Limits enforced by the service, e.g. the widest `getBlocks` range
+    pub async fn get_limits(&self) -> Result<RpcLimits> {
+        self.call("getLimits", params(vec![])).await
+    }
+
+    /// APIs, storage, caches and listening addresses of the instance, as it logged them
+    /// at startup
+    pub async fn get_build_info(&self) -> Result<RpcBuildInfo> {
+        self.call("getBuildInfo", params(vec![])).await
+    }
+
+    pub async fn get_block_time(&self, slot: Slot) -> Result<Option<UnixTimestamp>> {
+        self.call("getBlockTime", params(vec![json!(slot)])).await
+    }
+
+    /// Stored parts of the block at `slot`, a summary rather than a full block
+    pub async fn get_block_summary(
+        &self,
+        slot: Slot,
+        config: Option<RpcBlockConfig>,
+    ) -> Result<RpcBlockSummary> {
+        self.call("getBlockSummary", params(vec![json!(slot), to_value(config)]))
+            .await
+    }
+
+    /// Stored slots in `start_slot..=end_slot`
+    pub async fn get_blocks(
+        &self,
+        start_slot: Slot,
+        end_slot: Option<Slot>,
+        config: Option<RpcContextConfig>,
+    ) -> Result<Vec<Slot>> {
+        self.call(
+            "getBlocks",
+            params(vec![json!(start_slot), to_value(end_slot), to_value(config)]),
+        )
+        .await
+    }
+
+    pub async fn get_blocks_with_limit(
+        &self,
+        start_slot: Slot,
+        limit: usize,
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<Vec<Slot>> {
+        self.call(
+            "getBlocksWithLimit",
+            params(vec![json!(start_slot), json!(limit), to_value(commitment)]),
+        )
+        .await
+    }
+
+    /// Number of stored blocks in `start_slot..=end_slot`
+    pub async fn get_block_count(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+        config: Option<RpcContextConfig>,
+    ) -> Result<u64> {
+        self.call(
+            "getBlockCount",
+            params(vec![json!(start_slot), json!(end_slot), to_value(config)]),
+        )
+        .await
+    }
+
+    pub async fn get_block_rewards(&self, slot: Slot) -> Result<Vec<RpcBlockReward>> {
+        self.call("getBlockRewards", params(vec![json!(slot)])).await
+    }
+
+    pub async fn blocks_exist(&self, slots: Vec<Slot>) -> Result<Vec<bool>> {
+        self.call("blocksExist", params(vec![json!(slots)])).await
+    }
+
+    pub async fn get_block_meta_version(&self, slot: Slot) -> Result<Option<RpcBlockMetaVersion>> {
+        self.call("getBlockMetaVersion", params(vec![json!(slot)])).await
+    }
+
+    pub async fn get_block_height_for_slot(&self, slot: Slot) -> Result<Option<u64>> {
+        self.call("getBlockHeightForSlot", params(vec![json!(slot)])).await
+    }
+
+    pub async fn get_slot_for_block_height(&self, block_height: u64) -> Result<Option<Slot>> {
+        self.call("getSlotForBlockHeight", params(vec![json!(block_height)])).await
+    }
+
+    pub async fn get_slot_for_blockhash(&self, blockhash: &str) -> Result<Option<Slot>> {
+        self.call("getSlotForBlockhash", params(vec![json!(blockhash)])).await
+    }
+
+    pub async fn get_block_fee_stats(&self, slot: Slot) -> Result<Option<RpcFeeStats>> {
+        self.call("getBlockFeeStats", params(vec![json!(slot)])).await
+    }
+
+    pub async fn get_fee_stats_range(&self, start_slot: Slot, end_slot: Slot) -> Result<RpcFeeStats> {
+        self.call(
+            "getFeeStatsRange",
+            params(vec![json!(start_slot), json!(end_slot)]),
+        )
+        .await
+    }
+
+    /// The server does not filter fees by account and rejects non-empty `addresses`
+    pub async fn get_recent_prioritization_fees(
+        &self,
+        addresses: Option<Vec<String>>,
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.call(
+            "getRecentPrioritizationFees",
+            params(vec![to_value(addresses)]),
+        )
+        .await
+    }
+
+    pub async fn get_inflation_reward(
+        &self,
+        addresses: Vec<String>,
+        config: Option<RpcEpochConfig>,
+    ) -> Result<Vec<Option<RpcInflationReward>>> {
+        self.call(
+            "getInflationReward",
+            params(vec![json!(addresses), to_value(config)]),
+        )
+        .await
+    }
+
+    pub async fn get_backfill_status(
+        &self,
+        start_slot: Slot,
+        end_slot: Option<Slot>,
+    ) -> Result<Vec<RpcBackfillRange>> {
+        self.call(
+            "getBackfillStatus",
+            params(vec![json!(start_slot), to_value(end_slot)]),
+        )
+        .await
+    }
+
+    /// `getSlot` of the `/v2` namespace, failing instead of returning 0 when no block
+    /// is stored
+    pub async fn get_slot_v2(&self, config: Option<RpcContextConfig>) -> Result<Slot> {
+        self.call_v2("getSlot", params(vec![to_value(config)])).await
+    }
+
+    pub async fn get_block_height_v2(&self, config: Option<RpcContextConfig>) -> Result<u64> {
+        self.call_v2("getBlockHeight", params(vec![to_value(config)]))
+            .await
+    }
+
+    pub async fn get_first_available_block_v2(&self) -> Result<Slot> {
+        self.call_v2("getFirstAvailableBlock", params(vec![])).await
+    }
+
+    pub async fn get_block_time_v2(&self, slot: Slot) -> Result<Option<UnixTimestamp>> {
+        self.call_v2("getBlockTime", params(vec![json!(slot)])).await
+    }
+
+    /// One page of stored slots; continue from `next_start_slot` until it is `None`
+    pub async fn get_blocks_page(
+        &self,
+        start_slot: Slot,
+        end_slot: Option<Slot>,
+        config: Option<RpcBlocksPageConfig>,
+    ) -> Result<RpcSlotPage> {
+        self.call_v2(
+            "getBlocks",
+            params(vec![json!(start_slot), to_value(end_slot), to_value(config)]),
+        )
+        .await
+    }
+
+    /// Exclude `slot` from every read path. Requires the admin RPC to be enabled.
+    pub async fn mark_slot_orphaned(&self, slot: Slot) -> Result<()> {
+        self.call("markSlotOrphaned", params(vec![json!(slot)])).await
+    }
+
+    pub async fn unmark_slot_orphaned(&self, slot: Slot) -> Result<()> {
+        self.call("unmarkSlotOrphaned", params(vec![json!(slot)])).await
+    }
+
+    /// Deprecated arguments the service was started with. Requires the admin RPC to be
+    /// enabled.
+    pub async fn get_deprecated_arguments(&self) -> Result<Vec<RpcDeprecatedArgument>> {
+        self.call("getDeprecatedArguments", params(vec![])).await
+    }
+
+    /// Tables of the storage and the optional features they enable. Requires the admin
+    /// RPC to be enabled.
+    pub async fn get_schema_info(&self) -> Result<RpcSchemaInfo> {
+        self.call("getSchemaInfo", params(vec![])).await
+    }
+
+    /// Have the service close its HTTP listener and bind a new one. The call returns
+    /// before the listener is closed. Requires the admin RPC to be enabled.
+    pub async fn restart_rpc(&self) -> Result<()> {
+        self.call("restartRpc", params(vec![])).await
+    }
+}
+
+fn to_value<T: Serialize>(value: Option<T>) -> Value {
+    value
+        .map(|value| serde_json::to_value(value).unwrap_or(Value::Null))
+        .unwrap_or(Value::Null)
+}
+
+/// Positional params, omitting trailing unset optional arguments
+fn params(mut values: Vec<Value>) -> Value {
+    while values.last() == Some(&Value::Null) {
+        values.pop();
+    }
+    Value::Array(values)
+}
+
+fn parse_response<T: DeserializeOwned>(mut response: Value) -> Result<T> {
+    if let Some(error) = response.get_mut("error").map(Value::take) {
+        return Err(ClientError::Rpc {
+            code: error.get("code").and_then(Value::as_i64).unwrap_or_default(),
+            message: error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            data: error.get("data").cloned(),
+        });
+    }
+    let result = response
+        .get_mut("result")
+        .map(Value::take)
+        .ok_or_else(|| ClientError::InvalidResponse("missing result".to_string()))?;
+    serde_json::from_value(result).map_err(|err| ClientError::InvalidResponse(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params() {
+        assert_eq!(params(vec![json!(1), Value::Null, Value::Null]), json!([1]));
+        assert_eq!(params(vec![json!(1), Value::Null, json!({})]), json!([1, null, {}]));
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let slots: Vec<Slot> = parse_response(json!({"jsonrpc": "2.0", "result": [1, 2], "id": 1})).unwrap();
+        assert_eq!(slots, vec![1, 2]);
+
+        let error = parse_response::<Slot>(json!({
+            "jsonrpc": "2.0",
+            "error": {"code": -32009, "message": "Slot 5 was skipped"},
+            "id": 1,
+        }))
+        .unwrap_err();
+        assert!(matches!(error, ClientError::Rpc { code: -32009, .. }));
+    }
+}
@@ -0,0 +1,20 @@
+use {serde_json::Value, thiserror::Error};
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// Error object returned by the service
+    #[error("RPC error {code}: {message}")]
+    Rpc {
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,16 @@
+//! Typed client for the Block Meta RPC service.
+//!
+//! Wraps the JSON-RPC interface, including the `/v2` namespace and the methods this
+//! service adds on top of Solana's, so callers never assemble requests by hand.
+
+pub mod client;
+pub mod error;
+pub mod types;
+
+pub use {
+    client::BlockMetaRpcClient,
+    error::{ClientError, Result},
+};
+
+#[macro_use]
+extern crate serde_derive;
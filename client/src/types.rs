@@ -0,0 +1,184 @@
+//! Responses of the methods this service adds, mirroring the server's wire format
+
+use {
+    solana_sdk::{
+        clock::{Slot, UnixTimestamp},
+        commitment_config::CommitmentConfig,
+    },
+    std::collections::HashMap,
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcBackfillState {
+    Loaded,
+    Pending,
+    Failed,
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBackfillRange {
+    pub start_slot: Slot,
+    pub end_slot: Slot,
+    pub status: RpcBackfillState,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlockReward {
+    pub pubkey: String,
+    pub lamports: i64,
+    pub post_balance: u64,
+    pub reward_type: Option<String>,
+    pub commission: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlockMetaRevision {
+    pub block_time: Option<UnixTimestamp>,
+    pub deleted: bool,
+    pub updated_at: Option<UnixTimestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlockMetaVersion {
+    pub slot: Slot,
+    pub version: usize,
+    #[serde(flatten)]
+    pub current: RpcBlockMetaRevision,
+    /// Superseded versions, oldest first
+    pub history: Vec<RpcBlockMetaRevision>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlockSummary {
+    pub block_time: Option<UnixTimestamp>,
+    /// Block time in milliseconds, when the server has millisecond block times enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_time_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewards: Option<Vec<RpcBlockReward>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcFeePercentile {
+    pub percentile: u8,
+    pub prioritization_fee: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcFeeStats {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<Slot>,
+    pub transactions: u64,
+    pub total_fee: u64,
+    pub average_fee: f64,
+    pub prioritization_fee_percentiles: Vec<RpcFeePercentile>,
+    pub max_prioritization_fee: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlocksPageConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(flatten)]
+    pub commitment: Option<CommitmentConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcThrottleLimits {
+    pub cost_per_second: f64,
+    pub burst: f64,
+    /// Slots of a range call that cost one weight unit
+    pub slots_per_cost_unit: u64,
+    /// Weight per method; methods not listed weigh 1
+    pub method_costs: HashMap<String, f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcLimits {
+    pub max_request_body_size: usize,
+    pub max_response_size: Option<usize>,
+    /// Widest `getBlocks` and `getBlockCount` range
+    pub max_blocks_range: u64,
+    /// Largest `getBlocksWithLimit` limit
+    pub max_blocks_limit: usize,
+    pub max_fee_stats_range: u64,
+    pub max_prioritization_fee_accounts: usize,
+    pub max_blocks_exist_slots: usize,
+    /// Storage timeout of a call, also the largest budget a client may request
+    pub request_timeout_ms: Option<u64>,
+    pub max_in_flight_per_client: Option<usize>,
+    pub throttle: Option<RpcThrottleLimits>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcDeprecatedArgument {
+    pub argument: String,
+    pub replaced_by: Option<String>,
+    pub warning: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcStorageInfo {
+    pub backend: String,
+    pub server_version: Option<String>,
+    pub canary_percent: Option<u8>,
+    pub optional_features: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBuildInfo {
+    pub version: String,
+    pub apis: Vec<String>,
+    pub storage: Option<RpcStorageInfo>,
+    pub caches: Vec<String>,
+    pub auth: String,
+    pub listen_addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSchemaTable {
+    /// `primary`, or the shard holding the table
+    pub database: String,
+    pub name: String,
+    pub columns: Vec<String>,
+    pub estimated_rows: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSchemaFeature {
+    pub name: String,
+    pub enabled: bool,
+    /// Table or column the feature is served from
+    pub source: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSchemaInfo {
+    pub tables: Vec<RpcSchemaTable>,
+    pub features: Vec<RpcSchemaFeature>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSlotPage {
+    pub slots: Vec<Slot>,
+    pub next_start_slot: Option<Slot>,
+}
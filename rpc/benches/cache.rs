@@ -0,0 +1,52 @@
+//! In-process caching layers sitting in front of storage:
+//!
+//! ```text
+//! cargo bench -p block-meta-rpc --features bench
+//! ```
+
+use {
+    block_meta_rpc::{cache::TtlCache, singleflight::SingleFlight},
+    criterion::{black_box, criterion_group, criterion_main, Criterion},
+    std::time::Duration,
+    tokio::runtime::Runtime,
+};
+
+const CAPACITY: usize = 100_000;
+
+fn bench_ttl_cache(c: &mut Criterion) {
+    let cache = TtlCache::new("bench", Duration::from_secs(3600), CAPACITY);
+    for slot in 0..CAPACITY as u64 {
+        cache.insert(slot, slot as i64);
+    }
+
+    let mut group = c.benchmark_group("ttl_cache");
+    let mut slot = 0;
+    group.bench_function("get_hit", |b| {
+        b.iter(|| {
+            slot = (slot + 1) % CAPACITY as u64;
+            cache.get(black_box(&slot))
+        })
+    });
+    group.bench_function("get_miss", |b| b.iter(|| cache.get(black_box(&u64::MAX))));
+    // Every insert past capacity evicts the oldest entry
+    let mut next_slot = CAPACITY as u64;
+    group.bench_function("insert_evicting", |b| {
+        b.iter(|| {
+            cache.insert(next_slot, next_slot as i64);
+            next_slot += 1;
+        })
+    });
+    group.finish();
+}
+
+fn bench_single_flight(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let flights = SingleFlight::<u64, u64>::default();
+    c.bench_function("single_flight_uncontended", |b| {
+        b.to_async(&runtime)
+            .iter(|| flights.run(black_box(1), || async { 1 }))
+    });
+}
+
+criterion_group!(benches, bench_ttl_cache, bench_single_flight);
+criterion_main!(benches);
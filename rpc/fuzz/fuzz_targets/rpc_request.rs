@@ -0,0 +1,63 @@
+//! Arbitrary request bodies dispatched through the `/` and `/v2` JSON-RPC handlers.
+//!
+//! Storage is the fixed data of `sample_stub`, so every method runs its full path over
+//! stored results, with no database involved. Any panic is a finding.
+//!
+//! ```text
+//! cd rpc && cargo +nightly fuzz run rpc_request
+//! ```
+
+#![no_main]
+
+use {
+    block_meta_rpc::{
+        request_processor::{JsonRpcConfig, JsonRpcRequestProcessor},
+        rpc_service::{build_io_handler, build_v2_io_handler, RpcIoHandler, RpcMiddleware},
+        storage_stub::{sample_stub, stub_router},
+    },
+    libfuzzer_sys::fuzz_target,
+    solana_sdk::exit::Exit,
+    std::sync::{Arc, OnceLock, RwLock},
+    tokio::runtime::Runtime,
+};
+
+struct Harness {
+    runtime: Runtime,
+    io: RpcIoHandler,
+    v2_io: RpcIoHandler,
+    request_processor: JsonRpcRequestProcessor,
+}
+
+fn harness() -> &'static Harness {
+    static HARNESS: OnceLock<Harness> = OnceLock::new();
+    HARNESS.get_or_init(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let request_processor = JsonRpcRequestProcessor::new(
+            JsonRpcConfig::default_for_storage_rpc(),
+            Arc::new(RwLock::new(Exit::default())),
+            Some(runtime.block_on(stub_router(sample_stub()))),
+        );
+        Harness {
+            runtime,
+            io: build_io_handler(true, true, RpcMiddleware::default()),
+            v2_io: build_v2_io_handler(RpcMiddleware::default()),
+            request_processor,
+        }
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let request = match std::str::from_utf8(data) {
+        Ok(request) => request,
+        Err(_) => return,
+    };
+    let harness = harness();
+    for io in [&harness.io, &harness.v2_io] {
+        harness
+            .runtime
+            .block_on(io.handle_request(request, harness.request_processor.clone()));
+    }
+});
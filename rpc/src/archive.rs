@@ -0,0 +1,190 @@
+use {
+    crate::{cache::TtlCache, retention::ArchiveConfig},
+    arrow_array::{Array, Int64Array, RecordBatch, UInt64Array},
+    arrow_schema::{ArrowError, DataType, Field, Schema},
+    bytes::Bytes,
+    object_store::{aws::AmazonS3Builder, path::Path, ObjectStore},
+    parquet::{
+        arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter},
+        errors::ParquetError,
+    },
+    solana_sdk::clock::{Slot, UnixTimestamp},
+    solana_storage_mysql::meta_storage::{self, MetaStorage},
+    std::{collections::HashMap, sync::Arc, time::Duration},
+    thiserror::Error,
+};
+
+/// Number of slots stored in each archive file. Changing it orphans existing files.
+pub const ARCHIVE_PARTITION_SLOTS: u64 = 100_000;
+
+/// Number of decoded partitions kept in memory for archive reads
+const PARTITION_CACHE_CAPACITY: usize = 8;
+const PARTITION_CACHE_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] meta_storage::Error),
+
+    #[error("Malformed archive partition {0}")]
+    MalformedPartition(u64),
+}
+
+pub type Result<T> = std::result::Result<T, ArchiveError>;
+
+type Partition = Arc<HashMap<Slot, UnixTimestamp>>;
+
+/// Cold tier holding block times purged from MySQL, as one Parquet file per
+/// `ARCHIVE_PARTITION_SLOTS` slots on S3.
+///
+/// S3 credentials and region are read from the standard `AWS_*` environment variables.
+pub struct BlockArchive {
+    store: Arc<dyn ObjectStore>,
+    prefix: String,
+    partitions: TtlCache<u64, Partition>,
+}
+
+impl BlockArchive {
+    pub fn new(config: &ArchiveConfig) -> Result<Self> {
+        let store = AmazonS3Builder::from_env()
+            .with_bucket_name(&config.s3_bucket)
+            .build()?;
+        Ok(Self {
+            store: Arc::new(store),
+            prefix: config.s3_prefix.trim_end_matches('/').to_string(),
+            partitions: TtlCache::new(
+                "archive-partitions",
+                PARTITION_CACHE_TTL,
+                PARTITION_CACHE_CAPACITY,
+            ),
+        })
+    }
+
+    pub fn partition_of(slot: Slot) -> u64 {
+        slot / ARCHIVE_PARTITION_SLOTS
+    }
+
+    fn partition_path(&self, partition: u64) -> Path {
+        Path::from(format!("{}/block_time/{:012}.parquet", self.prefix, partition))
+    }
+
+    pub async fn contains_partition(&self, partition: u64) -> Result<bool> {
+        match self.store.head(&self.partition_path(partition)).await {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Copy every block of `partition` from `storage` into the archive. Partitions
+    /// already present are left untouched, as their rows may have been purged since.
+    pub async fn archive_partition(&self, storage: &MetaStorage, partition: u64) -> Result<usize> {
+        if self.contains_partition(partition).await? {
+            return Ok(0);
+        }
+        let start_slot = partition * ARCHIVE_PARTITION_SLOTS;
+        let blocks = storage
+            .get_block_times_in_range(start_slot, start_slot + ARCHIVE_PARTITION_SLOTS)
+            .await?;
+        if blocks.is_empty() {
+            return Ok(0);
+        }
+        let count = blocks.len();
+        let file = encode_partition(blocks)?;
+        self.store
+            .put(&self.partition_path(partition), Bytes::from(file))
+            .await?;
+        info!("Archived {} blocks of partition {}", count, partition);
+        Ok(count)
+    }
+
+    /// Look up the block time of `slot` in the archive
+    pub async fn get_block_time(&self, slot: Slot) -> Result<Option<UnixTimestamp>> {
+        let partition = Self::partition_of(slot);
+        if let Some(blocks) = self.partitions.get(&partition) {
+            return Ok(blocks.get(&slot).copied());
+        }
+        let file = match self.store.get(&self.partition_path(partition)).await {
+            Ok(file) => file.bytes().await?,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let blocks = Arc::new(decode_partition(partition, file)?);
+        self.partitions.insert(partition, blocks.clone());
+        Ok(blocks.get(&slot).copied())
+    }
+
+    pub fn submit_metrics(&self) {
+        self.partitions.submit_metrics();
+    }
+}
+
+fn partition_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("slot", DataType::UInt64, false),
+        Field::new("block_time", DataType::Int64, false),
+    ]))
+}
+
+fn encode_partition(blocks: Vec<(Slot, UnixTimestamp)>) -> Result<Vec<u8>> {
+    let (slots, block_times): (Vec<_>, Vec<_>) = blocks.into_iter().unzip();
+    let schema = partition_schema();
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(UInt64Array::from(slots)),
+            Arc::new(Int64Array::from(block_times)),
+        ],
+    )?;
+    let mut file = vec![];
+    let mut writer = ArrowWriter::try_new(&mut file, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(file)
+}
+
+fn decode_partition(partition: u64, file: Bytes) -> Result<HashMap<Slot, UnixTimestamp>> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+    let mut blocks = HashMap::new();
+    for batch in reader {
+        let batch = batch?;
+        let slots = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .ok_or(ArchiveError::MalformedPartition(partition))?;
+        let block_times = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .ok_or(ArchiveError::MalformedPartition(partition))?;
+        for row in 0..batch.num_rows() {
+            if slots.is_valid(row) && block_times.is_valid(row) {
+                blocks.insert(slots.value(row), block_times.value(row));
+            }
+        }
+    }
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_roundtrip() {
+        let blocks = vec![(100_000, 1_700_000_000), (100_002, 1_700_000_001)];
+        let file = encode_partition(blocks.clone()).unwrap();
+        let decoded = decode_partition(1, Bytes::from(file)).unwrap();
+        assert_eq!(decoded, blocks.into_iter().collect());
+    }
+}
@@ -0,0 +1,97 @@
+//! Summary of what an instance serves, logged as one line at startup and returned by
+//! `getBuildInfo`, so a deploy can be verified from either
+
+use std::fmt;
+
+/// Storage an instance serves from
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcStorageInfo {
+    pub backend: String,
+    /// Version reported by the database server, when it could be read
+    pub server_version: Option<String>,
+    /// Share of reads sent to the canary backend, when one is configured
+    pub canary_percent: Option<u8>,
+    /// Optional features the schema enables, as named by `getSchemaInfo`
+    pub optional_features: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBuildInfo {
+    pub version: String,
+    pub apis: Vec<String>,
+    /// `None` when no storage is configured
+    pub storage: Option<RpcStorageInfo>,
+    pub caches: Vec<String>,
+    /// `interceptors` when hooks of the embedding application see every request,
+    /// `none` otherwise
+    pub auth: String,
+    pub listen_addresses: Vec<String>,
+}
+
+/// `key=value` pairs, lists joined with `,` and `-` for none
+impl fmt::Display for RpcBuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |values: &[String]| match values {
+            [] => "-".to_string(),
+            values => values.join(","),
+        };
+        write!(f, "version={} apis={}", self.version, list(&self.apis))?;
+        match &self.storage {
+            Some(storage) => write!(
+                f,
+                " storage={} storage_version={} canary_percent={} optional_features={}",
+                storage.backend,
+                storage.server_version.as_deref().unwrap_or("-"),
+                storage
+                    .canary_percent
+                    .map_or("-".to_string(), |percent| percent.to_string()),
+                list(&storage.optional_features),
+            )?,
+            None => write!(f, " storage=-")?,
+        }
+        write!(
+            f,
+            " caches={} auth={} listen={}",
+            list(&self.caches),
+            self.auth,
+            list(&self.listen_addresses)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let mut build_info = RpcBuildInfo {
+            version: "1.0.0".to_string(),
+            apis: vec!["minimal".to_string(), "full".to_string()],
+            storage: None,
+            caches: vec![],
+            auth: "none".to_string(),
+            listen_addresses: vec!["0.0.0.0:8899".to_string()],
+        };
+        assert_eq!(
+            build_info.to_string(),
+            "version=1.0.0 apis=minimal,full storage=- caches=- auth=none listen=0.0.0.0:8899"
+        );
+
+        build_info.storage = Some(RpcStorageInfo {
+            backend: "mysql".to_string(),
+            server_version: Some("8.0.36".to_string()),
+            canary_percent: None,
+            optional_features: vec!["rewards".to_string(), "fees".to_string()],
+        });
+        build_info.caches = vec!["blockTime".to_string()];
+        assert_eq!(
+            build_info.to_string(),
+            "version=1.0.0 apis=minimal,full storage=mysql storage_version=8.0.36 \
+             canary_percent=- optional_features=rewards,fees caches=blockTime auth=none \
+             listen=0.0.0.0:8899"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use {
+    std::{
+        collections::{HashMap, VecDeque},
+        hash::Hash,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+struct TtlCacheInner<K, V> {
+    entries: HashMap<K, (V, Instant)>,
+    // Insertion order, used to evict the oldest entries once the cache is full
+    order: VecDeque<(K, Instant)>,
+}
+
+/// Cumulative counters of a cache, reported as metrics
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: u64,
+}
+
+/// Bounded in-process cache whose entries expire after a fixed TTL
+pub struct TtlCache<K, V> {
+    name: &'static str,
+    ttl: Duration,
+    capacity: usize,
+    inner: Mutex<TtlCacheInner<K, V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// `name` identifies the cache in metrics
+    pub fn new(name: &'static str, ttl: Duration, capacity: usize) -> Self {
+        Self {
+            name,
+            ttl,
+            capacity,
+            inner: Mutex::new(TtlCacheInner {
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+            hits: AtomicU64::default(),
+            misses: AtomicU64::default(),
+            evictions: AtomicU64::default(),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut inner = self.inner.lock().unwrap();
+        let expired = match inner.entries.get(key) {
+            Some((value, inserted_at)) if inserted_at.elapsed() < self.ttl => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Some(value.clone());
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            inner.entries.remove(key);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+        while inner.entries.len() >= self.capacity && !inner.entries.contains_key(&key) {
+            match inner.order.pop_front() {
+                Some((oldest, inserted_at)) => {
+                    // Skip queue entries whose key was re-inserted since
+                    if inner
+                        .entries
+                        .get(&oldest)
+                        .is_some_and(|(_, current)| *current == inserted_at)
+                    {
+                        inner.entries.remove(&oldest);
+                        self.evictions.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                None => break,
+            }
+        }
+        inner.order.push_back((key.clone(), now));
+        inner.entries.insert(key, (value, now));
+        // Keep the queue from growing without bound when keys are refreshed repeatedly
+        if inner.order.len() > self.capacity.saturating_mul(2) {
+            let TtlCacheInner { entries, order } = &mut *inner;
+            order.retain(|(key, inserted_at)| {
+                entries
+                    .get(key)
+                    .is_some_and(|(_, current)| current == inserted_at)
+            });
+        }
+    }
+
+    pub fn remove(&self, key: &K) {
+        self.inner.lock().unwrap().entries.remove(key);
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            entries: self.len() as u64,
+        }
+    }
+
+    /// Report the cumulative counters and the current entry count
+    pub fn submit_metrics(&self) {
+        let stats = self.stats();
+        solana_metrics::datapoint_info!(
+            "block-meta-rpc-cache",
+            "cache" => self.name,
+            ("hits", stats.hits, i64),
+            ("misses", stats.misses, i64),
+            ("evictions", stats.evictions, i64),
+            ("entries", stats.entries, i64),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ttl_cache_expiry_and_capacity() {
+        let cache = TtlCache::new("test", Duration::from_millis(50), 2);
+        cache.insert(1u64, ());
+        cache.insert(2u64, ());
+        assert_eq!(cache.get(&1), Some(()));
+
+        // Inserting past capacity evicts the oldest entry
+        cache.insert(3u64, ());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&3), Some(()));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&3), None);
+
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 3,
+                evictions: 3,
+                entries: 0,
+            }
+        );
+    }
+}
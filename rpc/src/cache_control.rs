@@ -0,0 +1,117 @@
+use {
+    serde_json::Value,
+    std::{collections::HashMap, time::Duration},
+};
+
+/// `Cache-Control` max-age advertised on successful JSON-RPC responses per method, so
+/// reverse proxies keying on the request body can absorb hot identical queries.
+/// Methods not listed are not marked cacheable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RpcCachePolicy {
+    max_ages: HashMap<String, Duration>,
+}
+
+impl RpcCachePolicy {
+    pub fn new(max_ages: HashMap<String, Duration>) -> Self {
+        Self { max_ages }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max_ages.is_empty()
+    }
+
+    /// Max-age of the response to `request`, a single call or a batch. Only responses
+    /// without errors whose every call is cacheable qualify; a batch gets the shortest
+    /// max-age of its calls.
+    pub fn max_age(&self, request: &str, response: &str) -> Option<Duration> {
+        if self.is_empty() {
+            return None;
+        }
+        let request: Value = serde_json::from_str(request).ok()?;
+        let calls = match &request {
+            Value::Array(calls) if !calls.is_empty() => calls.as_slice(),
+            Value::Object(_) => std::slice::from_ref(&request),
+            _ => return None,
+        };
+        let max_age = calls
+            .iter()
+            .map(|call| {
+                let method = call.get("method")?.as_str()?;
+                self.max_ages.get(method).copied()
+            })
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .min()?;
+
+        let response: Value = serde_json::from_str(response).ok()?;
+        let succeeded = |response: &Value| response.get("result").is_some();
+        let all_succeeded = match &response {
+            Value::Array(responses) => responses.iter().all(succeeded),
+            response => succeeded(response),
+        };
+        all_succeeded.then_some(max_age)
+    }
+}
+
+/// Parse `METHOD=SECONDS`
+pub fn parse_method_max_age(value: &str) -> Result<(String, Duration), String> {
+    let (method, max_age) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected METHOD=SECONDS, got {value}"))?;
+    let max_age = max_age
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("invalid max-age for {method}: {max_age}"))?;
+    Ok((method.trim().to_string(), Duration::from_secs(max_age)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_age() {
+        let policy = RpcCachePolicy::new(
+            [
+                ("getBlockTime".to_string(), Duration::from_secs(60)),
+                ("getBlocks".to_string(), Duration::from_secs(5)),
+            ]
+            .into(),
+        );
+        let ok = r#"{"jsonrpc":"2.0","result":1,"id":1}"#;
+        let error = r#"{"jsonrpc":"2.0","error":{"code":-32009,"message":""},"id":1}"#;
+        let call = |method: &str| format!(r#"{{"jsonrpc":"2.0","id":1,"method":"{method}"}}"#);
+
+        assert_eq!(
+            policy.max_age(&call("getBlockTime"), ok),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(policy.max_age(&call("getSlot"), ok), None);
+        assert_eq!(policy.max_age(&call("getBlockTime"), error), None);
+
+        let batch = format!("[{},{}]", call("getBlockTime"), call("getBlocks"));
+        assert_eq!(
+            policy.max_age(&batch, &format!("[{ok},{ok}]")),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(policy.max_age(&batch, &format!("[{ok},{error}]")), None);
+        let batch = format!("[{},{}]", call("getBlockTime"), call("getSlot"));
+        assert_eq!(policy.max_age(&batch, &format!("[{ok},{ok}]")), None);
+        assert_eq!(policy.max_age("[]", "[]"), None);
+
+        assert_eq!(
+            RpcCachePolicy::default().max_age(&call("getBlockTime"), ok),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_method_max_age() {
+        assert_eq!(
+            parse_method_max_age("getBlockTime = 60"),
+            Ok(("getBlockTime".to_string(), Duration::from_secs(60)))
+        );
+        assert!(parse_method_max_age("getBlockTime").is_err());
+        assert!(parse_method_max_age("getBlockTime=-1").is_err());
+    }
+}
@@ -1,6 +1,13 @@
 use {
     crate::{
-        request_processor::MAX_REQUEST_BODY_SIZE,
+        cache_control::parse_method_max_age,
+        client_ip::IpCidr,
+        cors::DEFAULT_CORS_MAX_AGE,
+        identity::parse_label,
+        throttle::parse_method_cost,
+        request_processor::{
+            RpcDeprecatedArgument, MAX_BLOCKS_RANGE_CAP, MAX_REQUEST_BODY_SIZE, MAX_RESPONSE_SIZE,
+        },
     },
     clap::{
         App,
@@ -13,6 +20,7 @@ use {
             is_parsable,
         },
     },
+    solana_rpc_client_api::request::MAX_GET_CONFIRMED_BLOCKS_RANGE,
     solana_sdk::{
         rpc_port,
     },
@@ -67,15 +75,33 @@ fn deprecated_arguments() -> Vec<DeprecatedArg> {
         (@into-option $v:expr) => { Some($v) };
     }
 
+    add_arg!(
+        Arg::with_name("enable_rpc_mysql_meta_storage")
+            .long("enable-rpc-mysql-meta-storage")
+            .takes_value(false)
+            .help("Fetch block metadata info from MySQL instance"),
+        usage_warning: "MySQL metadata storage is always enabled.",
+    );
     add_arg!(Arg::with_name("minimal_rpc_api")
         .long("minimal-rpc-api")
         .takes_value(false)
         .help("Only expose the RPC methods required to serve snapshots to other nodes"));
+    add_arg!(
+        Arg::with_name("rpc_mysql_address")
+            .long("rpc-mysql-address")
+            .value_name("ADDRESS")
+            .takes_value(true)
+            .help("Address of MySQL instance to use"),
+        replaced_by: "mysql-host",
+        usage_warning: "The address is still used as --mysql-host and --mysql-port.",
+    );
 
     res
 }
 
-pub fn warn_for_deprecated_arguments(matches: &ArgMatches) {
+/// Log a warning for every deprecated argument in use, returning them
+pub fn warn_for_deprecated_arguments(matches: &ArgMatches) -> Vec<RpcDeprecatedArgument> {
+    let mut used = vec![];
     for DeprecatedArg {
         arg,
         replaced_by,
@@ -83,7 +109,8 @@ pub fn warn_for_deprecated_arguments(matches: &ArgMatches) {
     } in deprecated_arguments().into_iter()
     {
         if matches.is_present(arg.b.name) {
-            let mut msg = format!("--{} is deprecated", arg.b.name.replace('_', "-"));
+            let argument = arg.b.name.replace('_', "-");
+            let mut msg = format!("--{argument} is deprecated");
             if let Some(replaced_by) = replaced_by {
                 msg.push_str(&format!(", please use --{replaced_by}"));
             }
@@ -95,8 +122,14 @@ pub fn warn_for_deprecated_arguments(matches: &ArgMatches) {
                 }
             }
             warn!("{}", msg);
+            used.push(RpcDeprecatedArgument {
+                argument,
+                replaced_by: replaced_by.map(str::to_string),
+                warning: msg,
+            });
         }
     }
+    used
 }
 
 pub fn port_validator(port: String) -> Result<(), String> {
@@ -105,6 +138,47 @@ pub fn port_validator(port: String) -> Result<(), String> {
         .map_err(|e| format!("{e:?}"))
 }
 
+/// Range and limit overrides must stay below `MAX_BLOCKS_RANGE_CAP`
+pub fn blocks_range_validator(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(range) if (1..=MAX_BLOCKS_RANGE_CAP).contains(&range) => Ok(()),
+        Ok(_) => Err(format!("must be between 1 and {MAX_BLOCKS_RANGE_CAP}")),
+        Err(err) => Err(format!("{err:?}")),
+    }
+}
+
+pub fn positive_count_validator(value: String) -> Result<(), String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{err:?}")),
+    }
+}
+
+pub fn positive_cost_validator(value: String) -> Result<(), String> {
+    match value.parse::<f64>() {
+        Ok(cost) if cost.is_finite() && cost > 0.0 => Ok(()),
+        Ok(_) => Err("must be a positive number".to_string()),
+        Err(err) => Err(format!("{err:?}")),
+    }
+}
+
+pub fn method_cost_validator(value: String) -> Result<(), String> {
+    parse_method_cost(&value).map(|_| ())
+}
+
+pub fn method_max_age_validator(value: String) -> Result<(), String> {
+    parse_method_max_age(&value).map(|_| ())
+}
+
+pub fn label_validator(value: String) -> Result<(), String> {
+    parse_label(&value).map(|_| ())
+}
+
+pub fn cidr_validator(value: String) -> Result<(), String> {
+    value.parse::<IpCidr>().map(|_| ())
+}
+
 pub fn storage_rpc_service<'a>(version: &'a str, default_args: &'a DefaultStorageRpcArgs) -> App<'a, 'a> {
     return App::new("solana-storage-rpc")
         .about("Solana Storage RPC Service")
@@ -113,6 +187,7 @@ pub fn storage_rpc_service<'a>(version: &'a str, default_args: &'a DefaultStorag
             Arg::with_name("log_path")
                 .short("l")
                 .long("log-path")
+                .env("SVC_LOG_PATH")
                 .value_name("DIR")
                 .takes_value(true)
                 .required(true)
@@ -137,31 +212,53 @@ pub fn storage_rpc_service<'a>(version: &'a str, default_args: &'a DefaultStorag
         .arg(
             Arg::with_name("rpc_port")
                 .long("rpc-port")
+                .env("SVC_RPC_PORT")
                 .value_name("PORT")
                 .takes_value(true)
                 .default_value(&default_args.rpc_port)
                 .validator(port_validator)
-                .help("Port for the RPC service"),
+                .help("Port for the RPC service, 0 lets the OS pick a free port"),
         )
         .arg(
-            Arg::with_name("enable_rpc_mysql_meta_storage")
-                .long("enable-rpc-mysql-meta-storage")
-                .takes_value(false)
-                .hidden(true)
-                .help("Fetch block metadata info from MySQL instance"),
+            Arg::with_name("mysql_dsn")
+                .long("mysql-dsn")
+                .value_name("DSN")
+                .takes_value(true)
+                .help("Full MySQL DSN, overrides SVC_MYSQL_DSN"),
+        )
+        .arg(
+            Arg::with_name("mysql_host")
+                .long("mysql-host")
+                .value_name("HOST")
+                .takes_value(true)
+                .help("MySQL host, overrides SVC_MYSQL_HOST"),
         )
         .arg(
-            Arg::with_name("rpc_mysql_address")
-                .long("rpc-mysql-address")
-                .value_name("ADDRESS")
+            Arg::with_name("mysql_port")
+                .long("mysql-port")
+                .value_name("PORT")
                 .takes_value(true)
-                .hidden(true)
-                .default_value("127.0.0.1:9090")
-                .help("Address of MySQL instance to use"),
+                .validator(port_validator)
+                .help("MySQL port, overrides SVC_MYSQL_PORT"),
+        )
+        .arg(
+            Arg::with_name("mysql_user")
+                .long("mysql-user")
+                .value_name("USER")
+                .takes_value(true)
+                .help("MySQL user, overrides SVC_MYSQL_USER"),
+        )
+        .arg(
+            Arg::with_name("mysql_name")
+                .long("mysql-name")
+                .value_name("DATABASE")
+                .takes_value(true)
+                .help("MySQL database name, overrides SVC_MYSQL_NAME"),
         )
         .arg(
             Arg::with_name("rpc_mysql_timeout")
                 .long("rpc-mysql-timeout")
+                .env("SVC_RPC_MYSQL_TIMEOUT")
                 .value_name("SECONDS")
                 .validator(is_parsable::<u64>)
                 .takes_value(true)
@@ -171,6 +268,7 @@ pub fn storage_rpc_service<'a>(version: &'a str, default_args: &'a DefaultStorag
         .arg(
             Arg::with_name("bind_address")
                 .long("bind-address")
+                .env("SVC_BIND_ADDRESS")
                 .value_name("HOST")
                 .takes_value(true)
                 .validator(solana_net_utils::is_host)
@@ -180,6 +278,7 @@ pub fn storage_rpc_service<'a>(version: &'a str, default_args: &'a DefaultStorag
         .arg(
             Arg::with_name("rpc_threads")
                 .long("rpc-threads")
+                .env("SVC_RPC_THREADS")
                 .value_name("NUMBER")
                 .validator(is_parsable::<usize>)
                 .takes_value(true)
@@ -189,6 +288,7 @@ pub fn storage_rpc_service<'a>(version: &'a str, default_args: &'a DefaultStorag
         .arg(
             Arg::with_name("rpc_niceness_adj")
                 .long("rpc-niceness-adjustment")
+                .env("SVC_RPC_NICENESS_ADJUSTMENT")
                 .value_name("ADJUSTMENT")
                 .takes_value(true)
                 .validator(is_niceness_adjustment_valid)
@@ -196,15 +296,474 @@ pub fn storage_rpc_service<'a>(version: &'a str, default_args: &'a DefaultStorag
                 .help("Add this value to niceness of RPC threads. Negative value \
                       increases priority, positive value decreases priority.")
         )
+        .arg(
+            Arg::with_name("storage_threads")
+                .long("storage-threads")
+                .env("SVC_STORAGE_THREADS")
+                .value_name("NUMBER")
+                .validator(is_parsable::<usize>)
+                .takes_value(true)
+                .default_value("0")
+                .help("Number of threads of a dedicated runtime running storage queries, \
+                      so that slow queries cannot starve request handling. \
+                      0 runs them on the RPC threads")
+        )
+        .arg(
+            Arg::with_name("storage_niceness_adj")
+                .long("storage-niceness-adjustment")
+                .env("SVC_STORAGE_NICENESS_ADJUSTMENT")
+                .value_name("ADJUSTMENT")
+                .takes_value(true)
+                .validator(is_niceness_adjustment_valid)
+                .default_value("0")
+                .help("Add this value to niceness of the storage threads started by \
+                      --storage-threads. Negative value increases priority, positive value \
+                      decreases priority.")
+        )
         .arg(
             Arg::with_name("rpc_max_request_body_size")
                 .long("rpc-max-request-body-size")
+                .env("SVC_RPC_MAX_REQUEST_BODY_SIZE")
                 .value_name("BYTES")
                 .takes_value(true)
                 .validator(is_parsable::<usize>)
                 .default_value(&default_args.rpc_max_request_body_size)
                 .help("The maximum request body size accepted by rpc service"),
         )
+        .arg(
+            Arg::with_name("rpc_max_response_size")
+                .long("rpc-max-response-size")
+                .env("SVC_RPC_MAX_RESPONSE_SIZE")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(&default_args.rpc_max_response_size)
+                .help("Responses larger than this are replaced by an error asking the client \
+                      to narrow its request; 0 disables the limit"),
+        )
+        .arg(
+            Arg::with_name("max_in_flight_per_client")
+                .long("max-in-flight-per-client")
+                .env("SVC_MAX_IN_FLIGHT_PER_CLIENT")
+                .value_name("COUNT")
+                .takes_value(true)
+                .validator(positive_count_validator)
+                .help("JSON-RPC calls a client, identified by API key or proxy-reported \
+                      address, may have in flight; unidentified clients share one cap. \
+                      Further calls are answered with a busy error. [default: unlimited]")
+        )
+        .arg(
+            Arg::with_name("client_cost_per_second")
+                .long("client-cost-per-second")
+                .env("SVC_CLIENT_COST_PER_SECOND")
+                .value_name("COST")
+                .takes_value(true)
+                .validator(positive_cost_validator)
+                .help("Cost each client, identified by API key or proxy-reported address, may \
+                      spend per second; unidentified clients share one budget. A call costs its method's weight, times the number of \
+                      started 100 slot blocks for range methods. [default: unthrottled]")
+        )
+        .arg(
+            Arg::with_name("client_cost_burst")
+                .long("client-cost-burst")
+                .env("SVC_CLIENT_COST_BURST")
+                .value_name("COST")
+                .takes_value(true)
+                .requires("client_cost_per_second")
+                .validator(positive_cost_validator)
+                .help("Cost a client may spend at once after being idle \
+                      [default: --client-cost-per-second]")
+        )
+        .arg(
+            Arg::with_name("method_costs")
+                .long("method-costs")
+                .env("SVC_METHOD_COSTS")
+                .value_name("METHOD=COST")
+                .takes_value(true)
+                .use_delimiter(true)
+                .requires("client_cost_per_second")
+                .validator(method_cost_validator)
+                .help("Comma separated method weights, e.g. getBlockSummary=2,getBlocks=1. \
+                      Methods not listed weigh 1.")
+        )
+        .arg(
+            Arg::with_name("storage_latency_buckets_ms")
+                .long("storage-latency-buckets-ms")
+                .env("SVC_STORAGE_LATENCY_BUCKETS_MS")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .use_delimiter(true)
+                .validator(is_parsable::<u64>)
+                .help("Comma separated upper bounds of the latency buckets storage call metrics \
+                      are tagged with [default: 1,2,5,10,25,50,100,250,500,1000,2500,5000,10000,30000]")
+        )
+        .arg(
+            Arg::with_name("max_get_blocks_range")
+                .long("max-get-blocks-range")
+                .env("SVC_MAX_GET_BLOCKS_RANGE")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .validator(blocks_range_validator)
+                .default_value(&default_args.max_get_blocks_range)
+                .help("Widest slot range accepted by getBlocks")
+        )
+        .arg(
+            Arg::with_name("max_get_blocks_with_limit")
+                .long("max-get-blocks-with-limit")
+                .env("SVC_MAX_GET_BLOCKS_WITH_LIMIT")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .validator(blocks_range_validator)
+                .default_value(&default_args.max_get_blocks_range)
+                .help("Largest limit accepted by getBlocksWithLimit")
+        )
+        .arg(
+            Arg::with_name("rpc_negative_cache_ttl")
+                .long("rpc-negative-cache-ttl")
+                .env("SVC_RPC_NEGATIVE_CACHE_TTL")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value(&default_args.rpc_negative_cache_ttl)
+                .help("Number of seconds missing blocks are remembered before storage is queried \
+                      again. 0 disables the cache.")
+        )
+        .arg(
+            Arg::with_name("rpc_negative_cache_capacity")
+                .long("rpc-negative-cache-capacity")
+                .env("SVC_RPC_NEGATIVE_CACHE_CAPACITY")
+                .value_name("ENTRIES")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(&default_args.rpc_negative_cache_capacity)
+                .help("Maximum number of missing blocks remembered")
+        )
+        .arg(
+            Arg::with_name("rest_max_age")
+                .long("rest-max-age")
+                .env("SVC_REST_MAX_AGE")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value(&default_args.rest_max_age)
+                .help("Cache-Control max-age advertised for REST resources that may still change")
+        )
+        .arg(
+            Arg::with_name("rpc_cache_max_age")
+                .long("rpc-cache-max-age")
+                .env("SVC_RPC_CACHE_MAX_AGE")
+                .value_name("METHOD=SECONDS")
+                .takes_value(true)
+                .use_delimiter(true)
+                .validator(method_max_age_validator)
+                .help("Comma separated Cache-Control max-ages of successful JSON-RPC \
+                      responses, e.g. getBlockTime=60,getBlocks=5, for proxies caching \
+                      POST requests by body. Responses of other methods are not marked \
+                      cacheable.")
+        )
+        .arg(
+            Arg::with_name("cors_allowed_origins")
+                .long("cors-allowed-origins")
+                .env("SVC_CORS_ALLOWED_ORIGINS")
+                .value_name("ORIGINS")
+                .takes_value(true)
+                .use_delimiter(true)
+                .help("Comma separated origins allowed to call the JSON-RPC endpoint from a \
+                      browser [default: any origin]")
+        )
+        .arg(
+            Arg::with_name("cors_allowed_headers")
+                .long("cors-allowed-headers")
+                .env("SVC_CORS_ALLOWED_HEADERS")
+                .value_name("HEADERS")
+                .takes_value(true)
+                .use_delimiter(true)
+                .help("Comma separated request headers allowed in cross-origin calls besides \
+                      the CORS-safelisted ones [default: any header]")
+        )
+        .arg(
+            Arg::with_name("cors_max_age")
+                .long("cors-max-age")
+                .env("SVC_CORS_MAX_AGE")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u32>)
+                .default_value(&default_args.cors_max_age)
+                .help("How long browsers may cache CORS preflight responses")
+        )
+        .arg(
+            Arg::with_name("disable_cors")
+                .long("disable-cors")
+                .takes_value(false)
+                .conflicts_with_all(&["cors_allowed_origins", "cors_allowed_headers"])
+                .help("Send no CORS headers, for deployments only reached by backend clients")
+        )
+        .arg(
+            Arg::with_name("retention_slots")
+                .long("retention-slots")
+                .env("SVC_RETENTION_SLOTS")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Purge block metadata more than SLOTS behind the latest stored slot")
+        )
+        .arg(
+            Arg::with_name("retention_age")
+                .long("retention-age")
+                .env("SVC_RETENTION_AGE")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Purge block metadata whose block time is older than SECONDS")
+        )
+        .arg(
+            Arg::with_name("retention_batch_size")
+                .long("retention-batch-size")
+                .env("SVC_RETENTION_BATCH_SIZE")
+                .value_name("ROWS")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(&default_args.retention_batch_size)
+                .help("Number of rows deleted per purge statement")
+        )
+        .arg(
+            Arg::with_name("retention_batch_interval_ms")
+                .long("retention-batch-interval-ms")
+                .env("SVC_RETENTION_BATCH_INTERVAL_MS")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value(&default_args.retention_batch_interval_ms)
+                .help("Pause between purge statements")
+        )
+        .arg(
+            Arg::with_name("retention_check_interval")
+                .long("retention-check-interval")
+                .env("SVC_RETENTION_CHECK_INTERVAL")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value(&default_args.retention_check_interval)
+                .help("How often the retention boundary is recomputed and purged up to")
+        )
+        .arg(
+            Arg::with_name("health_max_slots_behind")
+                .long("health-max-slots-behind")
+                .env("SVC_HEALTH_MAX_SLOTS_BEHIND")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Report getHealth as behind once the latest stored slot trails the \
+                      cluster by more than SLOTS")
+        )
+        .arg(
+            Arg::with_name("health_reference_rpc_url")
+                .long("health-reference-rpc-url")
+                .env("SVC_HEALTH_REFERENCE_RPC_URL")
+                .value_name("URL")
+                .takes_value(true)
+                .help("RPC endpoint, e.g. a known validator, providing the cluster slot. \
+                      The lag behind it is reported as a metric and checked against \
+                      --health-max-slots-behind, which otherwise estimates the cluster slot \
+                      from the latest block time")
+        )
+        .arg(
+            Arg::with_name("health_slot_duration_ms")
+                .long("health-slot-duration-ms")
+                .env("SVC_HEALTH_SLOT_DURATION_MS")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .requires("health_max_slots_behind")
+                .help("Expected slot duration used to estimate the cluster slot [default: 400]")
+        )
+        .arg(
+            Arg::with_name("require_min_slot")
+                .long("require-min-slot")
+                .env("SVC_REQUIRE_MIN_SLOT")
+                .value_name("SLOT")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Only report ready on /ready and getHealth once storage reaches back \
+                      to SLOT")
+        )
+        .arg(
+            Arg::with_name("require_max_staleness")
+                .long("require-max-staleness")
+                .env("SVC_REQUIRE_MAX_STALENESS")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Only report ready on /ready and getHealth while the latest stored \
+                      block is at most SECONDS old")
+        )
+        .arg(
+            Arg::with_name("archive_s3_bucket")
+                .long("archive-s3-bucket")
+                .env("SVC_ARCHIVE_S3_BUCKET")
+                .value_name("BUCKET")
+                .takes_value(true)
+                .help("Archive purged block metadata as Parquet files to this S3 bucket and \
+                      serve historical getBlockTime requests from it. Credentials are read \
+                      from the AWS_* environment variables. Requires a build with the \
+                      `archive` feature.")
+        )
+        .arg(
+            Arg::with_name("archive_s3_prefix")
+                .long("archive-s3-prefix")
+                .env("SVC_ARCHIVE_S3_PREFIX")
+                .value_name("PREFIX")
+                .takes_value(true)
+                .default_value(&default_args.archive_s3_prefix)
+                .help("Key prefix of the archive files inside the S3 bucket")
+        )
+        .arg(
+            Arg::with_name("feature_flags_file")
+                .long("feature-flags-file")
+                .env("SVC_FEATURE_FLAGS_FILE")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("JSON file of per-method feature flags, re-read when it changes. \
+                      Methods can be disabled or served to a percentage of calls.")
+        )
+        .arg(
+            Arg::with_name("enable_scan_prefetch")
+                .long("enable-scan-prefetch")
+                .takes_value(false)
+                .help("Detect clients walking getBlocks over consecutive ranges, identified \
+                      by API key or address, and prefetch their next range")
+        )
+        .arg(
+            Arg::with_name("cache_warm_slots")
+                .long("cache-warm-slots")
+                .env("SVC_CACHE_WARM_SLOTS")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .validator(positive_count_validator)
+                .help("Preload the block times of the most recent SLOTS slots into memory \
+                      at startup and keep them current, so queries at the tip of the chain \
+                      are served without querying MySQL [default: disabled]")
+        )
+        .arg(
+            Arg::with_name("slot_index_max_slots")
+                .long("slot-index-max-slots")
+                .env("SVC_SLOT_INDEX_MAX_SLOTS")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .validator(positive_count_validator)
+                .help("Keep a compressed in-memory index of the stored slots among the most \
+                      recent SLOTS, answering getBlocks and getBlockCount within it without \
+                      querying MySQL [default: disabled]")
+        )
+        .arg(
+            Arg::with_name("enable_block_time_millis")
+                .long("enable-block-time-millis")
+                .takes_value(false)
+                .help("Add blockTimeMs, the block time in milliseconds, to getBlockSummary responses. \
+                      getBlockTime keeps returning seconds.")
+        )
+        .arg(
+            Arg::with_name("enable_trace_exemplars")
+                .long("enable-trace-exemplars")
+                .takes_value(false)
+                .help("Report the storage latency of requests carrying a sampled W3C \
+                      traceparent header in block-meta-rpc-storage-exemplar, with the trace ID, \
+                      so latency spikes can be followed to their traces")
+        )
+        .arg(
+            Arg::with_name("enable_rpc_slot_headers")
+                .long("enable-rpc-slot-headers")
+                .takes_value(false)
+                .help("Add the X-First-Available-Slot, X-Latest-Slot and X-Block-Height \
+                      headers to JSON-RPC responses. REST responses always carry them.")
+        )
+        .arg(
+            Arg::with_name("reported_version")
+                .long("reported-version")
+                .env("SVC_REPORTED_VERSION")
+                .value_name("VERSION")
+                .takes_value(true)
+                .help("Core version reported by getVersion instead of this build's, for \
+                      clients that detect supported methods by version, e.g. 1.18.22")
+        )
+        .arg(
+            Arg::with_name("reported_feature_set")
+                .long("reported-feature-set")
+                .env("SVC_REPORTED_FEATURE_SET")
+                .value_name("FEATURE_SET")
+                .takes_value(true)
+                .validator(is_parsable::<u32>)
+                .help("Feature set reported by getVersion instead of this build's")
+        )
+        .arg(
+            Arg::with_name("service_instance_id")
+                .long("service-instance-id")
+                .env("SVC_SERVICE_INSTANCE_ID")
+                .value_name("ID")
+                .takes_value(true)
+                .help("Name of this instance, added to every log record, reported as the \
+                      host_id of every metric and served on /status")
+        )
+        .arg(
+            Arg::with_name("labels")
+                .long("labels")
+                .env("SVC_LABELS")
+                .value_name("KEY=VALUE")
+                .takes_value(true)
+                .use_delimiter(true)
+                .validator(label_validator)
+                .help("Comma separated labels of this instance, e.g. region=eu,pool=archive, \
+                      added to every log record and served on /status")
+        )
+        .arg(
+            Arg::with_name("api_keys")
+                .long("api-keys")
+                .env("SVC_API_KEYS")
+                .value_name("KEYS")
+                .takes_value(true)
+                .use_delimiter(true)
+                .hide_env_values(true)
+                .help("Comma separated API keys issued to clients. Clients sending one of \
+                      them in X-Api-Key are throttled and capped on it; any other key is \
+                      ignored. Prefer the environment variable, as arguments are visible \
+                      to other users of the host.")
+        )
+        .arg(
+            Arg::with_name("trusted_proxies")
+                .long("trusted-proxies")
+                .env("SVC_TRUSTED_PROXIES")
+                .value_name("CIDRS")
+                .takes_value(true)
+                .use_delimiter(true)
+                .validator(cidr_validator)
+                .help("Comma separated networks of the proxies in front of the service, \
+                      e.g. 10.0.0.0/8,fd00::/8. Clients without a known API key are keyed on the \
+                      nearest address outside them in Forwarded, X-Forwarded-For or \
+                      X-Real-IP. By default those headers are not believed and such clients \
+                      share one key.")
+        )
+        .arg(
+            Arg::with_name("strict_params")
+                .long("strict-params")
+                .takes_value(false)
+                .help("Reject calls carrying config fields or extra parameters the method \
+                      does not support, naming them. By default they are ignored, counted \
+                      and logged once per method.")
+        )
+        .arg(
+            Arg::with_name("restart_listener")
+                .long("restart-listener")
+                .takes_value(false)
+                .help("Start a new JSON-RPC listener when the service thread dies. \
+                      Either way the death is logged and reported as unhealthy.")
+        )
+        .arg(
+            Arg::with_name("enable_admin_rpc")
+                .long("enable-admin-rpc")
+                .takes_value(false)
+                .help("Expose the admin RPC methods, which modify storage. \
+                      Only enable on ports that are not publicly reachable.")
+        )
         .arg(
             Arg::with_name("log_messages_bytes_limit")
                 .long("log-messages-bytes-limit")
@@ -213,6 +772,13 @@ pub fn storage_rpc_service<'a>(version: &'a str, default_args: &'a DefaultStorag
                 .takes_value(true)
                 .help("Maximum number of bytes written to the program log before truncation")
         )
+        .args(
+            &deprecated_arguments()
+                .into_iter()
+                .map(|deprecated| deprecated.arg.hidden(true))
+                .collect::<Vec<_>>(),
+        )
+        .subcommand(crate::query::query_subcommand())
     ;
 }
 
@@ -222,6 +788,16 @@ pub struct DefaultStorageRpcArgs {
     pub rpc_threads: String,
     pub rpc_niceness_adjustment: String,
     pub rpc_max_request_body_size: String,
+    pub rpc_max_response_size: String,
+    pub max_get_blocks_range: String,
+    pub rpc_negative_cache_ttl: String,
+    pub rpc_negative_cache_capacity: String,
+    pub rest_max_age: String,
+    pub cors_max_age: String,
+    pub retention_batch_size: String,
+    pub retention_batch_interval_ms: String,
+    pub retention_check_interval: String,
+    pub archive_s3_prefix: String,
     pub enable_rpc_mysql_meta_storage: bool,
 }
 
@@ -233,6 +809,16 @@ impl DefaultStorageRpcArgs {
             rpc_threads: num_cpus::get().to_string(),
             rpc_niceness_adjustment: "0".to_string(),
             rpc_max_request_body_size: MAX_REQUEST_BODY_SIZE.to_string(),
+            rpc_max_response_size: MAX_RESPONSE_SIZE.to_string(),
+            max_get_blocks_range: MAX_GET_CONFIRMED_BLOCKS_RANGE.to_string(),
+            rpc_negative_cache_ttl: "10".to_string(),
+            rpc_negative_cache_capacity: "100000".to_string(),
+            rest_max_age: "1".to_string(),
+            cors_max_age: DEFAULT_CORS_MAX_AGE.to_string(),
+            retention_batch_size: "1000".to_string(),
+            retention_batch_interval_ms: "100".to_string(),
+            retention_check_interval: "60".to_string(),
+            archive_s3_prefix: "block-meta".to_string(),
             enable_rpc_mysql_meta_storage: true,
         }
     }
@@ -0,0 +1,246 @@
+//! Client address of a request that went through reverse proxies, read from the
+//! `Forwarded`, `X-Forwarded-For` and `X-Real-IP` headers
+
+use {
+    jsonrpc_http_server::hyper::HeaderMap,
+    std::{
+        net::{IpAddr, Ipv4Addr},
+        str::FromStr,
+    },
+};
+
+pub const FORWARDED_HEADER: &str = "forwarded";
+pub const X_FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+pub const X_REAL_IP_HEADER: &str = "x-real-ip";
+
+/// IPv4 or IPv6 network, e.g. `10.0.0.0/8` or `fd00::/8`; a bare address is a network
+/// of one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpCidr {
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        let (network, addr, bits) = match (self.network, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                (u32::from(network) as u128, u32::from(addr) as u128, 32)
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => (u128::from(network), u128::from(addr), 128),
+            _ => return false,
+        };
+        (network ^ addr)
+            .checked_shr(bits - self.prefix as u32)
+            .unwrap_or(0)
+            == 0
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (network, prefix) = match s.trim().split_once('/') {
+            Some((network, prefix)) => (network, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let network = network
+            .parse::<IpAddr>()
+            .map_err(|_| format!("invalid network address: {network}"))?;
+        let bits = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= bits)
+                .ok_or_else(|| format!("invalid prefix length: {prefix}"))?,
+            None => bits,
+        };
+        Ok(Self { network, prefix })
+    }
+}
+
+/// Proxies whose report of the previous hop is believed. Without any, the first address
+/// of the forwarding headers is taken as is, as a client may have set it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    networks: Vec<IpCidr>,
+}
+
+impl TrustedProxies {
+    pub fn new(networks: Vec<IpCidr>) -> Self {
+        Self { networks }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.networks.is_empty()
+    }
+
+    fn is_trusted(&self, hop: &str) -> bool {
+        parse_hop(hop).is_some_and(|addr| {
+            self.networks.iter().any(|network| network.contains(addr))
+        })
+    }
+
+    /// Address of the client, skipping trusted proxies from the nearest hop backwards.
+    /// The service is assumed to be reachable only through the nearest proxy.
+    pub fn client_ip(&self, headers: &HeaderMap) -> Option<String> {
+        let hops = forwarding_chain(headers);
+        let client = if self.is_empty() {
+            hops.first()
+        } else {
+            hops.iter()
+                .rev()
+                .find(|hop| !self.is_trusted(hop))
+                .or_else(|| hops.first())
+        }?;
+        Some(
+            parse_hop(client)
+                .map(|addr| addr.to_string())
+                .unwrap_or_else(|| client.clone()),
+        )
+    }
+}
+
+/// Addresses a request was forwarded for, client first, from the first of `Forwarded`,
+/// `X-Forwarded-For` and `X-Real-IP` present
+fn forwarding_chain(headers: &HeaderMap) -> Vec<String> {
+    let values = |name: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let forwarded = values(FORWARDED_HEADER)
+        .iter()
+        .filter_map(|element| forwarded_for(element))
+        .collect::<Vec<_>>();
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+    let forwarded_for = values(X_FORWARDED_FOR_HEADER);
+    if !forwarded_for.is_empty() {
+        return forwarded_for;
+    }
+    values(X_REAL_IP_HEADER).into_iter().take(1).collect()
+}
+
+/// `for` parameter of a `Forwarded` element, e.g. `for="[2001:db8::1]:4711";proto=https`
+fn forwarded_for(element: &str) -> Option<String> {
+    element.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case("for")
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Address of a hop, without port or brackets; IPv4-mapped IPv6 addresses are reported
+/// as IPv4. `None` for obfuscated or unknown hops.
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    let hop = hop.trim().trim_matches('"');
+    let addr = if let Some(bracketed) = hop.strip_prefix('[') {
+        bracketed.split_once(']')?.0.parse::<IpAddr>().ok()?
+    } else if let Ok(addr) = hop.parse::<IpAddr>() {
+        addr
+    } else {
+        IpAddr::V4(hop.rsplit_once(':')?.0.parse::<Ipv4Addr>().ok()?)
+    };
+    Some(match addr {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+        addr => addr,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, jsonrpc_http_server::hyper::header::HeaderValue};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_ip_cidr() {
+        let cidr = "10.0.0.0/8".parse::<IpCidr>().unwrap();
+        assert!(cidr.contains("10.1.2.3".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+        assert!(!cidr.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+        let cidr = "fd00::/8".parse::<IpCidr>().unwrap();
+        assert!(cidr.contains("fd12::1".parse().unwrap()));
+        assert!(!cidr.contains("fe80::1".parse().unwrap()));
+
+        let any = "::/0".parse::<IpCidr>().unwrap();
+        assert!(any.contains("2001:db8::1".parse().unwrap()));
+        let single = "192.0.2.1".parse::<IpCidr>().unwrap();
+        assert!(single.contains("192.0.2.1".parse().unwrap()));
+        assert!(!single.contains("192.0.2.2".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("10.0.0/8".parse::<IpCidr>().is_err());
+    }
+
+    #[test]
+    fn test_parse_hop() {
+        let hop = |hop| parse_hop(hop).map(|addr| addr.to_string());
+        assert_eq!(hop("192.0.2.60"), Some("192.0.2.60".to_string()));
+        assert_eq!(hop("192.0.2.60:4711"), Some("192.0.2.60".to_string()));
+        assert_eq!(
+            hop("\"[2001:db8::1]:4711\""),
+            Some("2001:db8::1".to_string())
+        );
+        assert_eq!(hop("2001:db8::1"), Some("2001:db8::1".to_string()));
+        assert_eq!(hop("::ffff:192.0.2.60"), Some("192.0.2.60".to_string()));
+        assert_eq!(hop("_hidden"), None);
+        assert_eq!(hop("unknown"), None);
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let untrusted = TrustedProxies::default();
+        let trusted = TrustedProxies::new(vec!["10.0.0.0/8".parse().unwrap()]);
+
+        let chain = headers(&[(
+            X_FORWARDED_FOR_HEADER,
+            "203.0.113.7, 198.51.100.2, 10.0.0.5",
+        )]);
+        assert_eq!(untrusted.client_ip(&chain), Some("203.0.113.7".to_string()));
+        assert_eq!(trusted.client_ip(&chain), Some("198.51.100.2".to_string()));
+
+        let all_trusted = headers(&[
+            (X_FORWARDED_FOR_HEADER, "10.0.0.9"),
+            (X_FORWARDED_FOR_HEADER, "10.0.0.5"),
+        ]);
+        assert_eq!(
+            trusted.client_ip(&all_trusted),
+            Some("10.0.0.9".to_string())
+        );
+
+        let forwarded = headers(&[
+            (
+                FORWARDED_HEADER,
+                "for=\"[2001:db8::1]:4711\";proto=https, for=10.0.0.5",
+            ),
+            (X_FORWARDED_FOR_HEADER, "198.51.100.2"),
+        ]);
+        assert_eq!(
+            trusted.client_ip(&forwarded),
+            Some("2001:db8::1".to_string())
+        );
+
+        let real_ip = headers(&[(X_REAL_IP_HEADER, "203.0.113.7")]);
+        assert_eq!(trusted.client_ip(&real_ip), Some("203.0.113.7".to_string()));
+        assert_eq!(trusted.client_ip(&HeaderMap::new()), None);
+    }
+}
@@ -0,0 +1,203 @@
+use {
+    crate::{
+        custom_error::RpcCustomError, middleware::ANONYMOUS_CLIENT_KEY,
+        request_processor::JsonRpcRequestProcessor,
+    },
+    jsonrpc_core::{
+        futures::{
+            future::{self, Either},
+            FutureExt,
+        },
+        middleware::{Middleware, NoopFuture},
+        BoxFuture, Call, Failure, Output,
+    },
+    std::{
+        collections::HashMap,
+        future::Future,
+        sync::{Arc, Mutex},
+    },
+};
+
+/// Requests in flight per client, bounded so that one client issuing many parallel
+/// scans cannot hold every storage connection
+#[derive(Clone)]
+pub struct ClientConcurrency {
+    max_in_flight: usize,
+    in_flight: Arc<Mutex<HashMap<Arc<str>, usize>>>,
+}
+
+/// A request slot of a client, released when dropped
+pub struct ClientPermit {
+    in_flight: Arc<Mutex<HashMap<Arc<str>, usize>>>,
+    client_key: Arc<str>,
+}
+
+impl Drop for ClientPermit {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(&self.client_key) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(&self.client_key);
+            }
+        }
+    }
+}
+
+impl ClientConcurrency {
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            max_in_flight,
+            in_flight: Arc::default(),
+        }
+    }
+
+    /// Take a request slot of `client_key`, `None` if all of them are in use
+    pub fn try_acquire(&self, client_key: &Arc<str>) -> Option<ClientPermit> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let count = in_flight.entry(client_key.clone()).or_default();
+        if *count >= self.max_in_flight {
+            return None;
+        }
+        *count += 1;
+        Some(ClientPermit {
+            in_flight: self.in_flight.clone(),
+            client_key: client_key.clone(),
+        })
+    }
+}
+
+/// JSON-RPC middleware answering calls beyond a client's concurrency cap with a busy
+/// error. Clients are identified by an issued API key or proxy-reported address;
+/// requests carrying neither, including those with an unknown key, share one cap.
+#[derive(Clone, Default)]
+pub struct ConcurrencyLimitMiddleware {
+    concurrency: Option<ClientConcurrency>,
+}
+
+impl ConcurrencyLimitMiddleware {
+    pub fn new(max_in_flight_per_client: Option<usize>) -> Self {
+        Self {
+            concurrency: max_in_flight_per_client.map(ClientConcurrency::new),
+        }
+    }
+}
+
+impl Middleware<JsonRpcRequestProcessor> for ConcurrencyLimitMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = BoxFuture<Option<Output>>;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let concurrency = match &self.concurrency {
+            Some(concurrency) => concurrency,
+            None => return Either::Right(next(call, meta)),
+        };
+        let client_key = meta
+            .client_key()
+            .cloned()
+            .unwrap_or_else(|| Arc::from(ANONYMOUS_CLIENT_KEY));
+        match concurrency.try_acquire(&client_key) {
+            Some(permit) => Either::Left(
+                next(call, meta)
+                    .map(move |output| {
+                        drop(permit);
+                        output
+                    })
+                    .boxed(),
+            ),
+            None => {
+                debug!("{} exceeds its concurrent request cap", client_key);
+                let failure = match call {
+                    Call::MethodCall(method_call) => Some(Output::Failure(Failure {
+                        jsonrpc: method_call.jsonrpc,
+                        error: RpcCustomError::ClientBusy {
+                            max_in_flight: concurrency.max_in_flight,
+                        }
+                        .into(),
+                        id: method_call.id,
+                    })),
+                    // Notifications and invalid calls are not answered
+                    _ => None,
+                };
+                Either::Left(future::ready(failure).boxed())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            client_ip::TrustedProxies,
+            custom_error::JSON_RPC_CLIENT_BUSY,
+            middleware::{client_key, ApiKeys, API_KEY_HEADER},
+            request_processor::JsonRpcConfig,
+        },
+        jsonrpc_core::{MetaIoHandler, Value},
+        jsonrpc_http_server::hyper,
+        solana_sdk::exit::Exit,
+        std::sync::RwLock,
+    };
+
+    #[test]
+    fn test_client_concurrency() {
+        let concurrency = ClientConcurrency::new(2);
+        let alice: Arc<str> = Arc::from("key:alice");
+        let bob: Arc<str> = Arc::from("key:bob");
+
+        let first = concurrency.try_acquire(&alice).unwrap();
+        let _second = concurrency.try_acquire(&alice).unwrap();
+        assert!(concurrency.try_acquire(&alice).is_none());
+        assert!(concurrency.try_acquire(&bob).is_some());
+        drop(first);
+        assert!(concurrency.try_acquire(&alice).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_api_keys_share_a_cap() {
+        let mut io = MetaIoHandler::with_middleware(ConcurrencyLimitMiddleware::new(Some(1)));
+        io.add_method_with_meta("hang", |_, _: JsonRpcRequestProcessor| future::pending());
+        io.add_method_with_meta("ping", |_, _: JsonRpcRequestProcessor| {
+            future::ready(Ok(Value::Null))
+        });
+        let io = Arc::new(io);
+        let processor = JsonRpcRequestProcessor::new(
+            JsonRpcConfig::default(),
+            Arc::new(RwLock::new(Exit::default())),
+            None,
+        );
+        let api_keys = ApiKeys::new(vec!["alice".to_string()]);
+        let meta = |api_key: &'static str| {
+            let mut headers = hyper::HeaderMap::new();
+            headers.insert(API_KEY_HEADER, hyper::header::HeaderValue::from_static(api_key));
+            processor
+                .clone()
+                .with_client_key(client_key(&headers, &api_keys, &TrustedProxies::default()))
+        };
+        let request = |method| format!(r#"{{"jsonrpc":"2.0","id":1,"method":"{method}"}}"#);
+
+        let held = tokio::spawn({
+            let io = io.clone();
+            let (request, meta) = (request("hang"), meta("made-up-1"));
+            async move { io.handle_request(&request, meta).await }
+        });
+        tokio::task::yield_now().await;
+        // A fresh unknown key lands on the same anonymous cap
+        let response = io.handle_request(&request("ping"), meta("made-up-2")).await.unwrap();
+        assert!(response.contains(&JSON_RPC_CLIENT_BUSY.to_string()), "{response}");
+        let response = io.handle_request(&request("ping"), meta("alice")).await.unwrap();
+        assert!(response.contains(r#""result":null"#), "{response}");
+        held.abort();
+    }
+}
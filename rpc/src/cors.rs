@@ -0,0 +1,60 @@
+use jsonrpc_http_server::{
+    cors::AccessControlAllowHeaders, AccessControlAllowOrigin, DomainsValidation,
+};
+
+/// Preflight responses are cached by browsers for a day unless configured otherwise
+pub const DEFAULT_CORS_MAX_AGE: u32 = 86400;
+
+/// Cross-origin access to the JSON-RPC endpoint. The server answers preflight requests
+/// itself, allowing the `POST` and `OPTIONS` methods.
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Send no CORS headers, for deployments only reached by backend clients
+    pub disabled: bool,
+    /// Origins allowed to call the endpoint; any origin when empty
+    pub allowed_origins: Vec<String>,
+    /// Request headers allowed besides the CORS-safelisted ones; any header when unset
+    pub allowed_headers: Option<Vec<String>>,
+    /// How long browsers may cache a preflight response, in seconds
+    pub max_age: u32,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            disabled: false,
+            allowed_origins: vec![],
+            allowed_headers: None,
+            max_age: DEFAULT_CORS_MAX_AGE,
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn domains_validation(&self) -> DomainsValidation<AccessControlAllowOrigin> {
+        if self.disabled {
+            return DomainsValidation::Disabled;
+        }
+        if self.allowed_origins.is_empty() {
+            return DomainsValidation::AllowOnly(vec![AccessControlAllowOrigin::Any]);
+        }
+        DomainsValidation::AllowOnly(
+            self.allowed_origins
+                .iter()
+                .map(|origin| AccessControlAllowOrigin::from(origin.as_str()))
+                .collect(),
+        )
+    }
+
+    pub fn allow_headers(&self) -> AccessControlAllowHeaders {
+        match &self.allowed_headers {
+            Some(headers) => AccessControlAllowHeaders::Only(headers.clone()),
+            None => AccessControlAllowHeaders::Any,
+        }
+    }
+
+    /// Preflight max-age, omitted when CORS is disabled
+    pub fn max_age(&self) -> Option<u32> {
+        (!self.disabled).then_some(self.max_age)
+    }
+}
@@ -1,12 +1,24 @@
 use {
+    crate::health::HealthLag,
     jsonrpc_core::{Error, ErrorCode},
     solana_sdk::clock::Slot,
     thiserror::Error,
 };
 
+pub const JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY: i64 = -32005;
 pub const JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED: i64 = -32009;
 pub const JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED: i64 = -32016;
 pub const JSON_RPC_MYSQL_ERROR: i64 = -32017;
+pub const JSON_RPC_STORAGE_TIMEOUT: i64 = -32018;
+pub const JSON_RPC_UNSUPPORTED_BY_SCHEMA: i64 = -32019;
+pub const JSON_RPC_STORAGE_UNAVAILABLE: i64 = -32020;
+pub const JSON_RPC_NO_BLOCKS_STORED: i64 = -32021;
+pub const JSON_RPC_RESPONSE_TOO_LARGE: i64 = -32022;
+pub const JSON_RPC_SCHEMA_DRIFT: i64 = -32023;
+pub const JSON_RPC_CLIENT_BUSY: i64 = -32024;
+pub const JSON_RPC_THROTTLED: i64 = -32025;
+pub const JSON_RPC_STORAGE_BUSY: i64 = -32026;
+pub const JSON_RPC_RANGE_TOO_LARGE: i64 = -32027;
 
 #[derive(Error, Debug)]
 pub enum RpcCustomError {
@@ -15,7 +27,41 @@ pub enum RpcCustomError {
     #[error("MinContextSlotNotReached")]
     MinContextSlotNotReached { context_slot: Slot },
     #[error("MySQLError")]
-    MySQLError { message: String }
+    MySQLError { message: String },
+    #[error("StorageTimeout")]
+    StorageTimeout,
+    #[error("UnsupportedBySchema")]
+    UnsupportedBySchema { feature: &'static str },
+    #[error("StorageUnavailable")]
+    StorageUnavailable,
+    #[error("StorageBusy")]
+    StorageBusy,
+    #[error("NoBlocksStored")]
+    NoBlocksStored,
+    #[error("ResponseTooLarge")]
+    ResponseTooLarge { max_response_size: usize },
+    #[error("SchemaDrift")]
+    SchemaDrift { message: String },
+    #[error("NodeNotReady")]
+    NodeNotReady { reason: String },
+    #[error("NodeUnhealthy")]
+    NodeUnhealthy { lag: Option<HealthLag> },
+    #[error("ClientBusy")]
+    ClientBusy { max_in_flight: usize },
+    #[error("Throttled")]
+    Throttled { retry_after_ms: u64 },
+    #[error("RangeTooLarge")]
+    RangeTooLarge { max_rows: usize },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeUnhealthyErrorData {
+    pub num_slots_behind: Option<Slot>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest_slot: Option<Slot>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_slot: Option<Slot>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,6 +70,30 @@ pub struct MinContextSlotNotReachedErrorData {
     pub context_slot: Slot,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseTooLargeErrorData {
+    pub max_response_size: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeTooLargeErrorData {
+    pub max_rows: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientBusyErrorData {
+    pub max_in_flight: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottledErrorData {
+    pub retry_after_ms: u64,
+}
+
 impl From<RpcCustomError> for Error {
     fn from(e: RpcCustomError) -> Self {
         match e {
@@ -44,6 +114,277 @@ impl From<RpcCustomError> for Error {
                 message,
                 data: None,
             },
+            RpcCustomError::StorageTimeout => Self {
+                code: ErrorCode::ServerError(JSON_RPC_STORAGE_TIMEOUT),
+                message: "Storage request timed out".to_string(),
+                data: None,
+            },
+            RpcCustomError::UnsupportedBySchema { feature } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_UNSUPPORTED_BY_SCHEMA),
+                message: format!("{feature} are not available in this storage schema"),
+                data: None,
+            },
+            RpcCustomError::StorageUnavailable => Self {
+                code: ErrorCode::ServerError(JSON_RPC_STORAGE_UNAVAILABLE),
+                message: "Block metadata storage is not available".to_string(),
+                data: None,
+            },
+            RpcCustomError::StorageBusy => Self {
+                code: ErrorCode::ServerError(JSON_RPC_STORAGE_BUSY),
+                message: "Block metadata storage is busy; retry shortly".to_string(),
+                data: None,
+            },
+            RpcCustomError::NoBlocksStored => Self {
+                code: ErrorCode::ServerError(JSON_RPC_NO_BLOCKS_STORED),
+                message: "No blocks are stored".to_string(),
+                data: None,
+            },
+            RpcCustomError::ResponseTooLarge { max_response_size } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_RESPONSE_TOO_LARGE),
+                message: format!(
+                    "Response exceeds the maximum size of {max_response_size} bytes; \
+                     request a narrower range"
+                ),
+                data: Some(serde_json::json!(ResponseTooLargeErrorData {
+                    max_response_size,
+                })),
+            },
+            RpcCustomError::SchemaDrift { message } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SCHEMA_DRIFT),
+                message: format!("Storage schema changed while serving the request: {message}"),
+                data: None,
+            },
+            RpcCustomError::NodeNotReady { reason } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY),
+                message: format!("Node is not ready: {reason}"),
+                data: None,
+            },
+            RpcCustomError::NodeUnhealthy { lag } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY),
+                message: match lag {
+                    Some(lag) => format!("Node is behind by {} slots", lag.slots_behind),
+                    None => "Node is unhealthy".to_string(),
+                },
+                data: Some(serde_json::json!(NodeUnhealthyErrorData {
+                    num_slots_behind: lag.map(|lag| lag.slots_behind),
+                    latest_slot: lag.map(|lag| lag.latest_slot),
+                    reference_slot: lag.map(|lag| lag.reference_slot),
+                })),
+            },
+            RpcCustomError::ClientBusy { max_in_flight } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_CLIENT_BUSY),
+                message: format!(
+                    "Too many concurrent requests; at most {max_in_flight} may be in flight \
+                     per client"
+                ),
+                data: Some(serde_json::json!(ClientBusyErrorData { max_in_flight })),
+            },
+            RpcCustomError::Throttled { retry_after_ms } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_THROTTLED),
+                message: format!(
+                    "Request cost exceeds the client's budget; retry in {retry_after_ms} ms"
+                ),
+                data: Some(serde_json::json!(ThrottledErrorData { retry_after_ms })),
+            },
+            RpcCustomError::RangeTooLarge { max_rows } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_RANGE_TOO_LARGE),
+                message: format!(
+                    "Range too large: the query matched more than {max_rows} rows; \
+                     request a narrower range"
+                ),
+                data: Some(serde_json::json!(RangeTooLargeErrorData { max_rows })),
+            },
+        }
+    }
+}
+
+/// Class of a JSON-RPC error in metrics, so alerts can page on backend failures and
+/// leave client mistakes out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    InvalidParams,
+    NotFound,
+    Throttled,
+    StorageTimeout,
+    StorageUnavailable,
+    Internal,
+}
+
+impl ErrorClass {
+    pub fn of(code: &ErrorCode) -> Self {
+        match code {
+            ErrorCode::ParseError
+            | ErrorCode::InvalidRequest
+            | ErrorCode::MethodNotFound
+            | ErrorCode::InvalidParams => Self::InvalidParams,
+            ErrorCode::ServerError(code) => match *code {
+                JSON_RPC_RESPONSE_TOO_LARGE | JSON_RPC_RANGE_TOO_LARGE => Self::InvalidParams,
+                JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED
+                | JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED
+                | JSON_RPC_NO_BLOCKS_STORED
+                | JSON_RPC_UNSUPPORTED_BY_SCHEMA => Self::NotFound,
+                JSON_RPC_THROTTLED | JSON_RPC_CLIENT_BUSY => Self::Throttled,
+                JSON_RPC_STORAGE_TIMEOUT => Self::StorageTimeout,
+                JSON_RPC_STORAGE_UNAVAILABLE
+                | JSON_RPC_STORAGE_BUSY
+                | JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY => Self::StorageUnavailable,
+                _ => Self::Internal,
+            },
+            ErrorCode::InternalError => Self::Internal,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidParams => "invalid_params",
+            Self::NotFound => "not_found",
+            Self::Throttled => "throttled",
+            Self::StorageTimeout => "storage_timeout",
+            Self::StorageUnavailable => "storage_unavailable",
+            Self::Internal => "internal",
+        }
+    }
+
+    /// Whether the error comes from the service or its storage rather than the request
+    pub fn is_backend(&self) -> bool {
+        matches!(
+            self,
+            Self::StorageTimeout | Self::StorageUnavailable | Self::Internal
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::Value};
+
+    #[test]
+    fn test_error_class() {
+        let class = |error: RpcCustomError| ErrorClass::of(&Error::from(error).code);
+        assert_eq!(
+            ErrorClass::of(&ErrorCode::InvalidParams),
+            ErrorClass::InvalidParams
+        );
+        assert_eq!(
+            class(RpcCustomError::LongTermStorageSlotSkipped { slot: 1 }),
+            ErrorClass::NotFound
+        );
+        assert_eq!(
+            class(RpcCustomError::Throttled { retry_after_ms: 1 }),
+            ErrorClass::Throttled
+        );
+        assert_eq!(
+            class(RpcCustomError::StorageTimeout),
+            ErrorClass::StorageTimeout
+        );
+        assert_eq!(
+            class(RpcCustomError::StorageBusy),
+            ErrorClass::StorageUnavailable
+        );
+        assert_eq!(
+            class(RpcCustomError::SchemaDrift {
+                message: String::new()
+            }),
+            ErrorClass::Internal
+        );
+        assert_eq!(
+            class(RpcCustomError::RangeTooLarge { max_rows: 10 }),
+            ErrorClass::InvalidParams
+        );
+        assert!(!ErrorClass::NotFound.is_backend());
+        assert!(ErrorClass::Internal.is_backend());
+    }
+
+    const GOLDEN_ERRORS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/rpc_errors.json");
+
+    /// Clients match on these codes, messages and data fields. After an intended change,
+    /// rewrite the file with `UPDATE_GOLDEN=1 cargo test`.
+    #[test]
+    fn test_golden_errors() {
+        let errors = [
+            (
+                "LongTermStorageSlotSkipped",
+                RpcCustomError::LongTermStorageSlotSkipped { slot: 42 },
+            ),
+            (
+                "MinContextSlotNotReached",
+                RpcCustomError::MinContextSlotNotReached { context_slot: 42 },
+            ),
+            (
+                "MySQLError",
+                RpcCustomError::MySQLError {
+                    message: "connection refused".to_string(),
+                },
+            ),
+            ("StorageTimeout", RpcCustomError::StorageTimeout),
+            (
+                "UnsupportedBySchema",
+                RpcCustomError::UnsupportedBySchema { feature: "Rewards" },
+            ),
+            ("StorageUnavailable", RpcCustomError::StorageUnavailable),
+            ("StorageBusy", RpcCustomError::StorageBusy),
+            ("NoBlocksStored", RpcCustomError::NoBlocksStored),
+            (
+                "ResponseTooLarge",
+                RpcCustomError::ResponseTooLarge {
+                    max_response_size: 1024,
+                },
+            ),
+            (
+                "SchemaDrift",
+                RpcCustomError::SchemaDrift {
+                    message: "column dropped".to_string(),
+                },
+            ),
+            (
+                "NodeNotReady",
+                RpcCustomError::NodeNotReady {
+                    reason: "no blocks stored".to_string(),
+                },
+            ),
+            (
+                "NodeUnhealthy",
+                RpcCustomError::NodeUnhealthy {
+                    lag: Some(HealthLag::new(90, 100)),
+                },
+            ),
+            (
+                "NodeUnhealthyWithoutLag",
+                RpcCustomError::NodeUnhealthy { lag: None },
+            ),
+            (
+                "ClientBusy",
+                RpcCustomError::ClientBusy { max_in_flight: 4 },
+            ),
+            (
+                "Throttled",
+                RpcCustomError::Throttled {
+                    retry_after_ms: 250,
+                },
+            ),
+            (
+                "RangeTooLarge",
+                RpcCustomError::RangeTooLarge { max_rows: 100_000 },
+            ),
+        ];
+        let actual: serde_json::Map<String, Value> = errors
+            .into_iter()
+            .map(|(name, err)| {
+                (
+                    name.to_string(),
+                    serde_json::to_value(Error::from(err)).unwrap(),
+                )
+            })
+            .collect();
+        let actual = Value::Object(actual);
+
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            let golden = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+            std::fs::write(GOLDEN_ERRORS, golden).unwrap();
+            return;
         }
+        let golden: Value =
+            serde_json::from_str(&std::fs::read_to_string(GOLDEN_ERRORS).unwrap()).unwrap();
+        assert_eq!(actual, golden);
     }
 }
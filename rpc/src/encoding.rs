@@ -0,0 +1,199 @@
+use {
+    flate2::read::GzDecoder,
+    serde_json::Value,
+    std::{borrow::Cow, io::Read},
+};
+
+pub const CONTENT_TYPE_JSON: &str = "application/json";
+pub const CONTENT_TYPE_MSGPACK: &str = "application/msgpack";
+pub const CONTENT_TYPE_CBOR: &str = "application/cbor";
+
+/// Wire encoding of a response body, negotiated per request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseEncoding {
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+impl ResponseEncoding {
+    /// Pick the encoding from an `encoding=` query parameter, falling back to the
+    /// `Accept` header. Anything unrecognized is answered with JSON.
+    pub fn negotiate(accept: Option<&str>, query: Option<&str>) -> Self {
+        let from_query = query.and_then(|query| {
+            query
+                .split('&')
+                .filter_map(|param| param.split_once('='))
+                .find(|(key, _)| *key == "encoding")
+                .and_then(|(_, value)| Self::from_name(value))
+        });
+        if let Some(encoding) = from_query {
+            return encoding;
+        }
+
+        accept
+            .into_iter()
+            .flat_map(|accept| accept.split(','))
+            .map(|media_type| media_type.split(';').next().unwrap_or_default().trim())
+            .find_map(|media_type| match media_type {
+                CONTENT_TYPE_MSGPACK | "application/x-msgpack" => Some(Self::MessagePack),
+                CONTENT_TYPE_CBOR => Some(Self::Cbor),
+                CONTENT_TYPE_JSON => Some(Self::Json),
+                _ => None,
+            })
+            .unwrap_or(Self::Json)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "msgpack" | "messagepack" => Some(Self::MessagePack),
+            "cbor" => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => CONTENT_TYPE_JSON,
+            Self::MessagePack => CONTENT_TYPE_MSGPACK,
+            Self::Cbor => CONTENT_TYPE_CBOR,
+        }
+    }
+
+    /// Re-encode a serialized JSON document
+    pub fn encode_json(&self, json: &str) -> Result<Vec<u8>, String> {
+        if *self == Self::Json {
+            return Ok(json.as_bytes().to_vec());
+        }
+        let value: Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        self.encode(&value)
+    }
+
+    pub fn encode(&self, value: &Value) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Json => serde_json::to_vec(value).map_err(|err| err.to_string()),
+            Self::MessagePack => rmp_serde::to_vec_named(value).map_err(|err| err.to_string()),
+            Self::Cbor => {
+                let mut bytes = vec![];
+                ciborium::ser::into_writer(value, &mut bytes).map_err(|err| err.to_string())?;
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+/// Compression of a request body, from its `Content-Encoding` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestEncoding {
+    Identity,
+    Gzip,
+}
+
+/// Why a request body could not be decoded
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The decoded body exceeds the allowed size
+    TooLarge,
+    Invalid(String),
+}
+
+impl RequestEncoding {
+    /// `None` for encodings the service does not accept
+    pub fn from_header(content_encoding: Option<&str>) -> Option<Self> {
+        match content_encoding.map(|value| value.trim().to_ascii_lowercase()) {
+            None => Some(Self::Identity),
+            Some(value) => match value.as_str() {
+                "" | "identity" => Some(Self::Identity),
+                "gzip" | "x-gzip" => Some(Self::Gzip),
+                _ => None,
+            },
+        }
+    }
+
+    /// Decode `body`, reading at most `limit` decoded bytes so that a small compressed
+    /// body cannot expand without bound
+    pub fn decode<'a>(&self, body: &'a [u8], limit: usize) -> Result<Cow<'a, [u8]>, DecodeError> {
+        let decoded = match self {
+            Self::Identity => Cow::Borrowed(body),
+            Self::Gzip => {
+                let mut decoded = vec![];
+                GzDecoder::new(body)
+                    .take(limit as u64 + 1)
+                    .read_to_end(&mut decoded)
+                    .map_err(|err| DecodeError::Invalid(err.to_string()))?;
+                Cow::Owned(decoded)
+            }
+        };
+        if decoded.len() > limit {
+            return Err(DecodeError::TooLarge);
+        }
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        flate2::{write::GzEncoder, Compression},
+        std::io::Write,
+    };
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(ResponseEncoding::negotiate(None, None), ResponseEncoding::Json);
+        assert_eq!(
+            ResponseEncoding::negotiate(Some("application/msgpack"), None),
+            ResponseEncoding::MessagePack
+        );
+        assert_eq!(
+            ResponseEncoding::negotiate(Some("text/html, application/cbor;q=0.9"), None),
+            ResponseEncoding::Cbor
+        );
+        assert_eq!(
+            ResponseEncoding::negotiate(Some("application/msgpack"), Some("encoding=json")),
+            ResponseEncoding::Json
+        );
+        assert_eq!(
+            ResponseEncoding::negotiate(None, Some("foo=bar&encoding=cbor")),
+            ResponseEncoding::Cbor
+        );
+    }
+
+    #[test]
+    fn test_encode_json() {
+        let json = r#"{"jsonrpc":"2.0","result":[1,2,3],"id":1}"#;
+        let msgpack = ResponseEncoding::MessagePack.encode_json(json).unwrap();
+        let decoded: Value = rmp_serde::from_slice(&msgpack).unwrap();
+        assert_eq!(decoded, serde_json::from_str::<Value>(json).unwrap());
+
+        let cbor = ResponseEncoding::Cbor.encode_json(json).unwrap();
+        let decoded: Value = ciborium::de::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(decoded, serde_json::from_str::<Value>(json).unwrap());
+    }
+
+    #[test]
+    fn test_decode_request() {
+        assert_eq!(RequestEncoding::from_header(None), Some(RequestEncoding::Identity));
+        assert_eq!(RequestEncoding::from_header(Some("GZIP")), Some(RequestEncoding::Gzip));
+        assert_eq!(RequestEncoding::from_header(Some("br")), None);
+
+        let body = br#"{"jsonrpc":"2.0","method":"getSlot","id":1}"#;
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(body).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(
+            RequestEncoding::Gzip.decode(&compressed, body.len()).unwrap(),
+            &body[..]
+        );
+        assert_eq!(
+            RequestEncoding::Gzip.decode(&compressed, body.len() - 1),
+            Err(DecodeError::TooLarge)
+        );
+        assert!(matches!(
+            RequestEncoding::Gzip.decode(body, 1024),
+            Err(DecodeError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,65 @@
+use {
+    crate::{custom_error::ErrorClass, request_processor::JsonRpcRequestProcessor},
+    jsonrpc_core::{
+        futures::{future::Either, FutureExt},
+        middleware::{Middleware, NoopFuture},
+        BoxFuture, Call, ErrorCode, Output,
+    },
+    std::future::Future,
+};
+
+/// JSON-RPC middleware reporting every failed call by method and `ErrorClass`,
+/// including calls rejected by the middleware behind it
+#[derive(Clone, Default)]
+pub struct ErrorMetricsMiddleware;
+
+impl Middleware<JsonRpcRequestProcessor> for ErrorMetricsMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = BoxFuture<Option<Output>>;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        if let Call::Notification(_) = call {
+            return Either::Right(next(call, meta));
+        }
+        let method = match &call {
+            Call::MethodCall(method_call) => method_call.method.clone(),
+            _ => String::new(),
+        };
+        Either::Left(
+            next(call, meta)
+                .map(move |output| {
+                    if let Some(Output::Failure(failure)) = &output {
+                        submit_error(&method, &failure.error.code);
+                    }
+                    output
+                })
+                .boxed(),
+        )
+    }
+}
+
+fn submit_error(method: &str, code: &ErrorCode) {
+    let class = ErrorClass::of(code);
+    // Unknown method names come from clients and would make the tag unbounded
+    let method = match code {
+        ErrorCode::MethodNotFound | ErrorCode::InvalidRequest => "unknown",
+        _ if method.is_empty() => "unknown",
+        _ => method,
+    };
+    solana_metrics::datapoint_info!(
+        "block-meta-rpc-error",
+        "method" => method,
+        "class" => class.as_str(),
+        ("code", code.code(), i64),
+        ("backend", class.is_backend(), bool),
+    );
+}
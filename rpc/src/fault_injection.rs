@@ -0,0 +1,158 @@
+//! Artificial storage faults for rehearsing a degraded database, set at runtime through
+//! the `setFaultInjection` admin method. Only built with the `fault-injection` feature.
+
+use {
+    solana_storage_mysql::meta_storage,
+    std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, RwLock,
+        },
+        time::Duration,
+    },
+};
+
+/// Resolution of the error rate; calls fail by position within each window of this size
+const ERROR_RATE_WINDOW: u64 = 10_000;
+
+/// Error returned by calls failed on purpose
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FaultKind {
+    /// A generic backend error, as for a lost connection
+    #[default]
+    Backend,
+    Timeout,
+    Busy,
+    Unavailable,
+}
+
+impl FaultKind {
+    fn error(&self) -> meta_storage::Error {
+        match self {
+            FaultKind::Backend => meta_storage::Error::StorageBackendError(
+                "injected storage fault".to_string().into(),
+            ),
+            FaultKind::Timeout => meta_storage::Error::Timeout,
+            FaultKind::Busy => meta_storage::Error::StorageBusy,
+            FaultKind::Unavailable => meta_storage::Error::StorageUnavailable,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FaultInjectionConfig {
+    /// Delay added before every affected call, counted against the storage timeout
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Share of affected calls that fail, from 0 to 1
+    #[serde(default)]
+    pub error_rate: f64,
+    #[serde(default)]
+    pub error: FaultKind,
+    /// Storage calls to affect, by their method label; every call when unset
+    #[serde(default)]
+    pub methods: Option<Vec<String>>,
+}
+
+impl FaultInjectionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.error_rate) {
+            return Err(format!(
+                "errorRate must be between 0 and 1, got {}",
+                self.error_rate
+            ));
+        }
+        Ok(())
+    }
+
+    fn affects(&self, method: &str) -> bool {
+        self.methods
+            .as_ref()
+            .map_or(true, |methods| methods.iter().any(|m| m == method))
+    }
+}
+
+/// Faults applied to the storage calls of a `StorageRouter`, shared by its clones
+#[derive(Clone, Default)]
+pub struct FaultInjector {
+    config: Arc<RwLock<Option<FaultInjectionConfig>>>,
+    counter: Arc<AtomicU64>,
+}
+
+impl FaultInjector {
+    /// Replace the active faults; `None` turns injection off
+    pub fn set(&self, config: Option<FaultInjectionConfig>) {
+        match &config {
+            Some(config) => warn!("Storage fault injection enabled: {:?}", config),
+            None => warn!("Storage fault injection disabled"),
+        }
+        *self.config.write().unwrap() = config;
+    }
+
+    pub fn get(&self) -> Option<FaultInjectionConfig> {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Delay and possibly fail a call of `method`. Like canary routing, a rolling counter
+    /// instead of a random roll makes the error share exact.
+    pub async fn inject(&self, method: &str) -> meta_storage::Result<()> {
+        let config = match self.get() {
+            Some(config) if config.affects(method) => config,
+            _ => return Ok(()),
+        };
+        if config.latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(config.latency_ms)).await;
+        }
+        let position = self.counter.fetch_add(1, Ordering::Relaxed) % ERROR_RATE_WINDOW;
+        if (position as f64) < config.error_rate * ERROR_RATE_WINDOW as f64 {
+            return Err(config.error.error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fault_injector() {
+        let injector = FaultInjector::default();
+        assert!(injector.inject("getSlot").await.is_ok());
+
+        injector.set(Some(FaultInjectionConfig {
+            error_rate: 0.25,
+            error: FaultKind::Timeout,
+            methods: Some(vec!["getSlot".to_string()]),
+            ..FaultInjectionConfig::default()
+        }));
+        let mut failures = 0;
+        for _ in 0..ERROR_RATE_WINDOW {
+            match injector.inject("getSlot").await {
+                Ok(()) => {}
+                Err(meta_storage::Error::Timeout) => failures += 1,
+                Err(err) => panic!("unexpected error {err}"),
+            }
+            assert!(injector.inject("getBlockTime").await.is_ok());
+        }
+        assert_eq!(failures, ERROR_RATE_WINDOW / 4);
+
+        injector.set(None);
+        assert!(injector.inject("getSlot").await.is_ok());
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = |error_rate| FaultInjectionConfig {
+            error_rate,
+            ..FaultInjectionConfig::default()
+        };
+        assert!(config(0.0).validate().is_ok());
+        assert!(config(1.0).validate().is_ok());
+        assert!(config(1.5).validate().is_err());
+        assert!(config(-0.1).validate().is_err());
+        assert!(config(f64::NAN).validate().is_err());
+    }
+}
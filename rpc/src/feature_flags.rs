@@ -0,0 +1,225 @@
+use {
+    crate::request_processor::JsonRpcRequestProcessor,
+    jsonrpc_core::{
+        futures::{
+            future::{self, Either},
+            FutureExt,
+        },
+        middleware::{Middleware, NoopCallFuture, NoopFuture},
+        Call, Error, Failure, Output,
+    },
+    std::{
+        collections::HashMap,
+        fs,
+        future::Future,
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, RwLock,
+        },
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+/// How often the flags file is checked for changes
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Rollout state of a single method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodFlag {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Share of calls (0-100) let through while `enabled`
+    #[serde(default = "default_percent")]
+    pub percent: u8,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_percent() -> u8 {
+    100
+}
+
+/// Contents of a flags file: `{"methods": {"getFeeStatsRange": {"percent": 10}}}`.
+/// Methods that are not listed are enabled.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct FlagSet {
+    #[serde(default)]
+    pub methods: HashMap<String, MethodFlag>,
+}
+
+/// Source of method feature flags, queried on every call
+pub trait FeatureFlagProvider: Send + Sync {
+    /// The flag of `method`, or `None` when the provider has no opinion
+    fn method_flag(&self, method: &str) -> Option<MethodFlag>;
+}
+
+/// Flags read from a JSON file, reloaded when the file changes
+pub struct FileFeatureFlagProvider {
+    path: PathBuf,
+    state: RwLock<FileState>,
+}
+
+struct FileState {
+    checked_at: Instant,
+    modified: Option<SystemTime>,
+    flags: FlagSet,
+}
+
+impl FileFeatureFlagProvider {
+    pub fn new(path: PathBuf) -> Result<Self, String> {
+        let (modified, flags) = Self::load(&path)?;
+        Ok(Self {
+            path,
+            state: RwLock::new(FileState {
+                checked_at: Instant::now(),
+                modified,
+                flags,
+            }),
+        })
+    }
+
+    fn load(path: &PathBuf) -> Result<(Option<SystemTime>, FlagSet), String> {
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {}: {}", path.display(), err))?;
+        let flags = serde_json::from_str(&contents)
+            .map_err(|err| format!("Invalid feature flags in {}: {}", path.display(), err))?;
+        Ok((modified, flags))
+    }
+
+    /// Reload the file if it changed since the last check. A file that fails to parse
+    /// keeps the previous flags in effect.
+    fn refresh(&self) {
+        if self.state.read().unwrap().checked_at.elapsed() < RELOAD_INTERVAL {
+            return;
+        }
+        let mut state = self.state.write().unwrap();
+        if state.checked_at.elapsed() < RELOAD_INTERVAL {
+            return;
+        }
+        state.checked_at = Instant::now();
+        let modified = fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified == state.modified {
+            return;
+        }
+        match Self::load(&self.path) {
+            Ok((modified, flags)) => {
+                info!("Reloaded feature flags from {}", self.path.display());
+                state.modified = modified;
+                state.flags = flags;
+            }
+            Err(err) => warn!("{}", err),
+        }
+    }
+}
+
+impl FeatureFlagProvider for FileFeatureFlagProvider {
+    fn method_flag(&self, method: &str) -> Option<MethodFlag> {
+        self.refresh();
+        self.state.read().unwrap().flags.methods.get(method).copied()
+    }
+}
+
+/// Evaluates method flags for the dispatcher
+#[derive(Clone)]
+pub struct FeatureFlags {
+    provider: Arc<dyn FeatureFlagProvider>,
+    counter: Arc<AtomicU64>,
+}
+
+impl FeatureFlags {
+    pub fn new(provider: Arc<dyn FeatureFlagProvider>) -> Self {
+        Self {
+            provider,
+            counter: Arc::default(),
+        }
+    }
+
+    /// Whether the next call to `method` may be served. Partial rollouts use a rolling
+    /// counter, so the configured share is exact over every 100 calls.
+    pub fn allows(&self, method: &str) -> bool {
+        match self.provider.method_flag(method) {
+            None => true,
+            Some(MethodFlag { enabled: false, .. }) => false,
+            Some(MethodFlag { percent, .. }) if percent >= 100 => true,
+            Some(MethodFlag { percent, .. }) => {
+                self.counter.fetch_add(1, Ordering::Relaxed) % 100 < percent as u64
+            }
+        }
+    }
+}
+
+/// JSON-RPC middleware rejecting calls to methods that are switched off
+#[derive(Clone, Default)]
+pub struct FeatureFlagMiddleware {
+    flags: Option<FeatureFlags>,
+}
+
+impl FeatureFlagMiddleware {
+    pub fn new(flags: Option<FeatureFlags>) -> Self {
+        Self { flags }
+    }
+}
+
+impl Middleware<JsonRpcRequestProcessor> for FeatureFlagMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = NoopCallFuture;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        if let (Some(flags), Call::MethodCall(method_call)) = (&self.flags, &call) {
+            if !flags.allows(&method_call.method) {
+                debug!("{} rejected by feature flag", method_call.method);
+                return Either::Left(future::ready(Some(Output::Failure(Failure {
+                    jsonrpc: method_call.jsonrpc,
+                    error: Error::method_not_found(),
+                    id: method_call.id.clone(),
+                }))).boxed());
+            }
+        }
+        Either::Right(next(call, meta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticProvider(FlagSet);
+
+    impl FeatureFlagProvider for StaticProvider {
+        fn method_flag(&self, method: &str) -> Option<MethodFlag> {
+            self.0.methods.get(method).copied()
+        }
+    }
+
+    #[test]
+    fn test_feature_flags() {
+        let flag_set: FlagSet = serde_json::from_str(
+            r#"{"methods": {"getSlot": {"enabled": false}, "getBlocks": {"percent": 25}}}"#,
+        )
+        .unwrap();
+        let flags = FeatureFlags::new(Arc::new(StaticProvider(flag_set)));
+
+        assert!(flags.allows("getBlockTime"));
+        assert!(!flags.allows("getSlot"));
+        let allowed = (0..100).filter(|_| flags.allows("getBlocks")).count();
+        assert_eq!(allowed, 25);
+    }
+}
@@ -0,0 +1,245 @@
+use {
+    solana_sdk::clock::{Slot, DEFAULT_MS_PER_SLOT},
+    std::{
+        future::Future,
+        time::{Duration, Instant},
+    },
+    tokio::sync::Mutex,
+};
+
+/// How long a reference slot, or a failure to get one, answers health checks before the
+/// reference RPC is asked again
+pub const REFERENCE_SLOT_TTL: Duration = Duration::from_secs(1);
+
+/// How far storage may trail the cluster before `getHealth` reports the node as behind
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    /// Slots storage may be behind the reference; the check is disabled when unset
+    pub max_slots_behind: Option<u64>,
+    /// RPC endpoint whose `getSlot` is the reference. The reference is otherwise
+    /// estimated from the latest stored block time and `slot_duration`.
+    pub reference_rpc_url: Option<String>,
+    /// Expected time between slots
+    pub slot_duration: Duration,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max_slots_behind: None,
+            reference_rpc_url: None,
+            slot_duration: Duration::from_millis(DEFAULT_MS_PER_SLOT),
+        }
+    }
+}
+
+/// Position of the latest stored slot relative to the cluster
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthLag {
+    pub latest_slot: Slot,
+    pub reference_slot: Slot,
+    pub slots_behind: u64,
+}
+
+impl HealthLag {
+    pub fn new(latest_slot: Slot, reference_slot: Slot) -> Self {
+        Self {
+            latest_slot,
+            reference_slot,
+            slots_behind: reference_slot.saturating_sub(latest_slot),
+        }
+    }
+}
+
+impl HealthConfig {
+    /// Whether the lag is measured, either to enforce a maximum or to report it
+    pub fn is_enabled(&self) -> bool {
+        self.max_slots_behind.is_some() || self.reference_rpc_url.is_some()
+    }
+
+    /// Slot the cluster should have reached by `now_ms`, given that `latest_slot` was
+    /// produced at `latest_block_time_ms`
+    pub fn estimate_slot(&self, latest_slot: Slot, latest_block_time_ms: i64, now_ms: i64) -> Slot {
+        let elapsed_ms = now_ms.saturating_sub(latest_block_time_ms).max(0) as u128;
+        let slot_ms = self.slot_duration.as_millis().max(1);
+        latest_slot.saturating_add((elapsed_ms / slot_ms) as u64)
+    }
+
+    /// Whether `lag` is within the allowed maximum
+    pub fn is_healthy(&self, lag: &HealthLag) -> bool {
+        self.max_slots_behind
+            .map_or(true, |max_slots_behind| lag.slots_behind <= max_slots_behind)
+    }
+}
+
+/// Overall condition of the service, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthState {
+    Ok,
+    /// Serving, with reduced redundancy
+    Degraded,
+    /// Not able to serve current data
+    Unhealthy,
+}
+
+impl HealthState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Degraded => "degraded",
+            Self::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+/// Results of the individual checks a health state is derived from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthChecks {
+    /// The JSON-RPC listener died and nothing is served
+    pub listener_down: bool,
+    pub storage_reachable: bool,
+    /// Storage trails the cluster by more than the allowed maximum, or the lag is unknown
+    /// while a maximum is enforced
+    pub behind: bool,
+    pub replicas_down: usize,
+    /// The stored slot range could not be refreshed and the last known one is served
+    pub stale_stored_range: bool,
+}
+
+impl HealthChecks {
+    /// Unhealthy when the listener is down or storage cannot be queried or is too far
+    /// behind, degraded while a replica is demoted or a stale stored range is served, along
+    /// with the reasons for any state other than ok
+    pub fn assess(&self) -> (HealthState, Vec<String>) {
+        let mut reasons = vec![];
+        let mut state = HealthState::Ok;
+        if self.listener_down {
+            reasons.push("listener down".to_string());
+            state = HealthState::Unhealthy;
+        }
+        if !self.storage_reachable {
+            reasons.push("storage unreachable".to_string());
+            state = HealthState::Unhealthy;
+        }
+        if self.behind {
+            reasons.push("storage behind the cluster".to_string());
+            state = HealthState::Unhealthy;
+        }
+        if self.replicas_down > 0 {
+            reasons.push(format!("{} replica(s) down", self.replicas_down));
+            state = state.max(HealthState::Degraded);
+        }
+        if self.stale_stored_range {
+            reasons.push("stored range stale".to_string());
+            state = state.max(HealthState::Degraded);
+        }
+        (state, reasons)
+    }
+}
+
+/// Last lookup of the reference slot. Health checks arriving while a lookup is in flight
+/// wait for it rather than each asking the reference RPC.
+#[derive(Debug, Default)]
+pub struct ReferenceSlotCache {
+    last: Mutex<Option<(Instant, Option<Slot>)>>,
+}
+
+impl ReferenceSlotCache {
+    /// Return the slot looked up less than `ttl` ago, or look it up again with `fetch`
+    pub async fn get_or_fetch<F>(&self, ttl: Duration, fetch: impl FnOnce() -> F) -> Option<Slot>
+    where
+        F: Future<Output = Option<Slot>>,
+    {
+        let mut last = self.last.lock().await;
+        if let Some((fetched_at, slot)) = *last {
+            if fetched_at.elapsed() < ttl {
+                return slot;
+            }
+        }
+        let slot = fetch().await;
+        *last = Some((Instant::now(), slot));
+        slot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_check() {
+        let config = HealthConfig {
+            max_slots_behind: Some(150),
+            ..HealthConfig::default()
+        };
+        assert_eq!(config.estimate_slot(1_000, 10_000, 50_000), 1_100);
+        assert_eq!(config.estimate_slot(1_000, 10_000, 5_000), 1_000);
+        assert!(config.is_healthy(&HealthLag::new(1_000, 1_150)));
+        assert!(config.is_healthy(&HealthLag::new(1_200, 1_150)));
+        assert_eq!(HealthLag::new(1_200, 1_150).slots_behind, 0);
+        assert!(!config.is_healthy(&HealthLag::new(1_000, 1_151)));
+        assert!(HealthConfig::default().is_healthy(&HealthLag::new(0, 1_000_000)));
+    }
+
+    #[test]
+    fn test_health_state() {
+        let checks = HealthChecks {
+            listener_down: false,
+            storage_reachable: true,
+            behind: false,
+            replicas_down: 0,
+            stale_stored_range: false,
+        };
+        assert_eq!(checks.assess(), (HealthState::Ok, vec![]));
+        let stale = HealthChecks {
+            stale_stored_range: true,
+            ..checks
+        };
+        assert_eq!(
+            stale.assess(),
+            (HealthState::Degraded, vec!["stored range stale".to_string()])
+        );
+        let degraded = HealthChecks {
+            replicas_down: 1,
+            ..checks
+        };
+        assert_eq!(
+            degraded.assess(),
+            (HealthState::Degraded, vec!["1 replica(s) down".to_string()])
+        );
+        let unhealthy = HealthChecks {
+            storage_reachable: false,
+            ..degraded
+        };
+        assert_eq!(unhealthy.assess().0, HealthState::Unhealthy);
+        let listener_down = HealthChecks {
+            listener_down: true,
+            ..checks
+        };
+        assert_eq!(
+            listener_down.assess(),
+            (HealthState::Unhealthy, vec!["listener down".to_string()])
+        );
+        assert_eq!(serde_json::to_value(HealthState::Degraded).unwrap(), json!("degraded"));
+    }
+
+    #[tokio::test]
+    async fn test_reference_slot_cache() {
+        let cache = ReferenceSlotCache::default();
+        let fetches = std::sync::atomic::AtomicUsize::new(0);
+        let fetch = |slot| {
+            fetches.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            async move { slot }
+        };
+        assert_eq!(cache.get_or_fetch(Duration::from_secs(60), || fetch(Some(5))).await, Some(5));
+        assert_eq!(cache.get_or_fetch(Duration::from_secs(60), || fetch(Some(9))).await, Some(5));
+        assert_eq!(fetches.load(std::sync::atomic::Ordering::Relaxed), 1);
+
+        // Failed lookups are kept as well, and replaced once expired
+        assert_eq!(cache.get_or_fetch(Duration::ZERO, || fetch(None)).await, None);
+        assert_eq!(cache.get_or_fetch(Duration::from_secs(60), || fetch(Some(9))).await, None);
+        assert_eq!(cache.get_or_fetch(Duration::ZERO, || fetch(Some(9))).await, Some(9));
+        assert_eq!(fetches.load(std::sync::atomic::Ordering::Relaxed), 3);
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+/// Identity of this instance, telling instances behind one load balancer apart in logs,
+/// metrics and `/status`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceIdentity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl ServiceIdentity {
+    pub fn is_empty(&self) -> bool {
+        self.instance_id.is_none() && self.labels.is_empty()
+    }
+
+    /// `instance=ID key=value ...`, appended to every log record
+    pub fn log_fields(&self) -> String {
+        self.instance_id
+            .iter()
+            .map(|instance_id| format!("instance={instance_id}"))
+            .chain(self.label_pairs())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn label_pairs(&self) -> impl Iterator<Item = String> + '_ {
+        self.labels
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+    }
+
+    /// Report the labels, keyed by the instance id every datapoint is tagged with
+    pub fn submit_metrics(&self) {
+        if self.is_empty() {
+            return;
+        }
+        let labels = self.label_pairs().collect::<Vec<_>>().join(",");
+        solana_metrics::datapoint_info!(
+            "block-meta-rpc-instance",
+            (
+                "instance_id",
+                self.instance_id.clone().unwrap_or_default(),
+                String
+            ),
+            ("labels", labels, String),
+        );
+    }
+}
+
+/// Parse `KEY=VALUE`. Keys are alphanumeric with `_`, `-` and `.`; values may not
+/// contain whitespace, so log records stay splittable.
+pub fn parse_label(value: &str) -> Result<(String, String), String> {
+    let (key, label) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got {value}"))?;
+    let (key, label) = (key.trim(), label.trim());
+    let valid_key = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !valid_key {
+        return Err(format!("invalid label key: {key}"));
+    }
+    if label.is_empty() || label.chars().any(char::is_whitespace) {
+        return Err(format!("invalid value for label {key}: {label}"));
+    }
+    Ok((key.to_string(), label.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(
+            parse_label(" region = eu-west-1 "),
+            Ok(("region".to_string(), "eu-west-1".to_string()))
+        );
+        assert!(parse_label("region").is_err());
+        assert!(parse_label("=eu").is_err());
+        assert!(parse_label("re gion=eu").is_err());
+        assert!(parse_label("region=").is_err());
+        assert!(parse_label("region=eu west").is_err());
+    }
+
+    #[test]
+    fn test_log_fields() {
+        let mut identity = ServiceIdentity::default();
+        assert_eq!(identity.log_fields(), "");
+        identity.labels.insert("zone".to_string(), "b".to_string());
+        identity
+            .labels
+            .insert("region".to_string(), "eu".to_string());
+        assert_eq!(identity.log_fields(), "region=eu zone=b");
+        identity.instance_id = Some("rpc-1".to_string());
+        assert_eq!(identity.log_fields(), "instance=rpc-1 region=eu zone=b");
+    }
+}
@@ -0,0 +1,174 @@
+use {
+    crate::{
+        client_ip::TrustedProxies,
+        middleware::{client_key, ApiKeys},
+    },
+    jsonrpc_http_server::hyper,
+    std::sync::Arc,
+};
+
+/// What is known of a request once its response is ready; the request itself has been
+/// consumed by then
+#[derive(Debug, Clone)]
+pub struct RequestInfo {
+    pub method: hyper::Method,
+    pub path: String,
+    pub client_key: Option<String>,
+}
+
+impl RequestInfo {
+    fn new(
+        request: &hyper::Request<hyper::Body>,
+        api_keys: &ApiKeys,
+        trusted_proxies: &TrustedProxies,
+    ) -> Self {
+        Self {
+            method: request.method().clone(),
+            path: request.uri().path().to_string(),
+            client_key: client_key(request.headers(), api_keys, trusted_proxies),
+        }
+    }
+}
+
+/// Hooks run around every HTTP request answered by the service, e.g. for custom
+/// authentication, header rewriting or auditing
+pub trait Interceptor: Send + Sync {
+    /// Inspect or rewrite the request before it is routed. Returning a response answers
+    /// the request with it instead.
+    fn on_request(
+        &self,
+        _request: &mut hyper::Request<hyper::Body>,
+    ) -> Result<(), hyper::Response<hyper::Body>> {
+        Ok(())
+    }
+
+    /// Inspect or rewrite a successful response
+    fn on_response(&self, _request: &RequestInfo, _response: &mut hyper::Response<hyper::Body>) {}
+
+    /// Inspect or rewrite a response with a client or server error status
+    fn on_error(&self, _request: &RequestInfo, _response: &mut hyper::Response<hyper::Body>) {}
+}
+
+/// Interceptors in the order they see requests; responses visit them in reverse
+#[derive(Clone, Default)]
+pub struct Interceptors {
+    chain: Vec<Arc<dyn Interceptor>>,
+}
+
+impl Interceptors {
+    pub fn push(&mut self, interceptor: impl Interceptor + 'static) {
+        self.chain.push(Arc::new(interceptor));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
+
+    /// Run the request hooks, stopping at the first interceptor that answers the request
+    pub fn on_request(
+        &self,
+        request: &mut hyper::Request<hyper::Body>,
+        api_keys: &ApiKeys,
+        trusted_proxies: &TrustedProxies,
+    ) -> Result<RequestInfo, hyper::Response<hyper::Body>> {
+        for interceptor in &self.chain {
+            interceptor.on_request(request)?;
+        }
+        Ok(RequestInfo::new(request, api_keys, trusted_proxies))
+    }
+
+    pub fn on_response(&self, request: &RequestInfo, response: &mut hyper::Response<hyper::Body>) {
+        let failed = response.status().is_client_error() || response.status().is_server_error();
+        for interceptor in self.chain.iter().rev() {
+            if failed {
+                interceptor.on_error(request, response);
+            } else {
+                interceptor.on_response(request, response);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::Mutex,
+    };
+
+    struct Recorder {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Interceptor for Recorder {
+        fn on_request(
+            &self,
+            request: &mut hyper::Request<hyper::Body>,
+        ) -> Result<(), hyper::Response<hyper::Body>> {
+            self.calls.lock().unwrap().push(format!("{} request", self.name));
+            if request.headers().contains_key("x-deny") {
+                return Err(hyper::Response::builder()
+                    .status(hyper::StatusCode::FORBIDDEN)
+                    .body(hyper::Body::empty())
+                    .unwrap());
+            }
+            Ok(())
+        }
+
+        fn on_response(&self, _request: &RequestInfo, _response: &mut hyper::Response<hyper::Body>) {
+            self.calls.lock().unwrap().push(format!("{} response", self.name));
+        }
+
+        fn on_error(&self, _request: &RequestInfo, _response: &mut hyper::Response<hyper::Body>) {
+            self.calls.lock().unwrap().push(format!("{} error", self.name));
+        }
+    }
+
+    #[test]
+    fn test_interceptor_order() {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let mut interceptors = Interceptors::default();
+        for name in ["auth", "audit"] {
+            interceptors.push(Recorder {
+                name,
+                calls: calls.clone(),
+            });
+        }
+
+        let mut request = hyper::Request::builder()
+            .uri("/slot")
+            .body(hyper::Body::empty())
+            .unwrap();
+        let info = interceptors
+            .on_request(&mut request, &ApiKeys::default(), &TrustedProxies::default())
+            .unwrap();
+        assert_eq!(info.path, "/slot");
+        let mut response = hyper::Response::new(hyper::Body::empty());
+        interceptors.on_response(&info, &mut response);
+        *response.status_mut() = hyper::StatusCode::SERVICE_UNAVAILABLE;
+        interceptors.on_response(&info, &mut response);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "auth request",
+                "audit request",
+                "audit response",
+                "auth response",
+                "audit error",
+                "auth error",
+            ]
+        );
+
+        calls.lock().unwrap().clear();
+        let mut request = hyper::Request::builder()
+            .header("x-deny", "1")
+            .body(hyper::Body::empty())
+            .unwrap();
+        let response = interceptors
+            .on_request(&mut request, &ApiKeys::default(), &TrustedProxies::default())
+            .unwrap_err();
+        assert_eq!(response.status(), hyper::StatusCode::FORBIDDEN);
+        assert_eq!(*calls.lock().unwrap(), vec!["auth request"]);
+    }
+}
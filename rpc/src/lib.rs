@@ -1,10 +1,39 @@
 #![allow(clippy::integer_arithmetic)]
 #![recursion_limit = "2048"]
 
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod build_info;
+pub mod cache;
+pub mod cache_control;
+pub mod client_ip;
+pub mod concurrency;
+pub mod cors;
 pub mod custom_error;
+pub mod encoding;
+pub mod error_metrics;
+#[cfg(feature = "fault-injection")]
+pub mod fault_injection;
+pub mod feature_flags;
+pub mod health;
+pub mod identity;
+pub mod interceptor;
+pub mod listener;
+pub mod params;
+pub mod prefetch;
+pub mod query;
+pub mod readiness;
 
 pub mod request_processor;
+pub mod response_limit;
+pub mod retention;
 pub mod rpc_service;
+pub mod singleflight;
+pub mod slot_index;
+pub mod storage_router;
+#[cfg(any(test, feature = "stub-storage"))]
+pub mod storage_stub;
+pub mod throttle;
 
 pub mod rpc;
 
@@ -16,6 +45,7 @@ pub mod logging;
 pub mod rpc_server;
 
 pub mod middleware;
+pub mod middleware_metrics;
 
 #[macro_use]
 extern crate log;
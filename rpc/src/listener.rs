@@ -0,0 +1,101 @@
+//! Control of the JSON-RPC HTTP listener shared by the service thread, its exit hook and
+//! the `restartRpc` admin method
+
+use {
+    crate::{client_ip::TrustedProxies, cors::CorsConfig, middleware::ApiKeys},
+    jsonrpc_http_server::CloseHandle,
+    std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        thread,
+        time::Duration,
+    },
+};
+
+/// Delay before a requested restart closes the listener, so the response to the request
+/// goes out first
+const RESTART_GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Settings applied each time the listener binds
+#[derive(Debug, Clone, Default)]
+pub struct ListenerConfig {
+    pub cors: CorsConfig,
+    pub api_keys: ApiKeys,
+    pub trusted_proxies: TrustedProxies,
+}
+
+/// Source of fresh listener settings, called when a restart is requested. On error the
+/// listener rebinds with its previous settings.
+pub type ListenerConfigLoader = Arc<dyn Fn() -> Result<ListenerConfig, String> + Send + Sync>;
+
+/// Handle on the running listener; clones share it
+#[derive(Clone, Default)]
+pub struct ListenerControl {
+    /// Replaced whenever the listener is restarted, so exit closes the live one
+    close_handle: Arc<Mutex<Option<CloseHandle>>>,
+    restart_requested: Arc<AtomicBool>,
+}
+
+impl ListenerControl {
+    /// Record the close handle of a newly bound listener
+    pub fn set(&self, close_handle: CloseHandle) {
+        *self.close_handle.lock().unwrap() = Some(close_handle);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.close_handle.lock().unwrap().is_some()
+    }
+
+    /// Close the listener for good
+    pub fn close(&self) {
+        if let Some(close_handle) = self.close_handle.lock().unwrap().take() {
+            close_handle.close();
+        }
+    }
+
+    /// Close the listener after the grace period, for the service thread to bind a new
+    /// one. Returns false when no listener is running. The close runs on its own thread,
+    /// so callers need no runtime.
+    pub fn request_restart(&self) -> bool {
+        if !self.is_running() {
+            return false;
+        }
+        let control = self.clone();
+        let spawned = thread::Builder::new()
+            .name("solRpcRestart".to_string())
+            .spawn(move || {
+                thread::sleep(RESTART_GRACE_PERIOD);
+                let close_handle = control.close_handle.lock().unwrap().clone();
+                if let Some(close_handle) = close_handle {
+                    control.restart_requested.store(true, Ordering::Release);
+                    close_handle.close();
+                }
+            });
+        if let Err(err) = spawned {
+            warn!("Failed to spawn the listener restart thread: {}", err);
+            return false;
+        }
+        true
+    }
+
+    /// Whether the listener stopped because a restart was requested, clearing the request
+    pub fn take_restart_request(&self) -> bool {
+        self.restart_requested.swap(false, Ordering::AcqRel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_without_listener() {
+        let control = ListenerControl::default();
+        assert!(!control.is_running());
+        assert!(!control.request_restart());
+        assert!(!control.take_restart_request());
+        control.close();
+    }
+}
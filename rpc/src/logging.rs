@@ -1,8 +1,9 @@
 use {
+    crate::identity::ServiceIdentity,
     std::{
         env,
         fs::{OpenOptions},
-        process::exit,
+        io::Write,
         thread::JoinHandle,
     },
 };
@@ -23,10 +24,36 @@ fn redirect_stderr(filename: &str) {
     }
 }
 
+// Set up the default logger, with the instance identity appended to the header of every
+// record when one is configured
+fn setup_logger(filter: &str, identity: &ServiceIdentity) {
+    if identity.is_empty() {
+        solana_logger::setup_with_default(filter);
+        return;
+    }
+    let fields = identity.log_fields();
+    env_logger::Builder::from_env(env_logger::Env::new().default_filter_or(filter))
+        .format(move |buf, record| {
+            writeln!(
+                buf,
+                "[{} {} {} {}] {}",
+                buf.timestamp_nanos(),
+                buf.default_styled_level(record.level()),
+                record.module_path().unwrap_or_default(),
+                fields,
+                record.args()
+            )
+        })
+        .init();
+}
+
 // Redirect stderr to a file with support for logrotate by sending a SIGUSR1 to the process.
 //
 // Upon success, future `log` macros and `eprintln!()` can be found in the specified log file.
-pub fn redirect_stderr_to_file(logfile: Option<String>) -> Option<JoinHandle<()>> {
+pub fn redirect_stderr_to_file(
+    logfile: Option<String>,
+    identity: &ServiceIdentity,
+) -> Result<Option<JoinHandle<()>>, String> {
     // Default to RUST_BACKTRACE=1 for more informative launcher logs
     if env::var_os("RUST_BACKTRACE").is_none() {
         env::set_var("RUST_BACKTRACE", "1")
@@ -35,8 +62,8 @@ pub fn redirect_stderr_to_file(logfile: Option<String>) -> Option<JoinHandle<()>
     let filter = "solana=info";
     match logfile {
         None => {
-            solana_logger::setup_with_default(filter);
-            None
+            setup_logger(filter, identity);
+            Ok(None)
         }
         Some(logfile) => {
             #[cfg(unix)]
@@ -44,33 +71,29 @@ pub fn redirect_stderr_to_file(logfile: Option<String>) -> Option<JoinHandle<()>
                 use log::info;
                 let mut signals =
                     signal_hook::iterator::Signals::new([signal_hook::consts::SIGUSR1])
-                        .unwrap_or_else(|err| {
-                            eprintln!("Unable to register SIGUSR1 handler: {err:?}");
-                            exit(1);
-                        });
+                        .map_err(|err| format!("Unable to register SIGUSR1 handler: {err:?}"))?;
 
-                solana_logger::setup_with_default(filter);
+                setup_logger(filter, identity);
                 redirect_stderr(&logfile);
-                Some(
-                    std::thread::Builder::new()
-                        .name("solSigUsr1".into())
-                        .spawn(move || {
-                            for signal in signals.forever() {
-                                info!(
-                                    "received SIGUSR1 ({}), reopening log file: {:?}",
-                                    signal, logfile
-                                );
-                                redirect_stderr(&logfile);
-                            }
-                        })
-                        .unwrap(),
-                )
+                std::thread::Builder::new()
+                    .name("solSigUsr1".into())
+                    .spawn(move || {
+                        for signal in signals.forever() {
+                            info!(
+                                "received SIGUSR1 ({}), reopening log file: {:?}",
+                                signal, logfile
+                            );
+                            redirect_stderr(&logfile);
+                        }
+                    })
+                    .map(Some)
+                    .map_err(|err| format!("Unable to spawn the log reopen thread: {err}"))
             }
             #[cfg(not(unix))]
             {
                 println!("logrotate is not supported on this platform");
                 solana_logger::setup_file_with_default(&logfile, filter);
-                None
+                Ok(None)
             }
         }
     }
@@ -1,16 +1,33 @@
 use {
-    clap::value_t_or_exit,
+    clap::{value_t_or_exit, values_t_or_exit},
     log::*,
     solana_net_utils::parse_host,
     block_meta_rpc::{
+        cache_control::parse_method_max_age,
+        client_ip::{IpCidr, TrustedProxies},
         cli,
-        config::Config,
+        config::{self, Config},
+        cors::CorsConfig,
+        query,
         logging::redirect_stderr_to_file,
+        middleware::ApiKeys,
+        health::HealthConfig,
+        identity::{parse_label, ServiceIdentity},
+        readiness::ReadinessConfig,
+        storage_router::LatencyBuckets,
+        throttle::{parse_method_cost, ThrottleConfig},
         request_processor::JsonRpcConfig,
+        retention::{ArchiveConfig, RetentionConfig},
         rpc_server::RpcServer,
     },
     solana_storage_mysql::{
-        mysql::MySQLConfig,
+        mysql::{
+            parse_index_hints,
+            parse_slot_shards,
+            MySQLConfig,
+            ReconnectGate,
+            ReconnectLimits,
+        },
     },
     solana_version::version,
     std::{
@@ -19,6 +36,7 @@ use {
         process::exit,
         sync::Arc,
         time::{
+            Duration,
             SystemTime,
             UNIX_EPOCH,
         },
@@ -33,10 +51,21 @@ enum Output {
 }
 
 fn main() {
+    // Command line arguments fall back to the SVC_* variables, which fall back to the file
+    config::load_config_file();
+
     let default_args = cli::DefaultStorageRpcArgs::new();
     let version = version!(); // Store version in a variable
     let matches = cli::storage_rpc_service(version, &default_args).get_matches();
 
+    if let ("query", Some(query_matches)) = matches.subcommand() {
+        if let Err(err) = query::run(query_matches) {
+            eprintln!("Error: {err}");
+            exit(1);
+        }
+        return;
+    }
+
     // Decide logging style
     let output = if matches.is_present("quiet") {
         Output::None
@@ -80,14 +109,31 @@ fn main() {
         None
     };
 
+    let identity = ServiceIdentity {
+        instance_id: matches.value_of("service_instance_id").map(str::to_string),
+        labels: matches
+            .values_of("labels")
+            .into_iter()
+            .flatten()
+            .filter_map(|value| parse_label(value).ok())
+            .collect(),
+    };
+    if let Some(instance_id) = &identity.instance_id {
+        solana_metrics::set_host_id(instance_id.clone());
+    }
+
     // Set up the logger
-    let _logger_thread = redirect_stderr_to_file(logfile);
+    let _logger_thread = redirect_stderr_to_file(logfile, &identity).unwrap_or_else(|err| {
+        eprintln!("Error: {err}");
+        exit(1);
+    });
 
     info!("solana-meta-rpc {}", version);
     info!(
         "Starting block metadata rpc service with: {:#?}",
         std::env::args_os()
     );
+    let deprecated_arguments = cli::warn_for_deprecated_arguments(&matches);
 
     // Grab CLI parameters
     let rpc_port = value_t_or_exit!(matches, "rpc_port", u16);
@@ -98,7 +144,53 @@ fn main() {
         })
     });
 
-    let app_config = Arc::new(Config::new());
+    let rpc_mysql_timeout = Duration::from_secs(value_t_or_exit!(matches, "rpc_mysql_timeout", u64));
+
+    let app_config = Arc::new(Config::new(&matches).unwrap_or_else(|err| {
+        eprint!("Error: {err}");
+        exit(1);
+    }));
+
+    let default_reconnect_limits = ReconnectLimits::default();
+    let reconnect_limits = ReconnectLimits {
+        max_concurrent: app_config
+            .mysql_reconnect_concurrency
+            .unwrap_or(default_reconnect_limits.max_concurrent),
+        min_interval: app_config
+            .mysql_reconnect_interval_ms
+            .map(Duration::from_millis)
+            .unwrap_or(default_reconnect_limits.min_interval),
+        ..default_reconnect_limits
+    };
+    // Shared by the primary, replicas, shards and canary, so that they do not reconnect
+    // all at once after an outage
+    let reconnect_gate = Arc::new(ReconnectGate::new(reconnect_limits));
+    let acquire_timeout = app_config.mysql_acquire_timeout_ms.map(Duration::from_millis);
+
+    let shards = parse_slot_shards(&app_config.mysql_shards).unwrap_or_else(|err| {
+        eprintln!("Error: SVC_MYSQL_SHARDS: {err}");
+        exit(1);
+    });
+    let block_time_format = app_config.mysql_block_time_format.parse().unwrap_or_else(|err| {
+        eprintln!("Error: SVC_MYSQL_BLOCK_TIME_FORMAT: {err}");
+        exit(1);
+    });
+    let index_hints = parse_index_hints(&app_config.mysql_index_hints).unwrap_or_else(|err| {
+        eprintln!("Error: SVC_MYSQL_INDEX_HINTS: {err}");
+        exit(1);
+    });
+    let isolation_level = app_config.mysql_isolation_level.as_ref().map(|level| {
+        level.parse().unwrap_or_else(|err| {
+            eprintln!("Error: SVC_MYSQL_ISOLATION_LEVEL: {err}");
+            exit(1);
+        })
+    });
+    let block_partitions = app_config.mysql_block_partitions.as_ref().map(|scheme| {
+        scheme.parse().unwrap_or_else(|err| {
+            eprintln!("Error: SVC_MYSQL_BLOCK_PARTITIONS: {err}");
+            exit(1);
+        })
+    });
 
     // Prepare JSON RPC config
     let rpc_mysql_config = Some(MySQLConfig {
@@ -107,19 +199,214 @@ fn main() {
         username: app_config.mysql_user.clone(),
         password: app_config.mysql_password.clone(),
         db_name: app_config.mysql_name.clone(),
-        timeout: None,
+        timeout: Some(rpc_mysql_timeout),
+        dsn: app_config.mysql_dsn.clone(),
+        prewarm_connections: app_config.mysql_prewarm_connections,
+        reconnect_gate: reconnect_gate.clone(),
+        acquire_timeout,
+        replica_dsns: app_config.mysql_replica_dsns.clone(),
+        hedge_delay: app_config.mysql_hedge_delay_ms.map(Duration::from_millis),
+        max_replica_lag: app_config.mysql_max_replica_lag_secs.map(Duration::from_secs),
+        shards,
+        block_time_format,
+        block_partitions: block_partitions.clone(),
+        index_hints: index_hints.clone(),
+        isolation_level,
+        max_rows: app_config.mysql_max_rows,
+    });
+
+    let canary_configured =
+        app_config.canary_mysql_dsn.is_some() || app_config.canary_mysql_host.is_some();
+    let rpc_mysql_canary_config = canary_configured.then(|| MySQLConfig {
+        host: app_config
+            .canary_mysql_host
+            .clone()
+            .unwrap_or_else(|| app_config.mysql_host.clone()),
+        port: app_config.canary_mysql_port.unwrap_or(app_config.mysql_port),
+        username: app_config
+            .canary_mysql_user
+            .clone()
+            .unwrap_or_else(|| app_config.mysql_user.clone()),
+        password: app_config
+            .canary_mysql_password
+            .clone()
+            .unwrap_or_else(|| app_config.mysql_password.clone()),
+        db_name: app_config
+            .canary_mysql_name
+            .clone()
+            .unwrap_or_else(|| app_config.mysql_name.clone()),
+        timeout: Some(rpc_mysql_timeout),
+        dsn: app_config.canary_mysql_dsn.clone(),
+        prewarm_connections: app_config.mysql_prewarm_connections,
+        reconnect_gate,
+        acquire_timeout,
+        replica_dsns: vec![],
+        hedge_delay: None,
+        max_replica_lag: None,
+        shards: vec![],
+        // The canary serves the same schema
+        block_time_format,
+        block_partitions,
+        index_hints,
+        isolation_level,
+        max_rows: app_config.mysql_max_rows,
     });
 
     let mut rpc_config = JsonRpcConfig::default_for_storage_rpc();
     rpc_config.rpc_mysql_config = rpc_mysql_config;
+    rpc_config.rpc_mysql_canary_config = rpc_mysql_canary_config;
+    rpc_config.canary_traffic_percent = app_config.canary_traffic_percent;
+    rpc_config.request_timeout = Some(rpc_mysql_timeout);
     rpc_config.obsolete_v1_7_api = matches.is_present("obsolete_v1_7_rpc_api");
+    rpc_config.admin_rpc = config::is_enabled(&matches, "enable_admin_rpc");
+    rpc_config.scan_prefetch = config::is_enabled(&matches, "enable_scan_prefetch");
+    rpc_config.block_time_millis = config::is_enabled(&matches, "enable_block_time_millis");
+    rpc_config.strict_params = config::is_enabled(&matches, "strict_params");
+    rpc_config.restart_listener = config::is_enabled(&matches, "restart_listener");
+    rpc_config.trace_exemplars = config::is_enabled(&matches, "enable_trace_exemplars");
+    rpc_config.rpc_slot_headers = config::is_enabled(&matches, "enable_rpc_slot_headers");
     rpc_config.rpc_threads = value_t_or_exit!(matches, "rpc_threads", usize);
     rpc_config.rpc_niceness_adj = value_t_or_exit!(matches, "rpc_niceness_adj", i8);
+    rpc_config.deprecated_arguments = deprecated_arguments;
+    rpc_config.identity = identity;
+    rpc_config.reported_version = matches.value_of("reported_version").map(str::to_string);
+    rpc_config.reported_feature_set = matches
+        .value_of("reported_feature_set")
+        .map(|_| value_t_or_exit!(matches, "reported_feature_set", u32));
+    if matches.is_present("storage_latency_buckets_ms") {
+        rpc_config.storage_latency_buckets = LatencyBuckets::new(
+            values_t_or_exit!(matches, "storage_latency_buckets_ms", u64)
+                .into_iter()
+                .map(Duration::from_millis),
+        );
+    }
+    rpc_config.storage_threads = value_t_or_exit!(matches, "storage_threads", usize);
+    rpc_config.max_blocks_range = Some(value_t_or_exit!(matches, "max_get_blocks_range", u64));
+    rpc_config.max_blocks_limit = Some(value_t_or_exit!(matches, "max_get_blocks_with_limit", usize));
+    rpc_config.storage_niceness_adj = value_t_or_exit!(matches, "storage_niceness_adj", i8);
+    if rpc_config.storage_threads == 0 && rpc_config.storage_niceness_adj != 0 {
+        warn!("--storage-niceness-adjustment has no effect without --storage-threads");
+    }
     rpc_config.max_request_body_size = Some(value_t_or_exit!(
         matches,
         "rpc_max_request_body_size",
         usize
     ));
+    let max_response_size = value_t_or_exit!(matches, "rpc_max_response_size", usize);
+    rpc_config.max_response_size = (max_response_size > 0).then_some(max_response_size);
+    rpc_config.cache_warm_slots = matches
+        .value_of("cache_warm_slots")
+        .map(|_| value_t_or_exit!(matches, "cache_warm_slots", u64));
+    rpc_config.slot_index_max_slots = matches
+        .value_of("slot_index_max_slots")
+        .map(|_| value_t_or_exit!(matches, "slot_index_max_slots", u64));
+    rpc_config.max_in_flight_per_client = matches
+        .value_of("max_in_flight_per_client")
+        .map(|_| value_t_or_exit!(matches, "max_in_flight_per_client", usize));
+    rpc_config.throttle = matches.value_of("client_cost_per_second").map(|_| {
+        let cost_per_second = value_t_or_exit!(matches, "client_cost_per_second", f64);
+        ThrottleConfig {
+            cost_per_second,
+            burst: matches
+                .value_of("client_cost_burst")
+                .map(|_| value_t_or_exit!(matches, "client_cost_burst", f64))
+                .unwrap_or(cost_per_second),
+            method_costs: matches
+                .values_of("method_costs")
+                .into_iter()
+                .flatten()
+                .filter_map(|value| parse_method_cost(value).ok())
+                .collect(),
+        }
+    });
+    let negative_cache_ttl = value_t_or_exit!(matches, "rpc_negative_cache_ttl", u64);
+    rpc_config.negative_cache_ttl = (negative_cache_ttl > 0).then(|| Duration::from_secs(negative_cache_ttl));
+    rpc_config.negative_cache_capacity =
+        value_t_or_exit!(matches, "rpc_negative_cache_capacity", usize);
+    rpc_config.rest_max_age = value_t_or_exit!(matches, "rest_max_age", u64);
+    rpc_config.rpc_cache_max_ages = matches
+        .values_of("rpc_cache_max_age")
+        .into_iter()
+        .flatten()
+        .filter_map(|value| parse_method_max_age(value).ok())
+        .collect();
+    rpc_config.api_keys = ApiKeys::new(
+        matches
+            .values_of("api_keys")
+            .into_iter()
+            .flatten()
+            .map(str::to_string),
+    );
+    rpc_config.trusted_proxies = TrustedProxies::new(
+        matches
+            .values_of("trusted_proxies")
+            .into_iter()
+            .flatten()
+            .filter_map(|value| value.parse::<IpCidr>().ok())
+            .collect(),
+    );
+    rpc_config.cors = CorsConfig {
+        disabled: config::is_enabled(&matches, "disable_cors"),
+        allowed_origins: matches
+            .values_of("cors_allowed_origins")
+            .map(|origins| origins.map(str::to_string).collect())
+            .unwrap_or_default(),
+        allowed_headers: matches
+            .values_of("cors_allowed_headers")
+            .map(|headers| headers.map(str::to_string).collect()),
+        max_age: value_t_or_exit!(matches, "cors_max_age", u32),
+    };
+
+    rpc_config.feature_flags_path = matches.value_of("feature_flags_file").map(PathBuf::from);
+
+    rpc_config.health = HealthConfig {
+        max_slots_behind: matches
+            .value_of("health_max_slots_behind")
+            .map(|_| value_t_or_exit!(matches, "health_max_slots_behind", u64)),
+        reference_rpc_url: matches.value_of("health_reference_rpc_url").map(str::to_string),
+        slot_duration: matches
+            .value_of("health_slot_duration_ms")
+            .map(|_| Duration::from_millis(value_t_or_exit!(matches, "health_slot_duration_ms", u64)))
+            .unwrap_or(HealthConfig::default().slot_duration),
+    };
+
+    rpc_config.readiness = ReadinessConfig {
+        min_slot: matches
+            .value_of("require_min_slot")
+            .map(|_| value_t_or_exit!(matches, "require_min_slot", u64)),
+        max_staleness: matches
+            .value_of("require_max_staleness")
+            .map(|_| Duration::from_secs(value_t_or_exit!(matches, "require_max_staleness", u64))),
+    };
+
+    rpc_config.archive = matches
+        .value_of("archive_s3_bucket")
+        .map(|s3_bucket| ArchiveConfig {
+            s3_bucket: s3_bucket.to_string(),
+            s3_prefix: matches.value_of("archive_s3_prefix").unwrap_or_default().to_string(),
+        });
+
+    let retention_slots = matches
+        .value_of("retention_slots")
+        .map(|_| value_t_or_exit!(matches, "retention_slots", u64));
+    let retention_age = matches
+        .value_of("retention_age")
+        .map(|_| Duration::from_secs(value_t_or_exit!(matches, "retention_age", u64)));
+    if retention_slots.is_some() || retention_age.is_some() {
+        rpc_config.retention = Some(RetentionConfig {
+            slots: retention_slots,
+            max_age: retention_age,
+            batch_size: value_t_or_exit!(matches, "retention_batch_size", usize).max(1),
+            batch_interval: Duration::from_millis(value_t_or_exit!(
+                matches,
+                "retention_batch_interval_ms",
+                u64
+            )),
+            check_interval: Duration::from_secs(
+                value_t_or_exit!(matches, "retention_check_interval", u64).max(1),
+            ),
+        });
+    }
 
     // Build and start the RPC server
     let mut rpc_server = RpcServer::new()
@@ -1,30 +1,241 @@
 use {
+    crate::{
+        cache_control::RpcCachePolicy,
+        client_ip::TrustedProxies,
+        custom_error::JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED,
+        encoding::{DecodeError, RequestEncoding, ResponseEncoding},
+        health::HealthState,
+        interceptor::Interceptors,
+        middleware_metrics::{MiddlewareMetrics, MiddlewareOutcome},
+        request_processor::{JsonRpcRequestProcessor, StoredRange},
+        rpc_service::RpcIoHandler,
+    },
+    jsonrpc_core::futures::future::{BoxFuture, FutureExt},
     jsonrpc_http_server::{
         hyper,
         RequestMiddleware,
         RequestMiddlewareAction,
     },
+    solana_rpc_client_api::config::RpcContextConfig,
+    solana_sdk::clock::Slot,
     std::{
+        collections::HashSet,
+        fmt,
+        future::Future,
         path::{
             PathBuf
         },
+        sync::Arc,
+        time::Duration,
     },
 };
 
+/// Request header carrying the client's timeout budget in milliseconds
+pub const TIMEOUT_BUDGET_HEADER: &str = "x-timeout-ms";
+
+/// Request header carrying the client's API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// API keys issued to clients. Requests are only keyed on an API key found here, so
+/// clients cannot get a fresh budget by making keys up. Never printed.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ApiKeys(HashSet<String>);
+
+impl ApiKeys {
+    pub fn new(keys: impl IntoIterator<Item = String>) -> Self {
+        Self(
+            keys.into_iter()
+                .map(|key| key.trim().to_string())
+                .filter(|key| !key.is_empty())
+                .collect(),
+        )
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.contains(key)
+    }
+}
+
+impl fmt::Debug for ApiKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ApiKeys({} keys)", self.0.len())
+    }
+}
+
+/// W3C trace context header identifying the trace a request belongs to
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Response headers advertising the stored slot range, so proxies can route without
+/// an extra call
+pub const FIRST_AVAILABLE_SLOT_HEADER: &str = "x-first-available-slot";
+pub const LATEST_SLOT_HEADER: &str = "x-latest-slot";
+pub const BLOCK_HEIGHT_HEADER: &str = "x-block-height";
+
+/// Slots this far behind the latest stored slot are treated as settled, so CDNs and
+/// reverse proxies may cache their REST resources for `SETTLED_CACHE_CONTROL`.
+pub const SETTLED_SLOT_DEPTH: Slot = 32;
+
+/// Orphan marking and soft deletes can still change a settled slot, so its resources are
+/// only cached for a bounded time and revalidated against their ETag afterwards
+const SETTLED_CACHE_CONTROL: &str = "public, max-age=300";
+
+/// Number of slots fetched from storage per chunk of a streamed export
+pub const EXPORT_PAGE_SIZE: usize = 10_000;
+
+const CONTENT_TYPE_NDJSON: &str = "application/x-ndjson";
+
+/// Answers the HTTP requests of a registered REST path
+pub trait RestHandler: Send + Sync {
+    /// `request_processor` already carries the request's timeout budget and client key
+    fn handle(
+        &self,
+        request: hyper::Request<hyper::Body>,
+        request_processor: JsonRpcRequestProcessor,
+    ) -> BoxFuture<'static, hyper::Response<hyper::Body>>;
+}
+
+impl<F, Fut> RestHandler for F
+where
+    F: Fn(hyper::Request<hyper::Body>, JsonRpcRequestProcessor) -> Fut + Send + Sync,
+    Fut: Future<Output = hyper::Response<hyper::Body>> + Send + 'static,
+{
+    fn handle(
+        &self,
+        request: hyper::Request<hyper::Body>,
+        request_processor: JsonRpcRequestProcessor,
+    ) -> BoxFuture<'static, hyper::Response<hyper::Body>> {
+        self(request, request_processor).boxed()
+    }
+}
+
+/// REST endpoints registered in addition to the built-in routes
+#[derive(Clone, Default)]
+pub struct RestHandlers {
+    handlers: Vec<(String, Arc<dyn RestHandler>)>,
+}
+
+impl RestHandlers {
+    /// Route `path` to `handler`. A path ending in `/*` also matches every path below
+    /// it. Handlers registered first win when several paths match.
+    pub fn register(&mut self, path: impl Into<String>, handler: impl RestHandler + 'static) {
+        self.handlers.push((path.into(), Arc::new(handler)));
+    }
+
+    fn find(&self, path: &str) -> Option<&Arc<dyn RestHandler>> {
+        self.handlers
+            .iter()
+            .find(|(pattern, _)| rest_path_matches(pattern, path))
+            .map(|(_, handler)| handler)
+    }
+}
+
+fn rest_path_matches(pattern: &str, path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+        None => pattern.trim_end_matches('/') == path,
+    }
+}
+
 pub struct RpcRequestMiddleware {
     // log_path: PathBuf,
+    request_processor: JsonRpcRequestProcessor,
+    rest_max_age: u64,
+    max_request_body_size: usize,
+    /// Handler used to answer JSON-RPC requests that negotiated a binary encoding, and
+    /// every JSON-RPC request when interceptors need to see the response
+    binary_io: Option<Arc<RpcIoHandler>>,
+    /// Handler for the `/v2` namespace
+    v2_io: Option<Arc<RpcIoHandler>>,
+    rest_handlers: RestHandlers,
+    interceptors: Interceptors,
+    /// Max-age advertised on cacheable JSON-RPC responses
+    rpc_cache_policy: Arc<RpcCachePolicy>,
+    metrics: Arc<MiddlewareMetrics>,
+    api_keys: Arc<ApiKeys>,
+    trusted_proxies: Arc<TrustedProxies>,
 }
 
 impl RpcRequestMiddleware {
     pub fn new(
         _log_path: PathBuf,
+        request_processor: JsonRpcRequestProcessor,
+        rest_max_age: u64,
+        max_request_body_size: usize,
     ) -> Self {
         Self {
             // log_path,
+            request_processor,
+            rest_max_age,
+            max_request_body_size,
+            binary_io: None,
+            v2_io: None,
+            rest_handlers: RestHandlers::default(),
+            interceptors: Interceptors::default(),
+            rpc_cache_policy: Arc::default(),
+            metrics: Arc::default(),
+            api_keys: Arc::default(),
+            trusted_proxies: Arc::default(),
         }
     }
 
-    #[allow(dead_code)]
+    /// Serve `path` with `handler`, ahead of the built-in routes
+    pub fn register_handler(&mut self, path: impl Into<String>, handler: impl RestHandler + 'static) {
+        self.rest_handlers.register(path, handler);
+    }
+
+    /// Serve the endpoints registered in `rest_handlers`
+    pub fn with_rest_handlers(mut self, rest_handlers: RestHandlers) -> Self {
+        self.rest_handlers = rest_handlers;
+        self
+    }
+
+    /// Run `interceptors` around every request answered by the service
+    pub fn with_interceptors(mut self, interceptors: Interceptors) -> Self {
+        self.interceptors = interceptors;
+        self
+    }
+
+    /// Answer JSON-RPC requests sent with a MessagePack or CBOR `Accept` header
+    /// through `io`, re-encoding the response body
+    pub fn with_binary_rpc(mut self, io: Arc<RpcIoHandler>) -> Self {
+        self.binary_io = Some(io);
+        self
+    }
+
+    /// Advertise `rpc_cache_policy` on JSON-RPC responses; they are answered through the
+    /// binary handler when it is not empty
+    pub fn with_rpc_cache_policy(mut self, rpc_cache_policy: RpcCachePolicy) -> Self {
+        self.rpc_cache_policy = Arc::new(rpc_cache_policy);
+        self
+    }
+
+    /// Count what becomes of each request in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<MiddlewareMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Key clients presenting one of `api_keys` on it
+    pub fn with_api_keys(mut self, api_keys: ApiKeys) -> Self {
+        self.api_keys = Arc::new(api_keys);
+        self
+    }
+
+    /// Key clients on the address reported past `trusted_proxies`
+    pub fn with_trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = Arc::new(trusted_proxies);
+        self
+    }
+
+    /// Answer JSON-RPC requests posted to `/v2` through `io`
+    pub fn with_v2_rpc(mut self, io: Arc<RpcIoHandler>) -> Self {
+        self.v2_io = Some(io);
+        self
+    }
+
     fn internal_server_error() -> hyper::Response<hyper::Body> {
         hyper::Response::builder()
             .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
@@ -40,32 +251,681 @@ impl RpcRequestMiddleware {
 }
 
 impl RequestMiddleware for RpcRequestMiddleware {
-    fn on_request(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
+    fn on_request(&self, mut request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
         trace!("request uri: {}", request.uri());
 
-        if let Some(result) = process_rest(request.uri().path()) {
-            hyper::Response::builder()
-                .status(hyper::StatusCode::OK)
-                .body(hyper::Body::from(result))
-                .unwrap()
-                .into()
-        } else if request.uri().path() == "/health" {
+        if self.interceptors.is_empty() {
+            return self.route(request);
+        }
+        let info = match self
+            .interceptors
+            .on_request(&mut request, &self.api_keys, &self.trusted_proxies) {
+            Ok(info) => info,
+            Err(response) => {
+                let outcome = MiddlewareOutcome::intercepted(response.status());
+                debug!(
+                    "{} {} answered by an interceptor: {:?}",
+                    request.method(),
+                    request.uri().path(),
+                    outcome
+                );
+                self.metrics.record(outcome);
+                return response.into();
+            }
+        };
+        match self.route(request) {
+            RequestMiddlewareAction::Respond {
+                should_validate_hosts,
+                response,
+            } => {
+                let interceptors = self.interceptors.clone();
+                RequestMiddlewareAction::Respond {
+                    should_validate_hosts,
+                    response: Box::pin(async move {
+                        let mut response = response.await?;
+                        interceptors.on_response(&info, &mut response);
+                        Ok(response)
+                    }),
+                }
+            }
+            // Only requests the HTTP server rejects on its own are left to it
+            action => action,
+        }
+    }
+}
+
+impl RpcRequestMiddleware {
+    fn route(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
+
+        if let Some(handler) = self.rest_handlers.find(request.uri().path()) {
+            self.metrics.record(MiddlewareOutcome::CustomRest);
+            let request_processor = self
+                .request_processor
+                .with_timeout_budget(timeout_budget(request.headers()))
+                .with_client_key(client_key(request.headers(), &self.api_keys, &self.trusted_proxies))
+                .with_trace_id(trace_id(request.headers()));
+            let response = handler.handle(request, request_processor);
+            return RequestMiddlewareAction::Respond {
+                should_validate_hosts: true,
+                response: Box::pin(async move { Ok(response.await) }),
+            };
+        }
+
+        let accept = request
+            .headers()
+            .get(hyper::header::ACCEPT)
+            .and_then(|value| value.to_str().ok());
+
+        if request.method() == hyper::Method::GET {
+            if let Some((start_slot, end_slot)) = parse_export_path(request.uri().path()) {
+                self.metrics.record(MiddlewareOutcome::Export);
+                let request_processor = self
+                    .request_processor
+                    .with_timeout_budget(timeout_budget(request.headers()))
+                    .with_client_key(client_key(request.headers(), &self.api_keys, &self.trusted_proxies))
+                    .with_trace_id(trace_id(request.headers()));
+                return hyper::Response::builder()
+                    .status(hyper::StatusCode::OK)
+                    .header(hyper::header::CONTENT_TYPE, CONTENT_TYPE_NDJSON)
+                    .header(hyper::header::CACHE_CONTROL, "no-cache")
+                    .body(stream_blocks(request_processor, start_slot, end_slot))
+                    .unwrap()
+                    .into();
+            }
+            if let Some(route) = RestRoute::parse(request.uri().path()) {
+                self.metrics.record(MiddlewareOutcome::Rest);
+                let encoding = ResponseEncoding::negotiate(accept, request.uri().query());
+                let if_none_match = request
+                    .headers()
+                    .get(hyper::header::IF_NONE_MATCH)
+                    .and_then(|value| value.to_str().ok())
+                    .map(|value| value.to_string());
+                let request_processor = self
+                    .request_processor
+                    .with_timeout_budget(timeout_budget(request.headers()))
+                    .with_client_key(client_key(request.headers(), &self.api_keys, &self.trusted_proxies))
+                    .with_trace_id(trace_id(request.headers()));
+                let rest_max_age = self.rest_max_age;
+                return RequestMiddlewareAction::Respond {
+                    should_validate_hosts: true,
+                    response: Box::pin(async move {
+                        let mut response = process_rest(
+                            route,
+                            &request_processor,
+                            if_none_match,
+                            rest_max_age,
+                            encoding,
+                        )
+                        .await;
+                        add_slot_headers(response.headers_mut(), request_processor.stored_range());
+                        Ok(response)
+                    }),
+                };
+            }
+        }
+
+        if request.method() == hyper::Method::POST {
+            let encoding = ResponseEncoding::negotiate(accept, None);
+            let content_encoding = request
+                .headers()
+                .get(hyper::header::CONTENT_ENCODING)
+                .map(|value| value.to_str().unwrap_or("unknown"));
+            let request_encoding = match RequestEncoding::from_header(content_encoding) {
+                Some(request_encoding) => request_encoding,
+                None => {
+                    self.metrics.record(MiddlewareOutcome::UnsupportedEncoding);
+                    return hyper::Response::builder()
+                        .status(hyper::StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                        .header(hyper::header::ACCEPT_ENCODING, "gzip, identity")
+                        .body(hyper::Body::from(format!(
+                            "Unsupported Content-Encoding {}; use gzip or identity",
+                            content_encoding.unwrap_or_default()
+                        )))
+                        .unwrap()
+                        .into();
+                }
+            };
+            let slot_headers = self.request_processor.rpc_slot_headers();
+            let io = if request.uri().path().trim_end_matches('/') == "/v2" {
+                self.v2_io.as_ref()
+            } else if encoding != ResponseEncoding::Json
+                || request_encoding != RequestEncoding::Identity
+                || !self.interceptors.is_empty()
+                || slot_headers
+                || !self.rpc_cache_policy.is_empty()
+            {
+                self.binary_io.as_ref()
+            } else {
+                None
+            };
+            if let Some(io) = io {
+                let io = io.clone();
+                let max_request_body_size = self.max_request_body_size;
+                let rpc_cache_policy = self.rpc_cache_policy.clone();
+                let metrics = self.metrics.clone();
+                let meta = self
+                    .request_processor
+                    .with_timeout_budget(timeout_budget(request.headers()))
+                    .with_client_key(client_key(request.headers(), &self.api_keys, &self.trusted_proxies))
+                    .with_trace_id(trace_id(request.headers()));
+                return RequestMiddlewareAction::Respond {
+                    should_validate_hosts: true,
+                    response: Box::pin(async move {
+                        let stored_range = slot_headers.then(|| meta.stored_range()).flatten();
+                        let mut response = process_rpc(
+                            request,
+                            io,
+                            meta,
+                            max_request_body_size,
+                            request_encoding,
+                            encoding,
+                            &rpc_cache_policy,
+                        )
+                        .await;
+                        metrics.record(MiddlewareOutcome::rpc(response.status()));
+                        add_slot_headers(response.headers_mut(), stored_range);
+                        Ok(response)
+                    }),
+                };
+            }
+        }
+
+        if request.uri().path() == "/ready" {
+            self.metrics.record(MiddlewareOutcome::Health);
+            let request_processor = self.request_processor.clone();
+            return RequestMiddlewareAction::Respond {
+                should_validate_hosts: true,
+                response: Box::pin(async move {
+                    let (status, body) = match request_processor.check_readiness().await {
+                        Ok(()) => (hyper::StatusCode::OK, "ok".to_string()),
+                        Err(err) => {
+                            info!("readiness check: {}", err.message);
+                            (hyper::StatusCode::SERVICE_UNAVAILABLE, err.message)
+                        }
+                    };
+                    Ok(hyper::Response::builder()
+                        .status(status)
+                        .header(hyper::header::CACHE_CONTROL, "no-cache")
+                        .body(hyper::Body::from(body))
+                        .unwrap())
+                }),
+            };
+        }
+
+        if request.uri().path() == "/health" {
+            self.metrics.record(MiddlewareOutcome::Health);
             hyper::Response::builder()
                 .status(hyper::StatusCode::OK)
                 .body(hyper::Body::from(self.health_check()))
                 .unwrap()
                 .into()
         } else {
+            self.metrics.record(MiddlewareOutcome::Forwarded);
             request.into()
         }
     }
 }
 
-fn process_rest(path: &str) -> Option<String> {
-    match path {
-        //
-        // Add custom url endpoints here
-        //
-        _ => None,
+/// Parse the client's timeout budget from the request headers
+pub fn timeout_budget(headers: &hyper::HeaderMap) -> Option<Duration> {
+    headers
+        .get(TIMEOUT_BUDGET_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(Duration::from_millis)
+}
+
+/// Client key shared by every request that identifies no client
+pub const ANONYMOUS_CLIENT_KEY: &str = "anonymous";
+
+/// Identify the client of a request by its API key when it is one of `api_keys`,
+/// falling back to its address as reported by the proxies in front of the service.
+/// Forwarding headers are only believed when trusted proxies are configured, as clients
+/// could otherwise rotate them at will. The HTTP server does not hand the peer address
+/// to middleware, so without trusted proxies clients with no known key share one key.
+pub fn client_key(
+    headers: &hyper::HeaderMap,
+    api_keys: &ApiKeys,
+    trusted_proxies: &TrustedProxies,
+) -> Option<String> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| api_keys.contains(value))
+        .map(|api_key| format!("key:{api_key}"))
+        .or_else(|| {
+            if trusted_proxies.is_empty() {
+                return None;
+            }
+            trusted_proxies
+                .client_ip(headers)
+                .map(|address| format!("ip:{address}"))
+        })
+}
+
+/// Trace ID of a request, from a `traceparent` header whose trace is sampled
+pub fn trace_id(headers: &hyper::HeaderMap) -> Option<String> {
+    let traceparent = headers.get(TRACEPARENT_HEADER)?.to_str().ok()?;
+    let mut parts = traceparent.trim().split('-');
+    let (_version, trace_id, _parent_id, flags) =
+        (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    let sampled = u8::from_str_radix(flags, 16).ok()? & 0x01 != 0;
+    let valid = trace_id.len() == 32
+        && trace_id.bytes().all(|byte| byte.is_ascii_hexdigit())
+        && trace_id.bytes().any(|byte| byte != b'0');
+    (sampled && valid).then(|| trace_id.to_ascii_lowercase())
+}
+
+/// Advertise the stored slot range, when known
+fn add_slot_headers(headers: &mut hyper::HeaderMap, stored_range: Option<StoredRange>) {
+    let stored_range = match stored_range {
+        Some(stored_range) => stored_range,
+        None => return,
+    };
+    headers.insert(FIRST_AVAILABLE_SLOT_HEADER, stored_range.first_slot.into());
+    headers.insert(LATEST_SLOT_HEADER, stored_range.latest_slot.into());
+    if let Some(block_height) = stored_range.block_height {
+        headers.insert(BLOCK_HEIGHT_HEADER, block_height.into());
+    }
+}
+
+/// Answer a JSON-RPC request outside the HTTP server's own handler, decoding its body
+/// from `request_encoding` and answering in `encoding`. Responses `rpc_cache_policy`
+/// deems cacheable are marked so for proxies.
+async fn process_rpc(
+    request: hyper::Request<hyper::Body>,
+    io: Arc<RpcIoHandler>,
+    meta: JsonRpcRequestProcessor,
+    max_request_body_size: usize,
+    request_encoding: RequestEncoding,
+    encoding: ResponseEncoding,
+    rpc_cache_policy: &RpcCachePolicy,
+) -> hyper::Response<hyper::Body> {
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) if body.len() > max_request_body_size => {
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::PAYLOAD_TOO_LARGE)
+                .body(hyper::Body::empty())
+                .unwrap();
+        }
+        Ok(body) => body,
+        Err(err) => {
+            debug!("failed to read request body: {}", err);
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::BAD_REQUEST)
+                .body(hyper::Body::empty())
+                .unwrap();
+        }
+    };
+    let body = match request_encoding.decode(&body, max_request_body_size) {
+        Ok(body) => body,
+        Err(DecodeError::TooLarge) => {
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::PAYLOAD_TOO_LARGE)
+                .body(hyper::Body::empty())
+                .unwrap();
+        }
+        Err(DecodeError::Invalid(err)) => {
+            debug!("failed to decode request body: {}", err);
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::BAD_REQUEST)
+                .body(hyper::Body::empty())
+                .unwrap();
+        }
+    };
+    let body = match std::str::from_utf8(&body) {
+        Ok(body) => body,
+        Err(_) => {
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::BAD_REQUEST)
+                .body(hyper::Body::empty())
+                .unwrap();
+        }
+    };
+
+    let response = match io.handle_request(body, meta).await {
+        Some(response) => response,
+        // Notifications produce no response
+        None => {
+            return hyper::Response::builder()
+                .status(hyper::StatusCode::NO_CONTENT)
+                .body(hyper::Body::empty())
+                .unwrap();
+        }
+    };
+    let mut builder = hyper::Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header(hyper::header::CONTENT_TYPE, encoding.content_type())
+        .header(hyper::header::VARY, "Accept");
+    if let Some(max_age) = rpc_cache_policy.max_age(body, &response) {
+        // Generated now, proxies count the age from here
+        builder = builder
+            .header(
+                hyper::header::CACHE_CONTROL,
+                format!("public, max-age={}", max_age.as_secs()),
+            )
+            .header(hyper::header::AGE, "0");
+    }
+    match encoding.encode_json(&response) {
+        Ok(encoded) => builder.body(hyper::Body::from(encoded)).unwrap(),
+        Err(err) => {
+            warn!("failed to encode response as {}: {}", encoding.content_type(), err);
+            RpcRequestMiddleware::internal_server_error()
+        }
+    }
+}
+
+/// `/blocks/{start_slot}/{end_slot}`, an inclusive range of stored slots
+fn parse_export_path(path: &str) -> Option<(Slot, Slot)> {
+    let (start_slot, end_slot) = path
+        .trim_end_matches('/')
+        .strip_prefix("/blocks/")?
+        .split_once('/')?;
+    Some((start_slot.parse().ok()?, end_slot.parse().ok()?))
+}
+
+/// Stream the stored slots in `start_slot..=end_slot` as newline-delimited JSON, one
+/// array of slots per storage page, so that the whole range is never held in memory.
+///
+/// An error after the first chunk can no longer change the status code, so it is
+/// reported as a final `{"error": ...}` line.
+fn stream_blocks(
+    request_processor: JsonRpcRequestProcessor,
+    start_slot: Slot,
+    end_slot: Slot,
+) -> hyper::Body {
+    let (mut sender, body) = hyper::Body::channel();
+    tokio::spawn(async move {
+        let mut next_slot = start_slot;
+        loop {
+            let slots = match request_processor
+                .get_blocks_page(next_slot, end_slot, EXPORT_PAGE_SIZE)
+                .await
+            {
+                Ok(slots) => slots,
+                Err(err) => {
+                    let _ = sender.send_data(ndjson_line(&serde_json::json!({ "error": err }))).await;
+                    return;
+                }
+            };
+            let last_slot = match slots.last() {
+                Some(&last_slot) => last_slot,
+                None => return,
+            };
+            if sender.send_data(ndjson_line(&slots)).await.is_err() {
+                // The client went away
+                return;
+            }
+            if slots.len() < EXPORT_PAGE_SIZE || last_slot >= end_slot {
+                return;
+            }
+            next_slot = last_slot + 1;
+        }
+    });
+    body
+}
+
+fn ndjson_line<T: serde::Serialize>(value: &T) -> hyper::body::Bytes {
+    let mut line = serde_json::to_vec(value).unwrap_or_default();
+    line.push(b'\n');
+    line.into()
+}
+
+/// Read-only REST mirror of the JSON-RPC methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RestRoute {
+    Slot,
+    FirstAvailableBlock,
+    BlockTime(Slot),
+    Retention,
+    Status,
+}
+
+impl RestRoute {
+    fn parse(path: &str) -> Option<Self> {
+        match path.trim_end_matches('/') {
+            "/slot" => Some(Self::Slot),
+            "/first-available-block" => Some(Self::FirstAvailableBlock),
+            "/retention" => Some(Self::Retention),
+            "/status" => Some(Self::Status),
+            path => path
+                .strip_prefix("/block-time/")
+                .and_then(|slot| slot.parse().ok())
+                .map(Self::BlockTime),
+        }
+    }
+}
+
+/// Response body plus the caching headers that apply to it
+struct RestResponse {
+    status: hyper::StatusCode,
+    body: String,
+    cache_control: String,
+    etag: Option<String>,
+}
+
+async fn process_rest(
+    route: RestRoute,
+    request_processor: &JsonRpcRequestProcessor,
+    if_none_match: Option<String>,
+    rest_max_age: u64,
+    encoding: ResponseEncoding,
+) -> hyper::Response<hyper::Body> {
+    let short_lived = format!("public, max-age={rest_max_age}");
+    let response = match route {
+        RestRoute::Slot => match request_processor.get_slot(RpcContextConfig::default()).await {
+            Ok(slot) => RestResponse {
+                status: hyper::StatusCode::OK,
+                body: slot.to_string(),
+                cache_control: short_lived,
+                etag: None,
+            },
+            Err(err) => rest_error(err),
+        },
+        RestRoute::FirstAvailableBlock => RestResponse {
+            status: hyper::StatusCode::OK,
+            body: request_processor.get_first_available_block().await.to_string(),
+            cache_control: short_lived,
+            etag: None,
+        },
+        RestRoute::BlockTime(slot) => match request_processor.get_block_time(slot).await {
+            Ok(Some(block_time)) => {
+                let latest_slot = request_processor
+                    .get_slot(RpcContextConfig::default())
+                    .await
+                    .unwrap_or_default();
+                if slot.saturating_add(SETTLED_SLOT_DEPTH) <= latest_slot {
+                    RestResponse {
+                        status: hyper::StatusCode::OK,
+                        body: block_time.to_string(),
+                        cache_control: SETTLED_CACHE_CONTROL.to_string(),
+                        etag: Some(format!("\"{slot}-{block_time}\"")),
+                    }
+                } else {
+                    RestResponse {
+                        status: hyper::StatusCode::OK,
+                        body: block_time.to_string(),
+                        cache_control: "no-cache".to_string(),
+                        etag: None,
+                    }
+                }
+            }
+            Ok(None) => RestResponse {
+                status: hyper::StatusCode::NOT_FOUND,
+                body: String::new(),
+                cache_control: "no-cache".to_string(),
+                etag: None,
+            },
+            Err(err) => rest_error(err),
+        },
+        RestRoute::Retention => RestResponse {
+            status: hyper::StatusCode::OK,
+            body: serde_json::to_string(&request_processor.get_retention_policy()).unwrap_or_default(),
+            cache_control: short_lived,
+            etag: None,
+        },
+        RestRoute::Status => {
+            let (status, _) = request_processor.health_status().await;
+            RestResponse {
+                status: match status.state {
+                    HealthState::Unhealthy => hyper::StatusCode::SERVICE_UNAVAILABLE,
+                    _ => hyper::StatusCode::OK,
+                },
+                body: serde_json::to_string(&status).unwrap_or_default(),
+                cache_control: "no-cache".to_string(),
+                etag: None,
+            }
+        }
+    };
+
+    let not_modified = matches!(
+        (&response.etag, &if_none_match),
+        (Some(etag), Some(if_none_match)) if if_none_match.split(',').any(|tag| tag.trim() == etag.as_str())
+    );
+
+    let mut builder = hyper::Response::builder()
+        .header(hyper::header::CACHE_CONTROL, response.cache_control.as_str())
+        .header(hyper::header::VARY, "Accept");
+    if let Some(etag) = &response.etag {
+        builder = builder.header(hyper::header::ETAG, etag.as_str());
+    }
+    if not_modified {
+        return builder
+            .status(hyper::StatusCode::NOT_MODIFIED)
+            .body(hyper::Body::empty())
+            .unwrap();
+    }
+    let body = if response.body.is_empty() {
+        vec![]
+    } else {
+        match encoding.encode_json(&response.body) {
+            Ok(body) => body,
+            Err(err) => {
+                warn!("failed to encode response as {}: {}", encoding.content_type(), err);
+                return RpcRequestMiddleware::internal_server_error();
+            }
+        }
+    };
+    builder
+        .status(response.status)
+        .header(hyper::header::CONTENT_TYPE, encoding.content_type())
+        .body(hyper::Body::from(body))
+        .unwrap()
+}
+
+fn rest_error(err: jsonrpc_core::Error) -> RestResponse {
+    let status = match err.code {
+        jsonrpc_core::ErrorCode::InvalidParams => hyper::StatusCode::BAD_REQUEST,
+        jsonrpc_core::ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED) => {
+            hyper::StatusCode::NOT_FOUND
+        }
+        jsonrpc_core::ErrorCode::ServerError(_) => hyper::StatusCode::SERVICE_UNAVAILABLE,
+        _ => hyper::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    RestResponse {
+        status,
+        body: serde_json::to_string(&err).unwrap_or_default(),
+        cache_control: "no-cache".to_string(),
+        etag: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rest_path_matches() {
+        assert!(rest_path_matches("/status", "/status"));
+        assert!(rest_path_matches("/status", "/status/"));
+        assert!(!rest_path_matches("/status", "/status/1"));
+        assert!(rest_path_matches("/accounts/*", "/accounts"));
+        assert!(rest_path_matches("/accounts/*", "/accounts/abc"));
+        assert!(!rest_path_matches("/accounts/*", "/accountsabc"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_add_slot_headers() {
+        let mut headers = hyper::HeaderMap::new();
+        add_slot_headers(&mut headers, None);
+        assert!(headers.is_empty());
+
+        add_slot_headers(
+            &mut headers,
+            Some(StoredRange {
+                first_slot: 100,
+                latest_slot: 200,
+                block_height: None,
+            }),
+        );
+        assert_eq!(headers[FIRST_AVAILABLE_SLOT_HEADER], "100");
+        assert_eq!(headers[LATEST_SLOT_HEADER], "200");
+        assert!(!headers.contains_key(BLOCK_HEIGHT_HEADER));
+    }
+
+    #[test]
+    fn test_client_key() {
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut headers = hyper::HeaderMap::new();
+            for &(name, value) in pairs {
+                headers.insert(name, hyper::header::HeaderValue::from_static(value));
+            }
+            headers
+        };
+        let trusted_proxies = TrustedProxies::new(vec!["10.0.0.0/8".parse().unwrap()]);
+        let forwarded = headers(&[("x-forwarded-for", "203.0.113.7, 10.0.0.1")]);
+
+        let api_keys = ApiKeys::new(vec!["alice".to_string()]);
+        let no_keys = ApiKeys::default();
+
+        assert_eq!(
+            client_key(&forwarded, &no_keys, &trusted_proxies),
+            Some("ip:203.0.113.7".to_string())
+        );
+        // Spoofable without trusted proxies
+        assert_eq!(client_key(&forwarded, &no_keys, &TrustedProxies::default()), None);
+        assert_eq!(
+            client_key(&headers(&[(API_KEY_HEADER, " alice ")]), &api_keys, &TrustedProxies::default()),
+            Some("key:alice".to_string())
+        );
+        // Unknown keys are keyed like requests without one
+        assert_eq!(
+            client_key(&headers(&[(API_KEY_HEADER, "mallory")]), &api_keys, &TrustedProxies::default()),
+            None
+        );
+        assert_eq!(
+            client_key(
+                &headers(&[(API_KEY_HEADER, "mallory"), ("x-forwarded-for", "203.0.113.7, 10.0.0.1")]),
+                &api_keys,
+                &trusted_proxies
+            ),
+            Some("ip:203.0.113.7".to_string())
+        );
+        assert_eq!(format!("{api_keys:?}"), "ApiKeys(1 keys)");
+    }
+
+    #[test]
+    fn test_trace_id() {
+        let headers = |value: &'static str| {
+            let mut headers = hyper::HeaderMap::new();
+            headers.insert(TRACEPARENT_HEADER, hyper::header::HeaderValue::from_static(value));
+            headers
+        };
+        assert_eq!(
+            trace_id(&headers("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01")),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736".to_string())
+        );
+        // Not sampled
+        assert_eq!(
+            trace_id(&headers("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00")),
+            None
+        );
+        assert_eq!(
+            trace_id(&headers("00-00000000000000000000000000000000-00f067aa0ba902b7-01")),
+            None
+        );
+        assert_eq!(trace_id(&headers("garbage")), None);
+        assert_eq!(trace_id(&hyper::HeaderMap::new()), None);
+    }
+}
@@ -0,0 +1,134 @@
+//! Counters of the HTTP request middleware, kept apart from the JSON-RPC handler
+//! metrics so traffic answered or rejected before reaching the processors shows up
+
+use {
+    jsonrpc_http_server::hyper::StatusCode,
+    std::sync::atomic::{AtomicU64, Ordering},
+};
+
+/// What `RpcRequestMiddleware` did with a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MiddlewareOutcome {
+    /// Rejected by an interceptor with 401 or 403
+    AuthRejected,
+    /// Rejected by an interceptor with 429
+    RateLimited,
+    /// Answered by an interceptor with any other response
+    Intercepted,
+    UnsupportedEncoding,
+    /// JSON-RPC body over the size limit
+    Oversized,
+    /// JSON-RPC body that could not be read or decoded
+    Malformed,
+    /// JSON-RPC request answered by the middleware's own handlers
+    Rpc,
+    /// Left to the JSON-RPC server
+    Forwarded,
+    Rest,
+    /// Request to a registered REST handler
+    CustomRest,
+    Export,
+    /// `/health` and `/ready` probes
+    Health,
+}
+
+const OUTCOMES: usize = MiddlewareOutcome::Health as usize + 1;
+
+impl MiddlewareOutcome {
+    /// Outcome of a request an interceptor answered with `status`
+    pub fn intercepted(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Self::AuthRejected,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited,
+            _ => Self::Intercepted,
+        }
+    }
+
+    /// Outcome of a JSON-RPC request the middleware answered with `status`
+    pub fn rpc(status: StatusCode) -> Self {
+        match status {
+            StatusCode::PAYLOAD_TOO_LARGE => Self::Oversized,
+            StatusCode::BAD_REQUEST => Self::Malformed,
+            _ => Self::Rpc,
+        }
+    }
+}
+
+/// Cumulative request counts per outcome, shared by the middleware and the metrics loop
+#[derive(Debug, Default)]
+pub struct MiddlewareMetrics {
+    counts: [AtomicU64; OUTCOMES],
+}
+
+impl MiddlewareMetrics {
+    pub fn record(&self, outcome: MiddlewareOutcome) {
+        self.counts[outcome as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self, outcome: MiddlewareOutcome) -> u64 {
+        self.counts[outcome as usize].load(Ordering::Relaxed)
+    }
+
+    /// Report the cumulative counters
+    pub fn submit_metrics(&self) {
+        use MiddlewareOutcome::*;
+        let rejected = self.count(AuthRejected)
+            + self.count(RateLimited)
+            + self.count(UnsupportedEncoding)
+            + self.count(Oversized)
+            + self.count(Malformed);
+        solana_metrics::datapoint_info!(
+            "block-meta-rpc-middleware",
+            ("rejected", rejected, i64),
+            ("auth_rejected", self.count(AuthRejected), i64),
+            ("rate_limited", self.count(RateLimited), i64),
+            ("intercepted", self.count(Intercepted), i64),
+            ("unsupported_encoding", self.count(UnsupportedEncoding), i64),
+            ("oversized", self.count(Oversized), i64),
+            ("malformed", self.count(Malformed), i64),
+            ("rpc", self.count(Rpc), i64),
+            ("forwarded", self.count(Forwarded), i64),
+            ("rest", self.count(Rest), i64),
+            ("custom_rest", self.count(CustomRest), i64),
+            ("export", self.count(Export), i64),
+            ("health", self.count(Health), i64),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcomes() {
+        assert_eq!(
+            MiddlewareOutcome::intercepted(StatusCode::FORBIDDEN),
+            MiddlewareOutcome::AuthRejected
+        );
+        assert_eq!(
+            MiddlewareOutcome::intercepted(StatusCode::TOO_MANY_REQUESTS),
+            MiddlewareOutcome::RateLimited
+        );
+        assert_eq!(
+            MiddlewareOutcome::intercepted(StatusCode::OK),
+            MiddlewareOutcome::Intercepted
+        );
+        assert_eq!(
+            MiddlewareOutcome::rpc(StatusCode::PAYLOAD_TOO_LARGE),
+            MiddlewareOutcome::Oversized
+        );
+        assert_eq!(
+            MiddlewareOutcome::rpc(StatusCode::NO_CONTENT),
+            MiddlewareOutcome::Rpc
+        );
+
+        let metrics = MiddlewareMetrics::default();
+        metrics.record(MiddlewareOutcome::Health);
+        metrics.record(MiddlewareOutcome::Health);
+        metrics.record(MiddlewareOutcome::Export);
+        assert_eq!(metrics.count(MiddlewareOutcome::Health), 2);
+        assert_eq!(metrics.count(MiddlewareOutcome::Export), 1);
+        assert_eq!(metrics.count(MiddlewareOutcome::Rest), 0);
+    }
+}
@@ -0,0 +1,164 @@
+use {
+    crate::request_processor::JsonRpcRequestProcessor,
+    jsonrpc_core::{
+        futures::{
+            future::{self, Either},
+            FutureExt,
+        },
+        middleware::{Middleware, NoopCallFuture, NoopFuture},
+        Call, Error, Failure, Output, Params, Value,
+    },
+    std::{
+        collections::HashSet,
+        future::Future,
+        sync::{Arc, Mutex},
+    },
+};
+
+/// Positional parameters each method takes, its config object last, and the config
+/// object fields it honors. Methods not listed are not checked.
+fn signature(method: &str, v2: bool) -> Option<(usize, &'static [&'static str])> {
+    match method {
+        "getBlocks" if v2 => Some((3, &["commitment", "limit"])),
+        "getSlot" | "getBlockHeight" | "getTransactionCount" => {
+            Some((1, &["commitment", "minContextSlot"]))
+        }
+        "getBlocks" | "getBlockCount" => Some((3, &["commitment", "minContextSlot"])),
+        "getBlocksWithLimit" => Some((3, &["commitment"])),
+        "getBlockSummary" => Some((2, &["commitment", "rewards"])),
+        "getInflationReward" => Some((2, &["commitment", "epoch", "minContextSlot"])),
+        _ => None,
+    }
+}
+
+/// Fields of the config objects in `params` that `method` does not support, and the
+/// positional parameters it does not take, named by index
+fn unsupported_fields(method: &str, v2: bool, params: &Params) -> Vec<String> {
+    let ((max_params, supported), params) = match (signature(method, v2), params) {
+        (Some(signature), Params::Array(params)) => (signature, params),
+        _ => return vec![],
+    };
+    let fields = params
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(|config| config.keys())
+        .filter(|field| !supported.contains(&field.as_str()))
+        .cloned();
+    let extra_params = (max_params..params.len()).map(|index| format!("params[{index}]"));
+    fields.chain(extra_params).collect()
+}
+
+/// JSON-RPC middleware checking calls for config fields and positional parameters the
+/// method ignores. Strict mode rejects such calls; otherwise they are counted per
+/// method, and the first one of each method is logged.
+#[derive(Clone, Default)]
+pub struct ParamsMiddleware {
+    strict: bool,
+    v2: bool,
+    /// Methods whose ignored parameters were logged, among the few `signature` lists;
+    /// field names come from clients and are not kept
+    logged: Arc<Mutex<HashSet<String>>>,
+}
+
+impl ParamsMiddleware {
+    pub fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            ..Self::default()
+        }
+    }
+
+    /// Check against the `/v2` method signatures
+    pub fn for_v2(&self) -> Self {
+        Self {
+            v2: true,
+            ..self.clone()
+        }
+    }
+}
+
+impl Middleware<JsonRpcRequestProcessor> for ParamsMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = NoopCallFuture;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        if let Call::MethodCall(method_call) = &call {
+            let fields = unsupported_fields(&method_call.method, self.v2, &method_call.params);
+            if !fields.is_empty() {
+                if self.strict {
+                    return Either::Left(future::ready(Some(Output::Failure(Failure {
+                        jsonrpc: method_call.jsonrpc,
+                        error: Error::invalid_params(format!(
+                            "Unsupported parameters for {}: {}",
+                            method_call.method,
+                            fields.join(", ")
+                        )),
+                        id: method_call.id.clone(),
+                    }))).boxed());
+                }
+                solana_metrics::datapoint_info!(
+                    "block-meta-rpc-ignored-params",
+                    "method" => method_call.method.as_str(),
+                    ("count", fields.len() as i64, i64),
+                );
+                if self.logged.lock().unwrap().insert(method_call.method.clone()) {
+                    info!(
+                        "{} ignores {}; further calls are only counted",
+                        method_call.method,
+                        fields.join(", ")
+                    );
+                }
+            }
+        }
+        Either::Right(next(call, meta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsupported_fields() {
+        let params: Params = serde_json::from_value(json!([
+            1,
+            2,
+            {"commitment": "finalized", "encoding": "json", "transactionDetails": "full"}
+        ]))
+        .unwrap();
+        assert_eq!(
+            unsupported_fields("getBlocks", false, &params),
+            vec!["encoding", "transactionDetails"]
+        );
+        assert_eq!(
+            unsupported_fields("getBlockSummary", false, &params),
+            vec!["encoding", "transactionDetails", "params[2]"]
+        );
+        assert!(unsupported_fields("getBlockTime", false, &params).is_empty());
+
+        let params: Params = serde_json::from_value(json!([
+            1,
+            {"commitment": "finalized", "encoding": "json", "transactionDetails": "full"}
+        ]))
+        .unwrap();
+        assert_eq!(
+            unsupported_fields("getBlockSummary", false, &params),
+            vec!["encoding", "transactionDetails"]
+        );
+        let params: Params = serde_json::from_value(json!([{}, 5])).unwrap();
+        assert_eq!(unsupported_fields("getSlot", false, &params), vec!["params[1]"]);
+
+        let params: Params = serde_json::from_value(json!([1, null, {"limit": 10}])).unwrap();
+        assert!(unsupported_fields("getBlocks", true, &params).is_empty());
+        assert_eq!(unsupported_fields("getBlocks", false, &params), vec!["limit"]);
+    }
+}
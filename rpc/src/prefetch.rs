@@ -0,0 +1,83 @@
+use {
+    crate::cache::TtlCache,
+    solana_sdk::clock::Slot,
+    std::{sync::Arc, time::Duration},
+};
+
+/// Number of clients whose scan position is tracked
+const SCAN_CAPACITY: usize = 10_000;
+/// A client that has not continued its scan for this long starts over
+const SCAN_TTL: Duration = Duration::from_secs(60);
+/// Number of prefetched ranges kept until they are requested
+const RANGE_CAPACITY: usize = 1_024;
+const RANGE_TTL: Duration = Duration::from_secs(30);
+
+/// Detects clients walking `getBlocks` over consecutive ranges, and holds the next
+/// range of each scan once it has been fetched ahead of the request.
+pub struct ScanPrefetcher {
+    /// First slot of the range each client is expected to request next
+    scans: TtlCache<Arc<str>, Slot>,
+    ranges: TtlCache<(Slot, Slot), Arc<Vec<Slot>>>,
+}
+
+impl Default for ScanPrefetcher {
+    fn default() -> Self {
+        Self {
+            scans: TtlCache::new("scan-positions", SCAN_TTL, SCAN_CAPACITY),
+            ranges: TtlCache::new("scan-prefetch", RANGE_TTL, RANGE_CAPACITY),
+        }
+    }
+}
+
+impl ScanPrefetcher {
+    /// Slots of `start_slot..=end_slot`, if they were prefetched
+    pub fn get(&self, start_slot: Slot, end_slot: Slot) -> Option<Arc<Vec<Slot>>> {
+        self.ranges.get(&(start_slot, end_slot))
+    }
+
+    pub fn insert(&self, start_slot: Slot, end_slot: Slot, slots: Vec<Slot>) {
+        self.ranges.insert((start_slot, end_slot), Arc::new(slots));
+    }
+
+    /// Record that `client` requested `start_slot..=end_slot`. Returns the range to
+    /// prefetch when the request continues the client's previous one.
+    pub fn record(&self, client: &Arc<str>, start_slot: Slot, end_slot: Slot) -> Option<(Slot, Slot)> {
+        let sequential = self.scans.get(client) == Some(start_slot);
+        let next_start = end_slot.checked_add(1)?;
+        self.scans.insert(client.clone(), next_start);
+        if !sequential {
+            return None;
+        }
+        let next_end = next_start.checked_add(end_slot - start_slot)?;
+        self.get(next_start, next_end)
+            .is_none()
+            .then_some((next_start, next_end))
+    }
+
+    pub fn submit_metrics(&self) {
+        self.scans.submit_metrics();
+        self.ranges.submit_metrics();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_sequential_scan() {
+        let prefetcher = ScanPrefetcher::default();
+        let client: Arc<str> = Arc::from("indexer");
+
+        assert_eq!(prefetcher.record(&client, 100, 199), None);
+        assert_eq!(prefetcher.record(&client, 200, 299), Some((300, 399)));
+        prefetcher.insert(300, 399, vec![300, 301]);
+        assert_eq!(prefetcher.record(&client, 300, 399), Some((400, 499)));
+        prefetcher.insert(500, 599, vec![]);
+        // Already prefetched
+        assert_eq!(prefetcher.record(&client, 400, 499), None);
+        // A jump breaks the scan
+        assert_eq!(prefetcher.record(&client, 1_000, 1_099), None);
+        assert_eq!(prefetcher.record(&client, 1_100, 1_199), Some((1_200, 1_299)));
+    }
+}
@@ -0,0 +1,370 @@
+//! `query` subcommand: call a running deployment and print the result
+
+use {
+    block_meta_rpc_client::{types::RpcBlocksPageConfig, BlockMetaRpcClient},
+    clap::{value_t, values_t, App, AppSettings, Arg, ArgMatches, SubCommand},
+    serde_json::Value,
+    solana_clap_utils::input_validators::is_parsable,
+    solana_rpc_client_api::config::{RpcBlockConfig, RpcEpochConfig},
+    solana_sdk::clock::Slot,
+};
+
+fn slot_arg<'a, 'b>(name: &'a str, help: &'b str) -> Arg<'a, 'b> {
+    Arg::with_name(name)
+        .long(name)
+        .value_name("SLOT")
+        .takes_value(true)
+        .validator(is_parsable::<Slot>)
+        .help(help)
+}
+
+fn address_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("address")
+        .long("address")
+        .value_name("PUBKEY")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Account address, may be repeated")
+}
+
+pub fn query_subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("query")
+        .about("Call a running deployment and print the result")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .value_name("URL")
+                .takes_value(true)
+                .global(true)
+                .default_value("http://127.0.0.1:8899")
+                .help("RPC endpoint of the deployment"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .value_name("FORMAT")
+                .takes_value(true)
+                .global(true)
+                .possible_values(&["json", "table"])
+                .default_value("json")
+                .help("Print the result as JSON or as a table"),
+        )
+        .subcommand(SubCommand::with_name("get-health"))
+        .subcommand(SubCommand::with_name("get-version"))
+        .subcommand(SubCommand::with_name("get-limits"))
+        .subcommand(SubCommand::with_name("get-slot"))
+        .subcommand(SubCommand::with_name("get-block-height"))
+        .subcommand(SubCommand::with_name("get-transaction-count"))
+        .subcommand(SubCommand::with_name("get-first-available-block"))
+        .subcommand(
+            SubCommand::with_name("get-block-time")
+                .arg(slot_arg("slot", "Slot of the block").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("get-block-summary")
+                .arg(slot_arg("slot", "Slot of the block").required(true))
+                .arg(
+                    Arg::with_name("rewards")
+                        .long("rewards")
+                        .takes_value(false)
+                        .help("Include the block rewards"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-blocks")
+                .about("Stored slots from --start, up to --end or --limit slots")
+                .arg(slot_arg("start", "First slot, inclusive").required(true))
+                .arg(slot_arg("end", "Last slot, inclusive").conflicts_with("limit"))
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .value_name("COUNT")
+                        .takes_value(true)
+                        .validator(is_parsable::<usize>)
+                        .help("Return at most this many slots"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-blocks-page")
+                .about("One page of stored slots from the /v2 namespace")
+                .arg(slot_arg("start", "First slot, inclusive").required(true))
+                .arg(slot_arg("end", "Last slot, inclusive"))
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .value_name("COUNT")
+                        .takes_value(true)
+                        .validator(is_parsable::<usize>)
+                        .help("Page size"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-block-count")
+                .arg(slot_arg("start", "First slot, inclusive").required(true))
+                .arg(slot_arg("end", "Last slot, inclusive").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("get-block-rewards")
+                .arg(slot_arg("slot", "Slot of the block").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("blocks-exist").arg(
+                slot_arg("slot", "Slot to check, may be repeated")
+                    .required(true)
+                    .multiple(true)
+                    .number_of_values(1),
+            ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-block-meta-version")
+                .arg(slot_arg("slot", "Slot of the block").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("get-block-height-for-slot")
+                .arg(slot_arg("slot", "Slot of the block").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("get-slot-for-block-height").arg(
+                Arg::with_name("height")
+                    .long("height")
+                    .value_name("HEIGHT")
+                    .takes_value(true)
+                    .required(true)
+                    .validator(is_parsable::<u64>)
+                    .help("Height of the block"),
+            ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-slot-for-blockhash").arg(
+                Arg::with_name("blockhash")
+                    .long("blockhash")
+                    .value_name("HASH")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Base58 hash of the block"),
+            ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-block-fee-stats")
+                .arg(slot_arg("slot", "Slot of the block").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("get-fee-stats-range")
+                .arg(slot_arg("start", "First slot, inclusive").required(true))
+                .arg(slot_arg("end", "Last slot, inclusive").required(true)),
+        )
+        .subcommand(SubCommand::with_name("get-recent-prioritization-fees"))
+        .subcommand(
+            SubCommand::with_name("get-inflation-reward")
+                .arg(address_arg().required(true))
+                .arg(
+                    Arg::with_name("epoch")
+                        .long("epoch")
+                        .value_name("EPOCH")
+                        .takes_value(true)
+                        .validator(is_parsable::<u64>)
+                        .help("Epoch to report, defaults to the last completed one"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-backfill-status")
+                .arg(slot_arg("start", "First slot, inclusive").required(true))
+                .arg(slot_arg("end", "Last slot, inclusive")),
+        )
+}
+
+/// Run the `query` subcommand, blocking until the call completes
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    // Global args are propagated to the subcommand, which also sees values given after it
+    let global = |name: &str| {
+        matches
+            .subcommand()
+            .1
+            .and_then(|matches| matches.value_of(name))
+            .or_else(|| matches.value_of(name))
+            .map(str::to_string)
+    };
+    let url = global("url").unwrap_or_default();
+    let table = global("output").as_deref() == Some("table");
+    let client = BlockMetaRpcClient::new(url);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    let result = runtime.block_on(call(&client, matches.subcommand()))?;
+    if table {
+        print_table(&result);
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).map_err(|err| err.to_string())?
+        );
+    }
+    Ok(())
+}
+
+async fn call(
+    client: &BlockMetaRpcClient,
+    subcommand: (&str, Option<&ArgMatches<'_>>),
+) -> Result<Value, String> {
+    fn to_json<T: serde::Serialize>(
+        result: block_meta_rpc_client::Result<T>,
+    ) -> Result<Value, String> {
+        result
+            .map_err(|err| err.to_string())
+            .and_then(|value| serde_json::to_value(value).map_err(|err| err.to_string()))
+    }
+    let (name, matches) = match subcommand {
+        (name, Some(matches)) => (name, matches),
+        (name, None) => return Err(format!("Unknown query {name}")),
+    };
+    let slot = |name: &str| value_t!(matches, name, Slot).ok();
+    let limit = value_t!(matches, "limit", usize).ok();
+    let addresses = values_t!(matches, "address", String).ok();
+
+    match name {
+        "get-health" => to_json(client.get_health().await),
+        "get-version" => to_json(client.get_version().await),
+        "get-limits" => to_json(client.get_limits().await),
+        "get-slot" => to_json(client.get_slot(None).await),
+        "get-block-height" => to_json(client.get_block_height(None).await),
+        "get-transaction-count" => to_json(client.get_transaction_count(None).await),
+        "get-first-available-block" => to_json(client.get_first_available_block().await),
+        "get-block-time" => to_json(client.get_block_time(slot("slot").unwrap()).await),
+        "get-block-summary" => {
+            let config = RpcBlockConfig {
+                rewards: Some(matches.is_present("rewards")),
+                ..RpcBlockConfig::default()
+            };
+            to_json(client.get_block_summary(slot("slot").unwrap(), Some(config)).await)
+        }
+        "get-blocks" => match (slot("end"), limit) {
+            (end_slot, None) => to_json(client.get_blocks(slot("start").unwrap(), end_slot, None).await),
+            (_, Some(limit)) => to_json(
+                client
+                    .get_blocks_with_limit(slot("start").unwrap(), limit, None)
+                    .await,
+            ),
+        },
+        "get-blocks-page" => {
+            let config = RpcBlocksPageConfig {
+                limit,
+                ..RpcBlocksPageConfig::default()
+            };
+            to_json(
+                client
+                    .get_blocks_page(slot("start").unwrap(), slot("end"), Some(config))
+                    .await,
+            )
+        }
+        "get-block-count" => to_json(
+            client
+                .get_block_count(slot("start").unwrap(), slot("end").unwrap(), None)
+                .await,
+        ),
+        "get-block-rewards" => to_json(client.get_block_rewards(slot("slot").unwrap()).await),
+        "blocks-exist" => to_json(
+            client
+                .blocks_exist(values_t!(matches, "slot", Slot).unwrap())
+                .await,
+        ),
+        "get-block-meta-version" => {
+            to_json(client.get_block_meta_version(slot("slot").unwrap()).await)
+        }
+        "get-block-height-for-slot" => {
+            to_json(client.get_block_height_for_slot(slot("slot").unwrap()).await)
+        }
+        "get-slot-for-block-height" => to_json(
+            client
+                .get_slot_for_block_height(value_t!(matches, "height", u64).unwrap())
+                .await,
+        ),
+        "get-slot-for-blockhash" => to_json(
+            client
+                .get_slot_for_blockhash(matches.value_of("blockhash").unwrap())
+                .await,
+        ),
+        "get-block-fee-stats" => to_json(client.get_block_fee_stats(slot("slot").unwrap()).await),
+        "get-fee-stats-range" => to_json(
+            client
+                .get_fee_stats_range(slot("start").unwrap(), slot("end").unwrap())
+                .await,
+        ),
+        "get-recent-prioritization-fees" => {
+            to_json(client.get_recent_prioritization_fees(None).await)
+        }
+        "get-inflation-reward" => {
+            let config = value_t!(matches, "epoch", u64).ok().map(|epoch| RpcEpochConfig {
+                epoch: Some(epoch),
+                ..RpcEpochConfig::default()
+            });
+            to_json(
+                client
+                    .get_inflation_reward(addresses.unwrap_or_default(), config)
+                    .await,
+            )
+        }
+        "get-backfill-status" => to_json(
+            client
+                .get_backfill_status(slot("start").unwrap(), slot("end"))
+                .await,
+        ),
+        name => Err(format!("Unknown query {name}")),
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Print arrays of objects as columns, other arrays one item per line and objects as
+/// key/value rows
+fn print_table(result: &Value) {
+    let rows: Vec<Vec<String>> = match result {
+        Value::Array(items) => match items.first() {
+            Some(Value::Object(first)) => {
+                let columns: Vec<&String> = first.keys().collect();
+                std::iter::once(columns.iter().map(|column| column.to_string()).collect())
+                    .chain(items.iter().map(|item| {
+                        columns
+                            .iter()
+                            .map(|column| item.get(column.as_str()).map(cell).unwrap_or_default())
+                            .collect()
+                    }))
+                    .collect()
+            }
+            _ => items.iter().map(|item| vec![cell(item)]).collect(),
+        },
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| vec![key.clone(), cell(value)])
+            .collect(),
+        value => vec![vec![cell(value)]],
+    };
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(String::len)
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{value:<width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
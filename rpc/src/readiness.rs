@@ -0,0 +1,72 @@
+use {
+    solana_sdk::clock::{Slot, UnixTimestamp},
+    std::time::Duration,
+};
+
+/// Data an instance must hold before it reports ready, so that a replica restored
+/// from a partial backup does not receive production traffic
+#[derive(Debug, Clone, Default)]
+pub struct ReadinessConfig {
+    /// Storage must reach back to at least this slot
+    pub min_slot: Option<Slot>,
+    /// The latest stored block must be at most this old
+    pub max_staleness: Option<Duration>,
+}
+
+impl ReadinessConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.min_slot.is_some() || self.max_staleness.is_some()
+    }
+
+    /// Check the stored range against the requirements. `first_slot` is the first
+    /// available block, `latest_block_time` the block time of the latest stored block.
+    pub fn check(
+        &self,
+        first_slot: Option<Slot>,
+        latest_block_time: Option<UnixTimestamp>,
+        now: UnixTimestamp,
+    ) -> Result<(), String> {
+        if let Some(min_slot) = self.min_slot {
+            match first_slot {
+                Some(first_slot) if first_slot <= min_slot => {}
+                Some(first_slot) => {
+                    return Err(format!(
+                        "first available block {first_slot} is above the required slot {min_slot}"
+                    ))
+                }
+                None => return Err("no blocks are stored".to_string()),
+            }
+        }
+        if let Some(max_staleness) = self.max_staleness {
+            let latest_block_time = latest_block_time
+                .ok_or_else(|| "the latest block has no block time".to_string())?;
+            let staleness = now.saturating_sub(latest_block_time).max(0) as u64;
+            if staleness > max_staleness.as_secs() {
+                return Err(format!(
+                    "latest block is {staleness}s old, more than the allowed {}s",
+                    max_staleness.as_secs()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let config = ReadinessConfig {
+            min_slot: Some(100),
+            max_staleness: Some(Duration::from_secs(60)),
+        };
+        assert!(config.check(Some(50), Some(1_000), 1_030).is_ok());
+        assert!(config.check(Some(150), Some(1_000), 1_030).is_err());
+        assert!(config.check(None, Some(1_000), 1_030).is_err());
+        assert!(config.check(Some(50), Some(1_000), 1_061).is_err());
+        assert!(config.check(Some(50), None, 1_030).is_err());
+        assert!(ReadinessConfig::default().check(None, None, 0).is_ok());
+    }
+}
@@ -1,7 +1,30 @@
+#[cfg(feature = "archive")]
+use crate::archive::BlockArchive;
+#[cfg(feature = "fault-injection")]
+use crate::fault_injection::FaultInjectionConfig;
 use {
     crate::{
+        build_info::{RpcBuildInfo, RpcStorageInfo},
+        cache::TtlCache,
+        client_ip::TrustedProxies,
+        cors::CorsConfig,
         custom_error::RpcCustomError,
+        health::{
+            HealthChecks, HealthConfig, HealthLag, HealthState, ReferenceSlotCache,
+            REFERENCE_SLOT_TTL,
+        },
+        identity::ServiceIdentity,
+        listener::ListenerControl,
+        middleware::ApiKeys,
+        prefetch::ScanPrefetcher,
+        readiness::ReadinessConfig,
+        retention::{ArchiveConfig, RetentionConfig},
+        singleflight::SingleFlight,
+        slot_index::SlotIndex,
+        storage_router::{LatencyBuckets, StorageRouter},
+        throttle::{ThrottleConfig, SLOTS_PER_COST_UNIT},
     },
+    block_meta_rpc_client::BlockMetaRpcClient,
     jsonrpc_core::{
         Error, Metadata, Result
     },
@@ -9,6 +32,12 @@ use {
         config::*,
         request::{
             MAX_GET_CONFIRMED_BLOCKS_RANGE,
+            MAX_MULTIPLE_ACCOUNTS,
+        },
+        response::{
+            RpcInflationReward,
+            RpcPrioritizationFee,
+            RpcVersionInfo,
         },
     },
     solana_sdk::{
@@ -19,23 +48,71 @@ use {
         commitment_config::{
             CommitmentConfig,
         },
+        epoch_schedule::EpochSchedule,
         exit::Exit,
+        hash::Hash,
+        pubkey::Pubkey,
     },
     solana_storage_mysql::{
-        meta_storage,
+        meta_storage::{
+            self,
+            BackfillRange,
+            BackfillState,
+            BlockMetaRevision,
+            BlockMetaVersions,
+            BlockReward,
+            FeeStats,
+            SchemaFeature,
+            SchemaInfo,
+            TableInfo,
+            FEE_PERCENTILES,
+        },
         mysql::{
             MySQLConfig,
         }
     },
     std::{
+        collections::HashMap,
+        future::Future,
+        path::PathBuf,
         sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
             Arc,
             RwLock,
         },
+        time::{Duration, SystemTime, UNIX_EPOCH},
     },
 };
 
 pub const MAX_REQUEST_BODY_SIZE: usize = 50 * (1 << 10); // 50kB
+pub const MAX_RESPONSE_SIZE: usize = 64 * (1 << 20); // 64MB
+
+/// Upper bound for the configurable `getBlocks` range and `getBlocksWithLimit` limit
+pub const MAX_BLOCKS_RANGE_CAP: u64 = 10 * MAX_GET_CONFIRMED_BLOCKS_RANGE;
+
+/// Widest slot range fee statistics are computed over in one request
+pub const MAX_FEE_STATS_RANGE: u64 = 10_000;
+
+/// Number of recent slots reported by `getRecentPrioritizationFees`, matching the
+/// prioritization fee cache of a full node
+pub const RECENT_PRIORITIZATION_FEE_SLOTS: u64 = 150;
+
+/// Block times only change when a slot is orphaned, which evicts it, so warmed entries
+/// never expire; the cache capacity keeps the most recent slots
+const BLOCK_TIME_CACHE_TTL: Duration = Duration::MAX;
+
+/// Slots whose block times are read per storage query while warming the cache
+const WARM_BATCH_SLOTS: u64 = 10_000;
+
+/// Maximum number of accounts accepted by `getRecentPrioritizationFees`: none, as stored
+/// fees are not indexed by account
+pub const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 0;
+
+/// Maximum number of slots accepted by `blocksExist`
+pub const MAX_BLOCKS_EXIST_SLOTS: usize = 10_000;
+
+/// How long the health check waits for the reference RPC before estimating the slot
+const HEALTH_REFERENCE_TIMEOUT: Duration = Duration::from_secs(2);
 
 pub(crate) fn check_is_at_least_confirmed(commitment: CommitmentConfig) -> Result<()> {
     if !commitment.is_at_least_confirmed() {
@@ -52,14 +129,378 @@ pub struct RpcBlockCheck {
     pub exists: bool,
 }
 
+/// Ingestion state of a slot range as reported by `getBackfillStatus`. Slots outside
+/// every tracked range are `unknown`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcBackfillState {
+    Loaded,
+    Pending,
+    Failed,
+    Unknown,
+}
+
+impl From<BackfillState> for RpcBackfillState {
+    fn from(state: BackfillState) -> Self {
+        match state {
+            BackfillState::Loaded => Self::Loaded,
+            BackfillState::Pending => Self::Pending,
+            BackfillState::Failed => Self::Failed,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBackfillRange {
+    pub start_slot: Slot,
+    pub end_slot: Slot,
+    pub status: RpcBackfillState,
+}
+
+/// Reward paid to an account in a block, in the shape of Solana's `Reward`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlockReward {
+    pub pubkey: String,
+    pub lamports: i64,
+    pub post_balance: u64,
+    pub reward_type: Option<String>,
+    pub commission: Option<u8>,
+}
+
+impl From<BlockReward> for RpcBlockReward {
+    fn from(reward: BlockReward) -> Self {
+        Self {
+            pubkey: reward.pubkey,
+            lamports: reward.lamports,
+            post_balance: reward.post_balance,
+            reward_type: reward.reward_type,
+            commission: reward.commission,
+        }
+    }
+}
+
+/// One version of the stored metadata of a block
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlockMetaRevision {
+    pub block_time: Option<UnixTimestamp>,
+    pub deleted: bool,
+    pub updated_at: Option<UnixTimestamp>,
+}
+
+impl From<BlockMetaRevision> for RpcBlockMetaRevision {
+    fn from(revision: BlockMetaRevision) -> Self {
+        Self {
+            block_time: revision.block_time,
+            deleted: revision.deleted,
+            updated_at: revision.updated_at,
+        }
+    }
+}
+
+/// Current metadata of a block, soft-deleted or not, with its correction history
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlockMetaVersion {
+    pub slot: Slot,
+    /// Number of versions recorded, the current one included
+    pub version: usize,
+    #[serde(flatten)]
+    pub current: RpcBlockMetaRevision,
+    /// Superseded versions, oldest first
+    pub history: Vec<RpcBlockMetaRevision>,
+}
+
+impl RpcBlockMetaVersion {
+    fn new(slot: Slot, versions: BlockMetaVersions) -> Self {
+        Self {
+            slot,
+            version: versions.history.len() + 1,
+            current: versions.current.into(),
+            history: versions.history.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// The parts of a block this service stores
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlockSummary {
+    pub block_time: Option<UnixTimestamp>,
+    /// Block time in milliseconds, when millisecond block times are enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_time_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rewards: Option<Vec<RpcBlockReward>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcFeePercentile {
+    pub percentile: u8,
+    pub prioritization_fee: u64,
+}
+
+/// Fee statistics of a block, or of a slot range when `slot` is absent
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcFeeStats {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<Slot>,
+    pub transactions: u64,
+    pub total_fee: u64,
+    pub average_fee: f64,
+    pub prioritization_fee_percentiles: Vec<RpcFeePercentile>,
+    pub max_prioritization_fee: u64,
+}
+
+impl From<FeeStats> for RpcFeeStats {
+    fn from(stats: FeeStats) -> Self {
+        Self {
+            slot: stats.slot,
+            transactions: stats.transactions,
+            total_fee: stats.total_fee,
+            average_fee: stats.average_fee,
+            prioritization_fee_percentiles: FEE_PERCENTILES
+                .iter()
+                .zip(stats.prioritization_fee_percentiles)
+                .map(|(&percentile, prioritization_fee)| RpcFeePercentile {
+                    percentile,
+                    prioritization_fee,
+                })
+                .collect(),
+            max_prioritization_fee: stats.max_prioritization_fee,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlocksPageConfig {
+    pub limit: Option<usize>,
+    #[serde(flatten)]
+    pub commitment: Option<CommitmentConfig>,
+}
+
+/// Retention window applied by the purger, so clients can plan their backfill windows
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcRetentionPolicy {
+    pub enabled: bool,
+    /// Slots kept behind the latest stored slot
+    pub slots: Option<u64>,
+    /// Age in seconds of the oldest block kept
+    pub max_age_secs: Option<u64>,
+    /// Lowest slot kept by the latest purge
+    pub boundary_slot: Option<Slot>,
+    /// Whether purged block times are still served from the archive
+    pub archived: bool,
+}
+
+/// Cost budget of each client
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcThrottleLimits {
+    pub cost_per_second: f64,
+    pub burst: f64,
+    /// Slots of a range call that cost one weight unit
+    pub slots_per_cost_unit: u64,
+    /// Weight per method; methods not listed weigh 1
+    pub method_costs: HashMap<String, f64>,
+}
+
+/// Limits enforced by the service, so clients can size their requests up front
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcLimits {
+    pub max_request_body_size: usize,
+    pub max_response_size: Option<usize>,
+    /// Widest `getBlocks` and `getBlockCount` range
+    pub max_blocks_range: u64,
+    /// Largest `getBlocksWithLimit` limit
+    pub max_blocks_limit: usize,
+    pub max_fee_stats_range: u64,
+    pub max_prioritization_fee_accounts: usize,
+    pub max_blocks_exist_slots: usize,
+    /// Storage timeout of a call, also the largest budget a client may request
+    pub request_timeout_ms: Option<u64>,
+    pub max_in_flight_per_client: Option<usize>,
+    pub throttle: Option<RpcThrottleLimits>,
+}
+
+/// Health state with the checks that led to it, served on `/status`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcHealthStatus {
+    pub state: HealthState,
+    pub reasons: Vec<String>,
+    pub replicas_down: usize,
+    /// Set when the lag behind the cluster is measured
+    pub slots_behind: Option<u64>,
+    #[serde(flatten)]
+    pub identity: ServiceIdentity,
+}
+
+/// Deprecated command line argument the service was started with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcDeprecatedArgument {
+    pub argument: String,
+    pub replaced_by: Option<String>,
+    pub warning: String,
+}
+
+/// Table of a database the service reads, with the server's row estimate
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSchemaTable {
+    pub database: String,
+    pub name: String,
+    pub columns: Vec<String>,
+    pub estimated_rows: Option<u64>,
+}
+
+impl From<TableInfo> for RpcSchemaTable {
+    fn from(table: TableInfo) -> Self {
+        Self {
+            database: table.database,
+            name: table.name,
+            columns: table.columns,
+            estimated_rows: table.estimated_rows,
+        }
+    }
+}
+
+/// Optional feature and the table or column enabling it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSchemaFeature {
+    pub name: String,
+    pub enabled: bool,
+    pub source: Option<String>,
+}
+
+impl From<SchemaFeature> for RpcSchemaFeature {
+    fn from(feature: SchemaFeature) -> Self {
+        Self {
+            name: feature.name.to_string(),
+            enabled: feature.enabled,
+            source: feature.source,
+        }
+    }
+}
+
+/// Tables of the storage and the optional features they enable
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSchemaInfo {
+    pub tables: Vec<RpcSchemaTable>,
+    pub features: Vec<RpcSchemaFeature>,
+}
+
+impl From<SchemaInfo> for RpcSchemaInfo {
+    fn from(info: SchemaInfo) -> Self {
+        Self {
+            tables: info.tables.into_iter().map(Into::into).collect(),
+            features: info.features.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Page of slots with the slot to continue from, if there may be more
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSlotPage {
+    pub slots: Vec<Slot>,
+    pub next_start_slot: Option<Slot>,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct JsonRpcConfig {
     pub rpc_mysql_config: Option<MySQLConfig>,
+    pub rpc_mysql_canary_config: Option<MySQLConfig>,
+    pub canary_traffic_percent: u8,
+    /// Default storage timeout, also the upper bound for client-supplied budgets
+    pub request_timeout: Option<Duration>,
+    /// How long `BlockNotFound` results are cached; disabled when unset
+    pub negative_cache_ttl: Option<Duration>,
+    pub negative_cache_capacity: usize,
+    /// `max-age` in seconds advertised for REST resources that may still change
+    pub rest_max_age: u64,
+    /// Cross-origin access to the JSON-RPC endpoint
+    pub cors: CorsConfig,
     pub rpc_threads: usize,
     pub rpc_niceness_adj: i8,
+    /// Worker threads of a dedicated storage runtime; storage calls share the RPC
+    /// runtime when 0
+    pub storage_threads: usize,
+    /// Niceness adjustment of the storage runtime threads
+    pub storage_niceness_adj: i8,
+    /// Latency ranges storage call metrics are tagged with
+    pub storage_latency_buckets: LatencyBuckets,
     pub full_api: bool,
+    /// Expose the admin RPC methods, which modify storage
+    pub admin_rpc: bool,
+    /// Purge block metadata outside this window; disabled when unset
+    pub retention: Option<RetentionConfig>,
+    /// S3 archive holding purged block times; disabled when unset
+    pub archive: Option<ArchiveConfig>,
+    /// JSON file switching methods off or rolling them out gradually
+    pub feature_flags_path: Option<PathBuf>,
+    /// Prefetch the next range of clients scanning `getBlocks` sequentially
+    pub scan_prefetch: bool,
+    /// Add the block time in milliseconds to `getBlockSummary`; `getBlockTime` stays in seconds
+    pub block_time_millis: bool,
+    /// Data coverage required before `getHealth` and `/ready` report ready
+    pub readiness: ReadinessConfig,
+    /// Lag behind the cluster beyond which `getHealth` reports the node as behind
+    pub health: HealthConfig,
     pub obsolete_v1_7_api: bool,
     pub max_request_body_size: Option<usize>,
+    /// Widest `getBlocks` range, `MAX_GET_CONFIRMED_BLOCKS_RANGE` when unset
+    pub max_blocks_range: Option<u64>,
+    /// Largest `getBlocksWithLimit` limit, `MAX_GET_CONFIRMED_BLOCKS_RANGE` when unset
+    pub max_blocks_limit: Option<usize>,
+    /// Deprecated arguments given at startup, reported by the admin RPC
+    pub deprecated_arguments: Vec<RpcDeprecatedArgument>,
+    /// Responses serializing to more bytes than this are replaced by an error
+    pub max_response_size: Option<usize>,
+    /// Reject calls with config fields or extra parameters the method does not support
+    /// instead of ignoring them
+    pub strict_params: bool,
+    /// Calls each client may have in flight; unlimited when unset
+    pub max_in_flight_per_client: Option<usize>,
+    /// Cost budget of each client; unthrottled when unset
+    pub throttle: Option<ThrottleConfig>,
+    /// Report storage latencies of requests with a sampled `traceparent` as exemplars
+    pub trace_exemplars: bool,
+    /// Most recent slots whose block times are preloaded into the block time cache;
+    /// disabled when unset
+    pub cache_warm_slots: Option<u64>,
+    /// Most recent slots kept in the in-memory slot index; disabled when unset
+    pub slot_index_max_slots: Option<u64>,
+    /// Instance id and labels reported on `/status`
+    pub identity: ServiceIdentity,
+    /// Add the stored slot range headers to JSON-RPC responses, not only to REST ones
+    pub rpc_slot_headers: bool,
+    /// Core version reported by `getVersion` instead of this build's, for clients
+    /// detecting features by version
+    pub reported_version: Option<String>,
+    /// Feature set reported by `getVersion` instead of this build's
+    pub reported_feature_set: Option<u32>,
+    /// `Cache-Control` max-age of successful JSON-RPC responses per method; responses of
+    /// methods not listed are not marked cacheable
+    pub rpc_cache_max_ages: HashMap<String, Duration>,
+    /// API keys issued to clients; requests with any other key are keyed on their address
+    pub api_keys: ApiKeys,
+    /// Proxies trusted to report the previous hop, so clients are keyed on their own
+    /// address rather than on one they may have forged
+    pub trusted_proxies: TrustedProxies,
+    /// Start a new listener when the JSON-RPC service thread dies, instead of leaving
+    /// the process up and serving nothing
+    pub restart_listener: bool,
 }
 
 impl JsonRpcConfig {
@@ -72,12 +513,58 @@ impl JsonRpcConfig {
 }
 
 
+/// Storage result that can be handed to every caller sharing a coalesced lookup
+type SharedStorageResult<T> = std::result::Result<T, Arc<meta_storage::Error>>;
+
+/// In-flight storage lookups shared between concurrent identical requests
+#[derive(Default)]
+struct StorageFlights {
+    slot: SingleFlight<(), SharedStorageResult<Option<Slot>>>,
+    block_height: SingleFlight<(), SharedStorageResult<u64>>,
+    first_available_block: SingleFlight<(), SharedStorageResult<Option<Slot>>>,
+    /// Block times in milliseconds
+    block_time: SingleFlight<Slot, SharedStorageResult<i64>>,
+}
+
+/// Stored slot range advertised in response headers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StoredRange {
+    pub first_slot: Slot,
+    pub latest_slot: Slot,
+    pub block_height: Option<u64>,
+}
 
 pub struct JsonRpcRequestProcessor {
     config: JsonRpcConfig,
     #[allow(dead_code)]
     rpc_service_exit: Arc<RwLock<Exit>>,
-    metadata_storage: Option<meta_storage::MetaStorage>,
+    metadata_storage: Option<StorageRouter>,
+    storage_flights: Arc<StorageFlights>,
+    negative_cache: Option<Arc<TtlCache<Slot, ()>>>,
+    /// Block times in milliseconds of the most recent slots, filled by the cache warmer
+    block_time_cache: Option<Arc<TtlCache<Slot, i64>>>,
+    /// Slot up to which the block time cache has been warmed
+    warmed_slot: Arc<AtomicU64>,
+    /// Lowest slot kept by the retention purger
+    retention_boundary: Arc<AtomicU64>,
+    /// Stored slot range as of the last refresh; unset until storage holds a block
+    stored_range: Arc<RwLock<Option<StoredRange>>>,
+    /// Set while the last refresh failed and the range before it is still served
+    stored_range_stale: Arc<AtomicBool>,
+    #[cfg(feature = "archive")]
+    archive: Option<Arc<BlockArchive>>,
+    scan_prefetcher: Option<Arc<ScanPrefetcher>>,
+    slot_index: Option<Arc<SlotIndex>>,
+    /// Endpoint providing the reference slot of the health check
+    health_reference: Option<Arc<BlockMetaRpcClient>>,
+    reference_slot_cache: Arc<ReferenceSlotCache>,
+    /// API key or address identifying the client of the current request
+    client_key: Option<Arc<str>>,
+    /// Summary of what this instance serves, recorded once it listens
+    build_info: Arc<RwLock<RpcBuildInfo>>,
+    /// Set while the JSON-RPC listener is dead
+    listener_down: Arc<AtomicBool>,
+    listener: ListenerControl,
 }
 
 impl Metadata for JsonRpcRequestProcessor {}
@@ -88,6 +575,23 @@ impl Clone for JsonRpcRequestProcessor {
             config: self.config.clone(),
             rpc_service_exit: Arc::clone(&self.rpc_service_exit),
             metadata_storage: self.metadata_storage.clone(),
+            storage_flights: Arc::clone(&self.storage_flights),
+            negative_cache: self.negative_cache.clone(),
+            block_time_cache: self.block_time_cache.clone(),
+            warmed_slot: Arc::clone(&self.warmed_slot),
+            retention_boundary: Arc::clone(&self.retention_boundary),
+            stored_range: Arc::clone(&self.stored_range),
+            stored_range_stale: Arc::clone(&self.stored_range_stale),
+            #[cfg(feature = "archive")]
+            archive: self.archive.clone(),
+            scan_prefetcher: self.scan_prefetcher.clone(),
+            slot_index: self.slot_index.clone(),
+            health_reference: self.health_reference.clone(),
+            reference_slot_cache: Arc::clone(&self.reference_slot_cache),
+            client_key: self.client_key.clone(),
+            build_info: Arc::clone(&self.build_info),
+            listener_down: Arc::clone(&self.listener_down),
+            listener: self.listener.clone(),
         }
     }
 }
@@ -102,34 +606,262 @@ impl JsonRpcRequestProcessor {
     pub fn new(
         config: JsonRpcConfig,
         rpc_service_exit: Arc<RwLock<Exit>>,
-        metadata_storage: Option<meta_storage::MetaStorage>,
+        metadata_storage: Option<StorageRouter>,
     ) -> Self {
+        let negative_cache = config
+            .negative_cache_ttl
+            .map(|ttl| Arc::new(TtlCache::new("negative", ttl, config.negative_cache_capacity)));
+        let block_time_cache = config.cache_warm_slots.map(|warm_slots| {
+            Arc::new(TtlCache::new("block-time", BLOCK_TIME_CACHE_TTL, warm_slots as usize))
+        });
+        let scan_prefetcher = config.scan_prefetch.then(Arc::default);
+        let slot_index = config
+            .slot_index_max_slots
+            .map(|max_slots| Arc::new(SlotIndex::new(max_slots)));
+        let health_reference = config
+            .health
+            .reference_rpc_url
+            .as_ref()
+            .map(|url| Arc::new(BlockMetaRpcClient::new(url.clone())));
         Self {
             config,
             rpc_service_exit,
             metadata_storage,
+            storage_flights: Arc::default(),
+            negative_cache,
+            block_time_cache,
+            warmed_slot: Arc::default(),
+            retention_boundary: Arc::default(),
+            stored_range: Arc::default(),
+            stored_range_stale: Arc::default(),
+            #[cfg(feature = "archive")]
+            archive: None,
+            scan_prefetcher,
+            slot_index,
+            health_reference,
+            reference_slot_cache: Arc::default(),
+            client_key: None,
+            build_info: Arc::default(),
+            listener_down: Arc::default(),
+            listener: ListenerControl::default(),
+        }
+    }
+
+    pub fn get_retention_policy(&self) -> RpcRetentionPolicy {
+        let retention = self.config.retention.as_ref();
+        let boundary_slot = self.retention_boundary.load(Ordering::Acquire);
+        RpcRetentionPolicy {
+            enabled: retention.is_some(),
+            slots: retention.and_then(|retention| retention.slots),
+            max_age_secs: retention
+                .and_then(|retention| retention.max_age)
+                .map(|max_age| max_age.as_secs()),
+            boundary_slot: (boundary_slot > 0).then_some(boundary_slot),
+            #[cfg(feature = "archive")]
+            archived: self.archive.is_some(),
+            #[cfg(not(feature = "archive"))]
+            archived: false,
+        }
+    }
+
+    pub fn get_limits(&self) -> RpcLimits {
+        RpcLimits {
+            max_request_body_size: self
+                .config
+                .max_request_body_size
+                .unwrap_or(MAX_REQUEST_BODY_SIZE),
+            max_response_size: self.config.max_response_size,
+            max_blocks_range: self.max_blocks_range(),
+            max_blocks_limit: self.max_blocks_limit(),
+            max_fee_stats_range: MAX_FEE_STATS_RANGE,
+            max_prioritization_fee_accounts: MAX_PRIORITIZATION_FEE_ACCOUNTS,
+            max_blocks_exist_slots: MAX_BLOCKS_EXIST_SLOTS,
+            request_timeout_ms: self
+                .config
+                .request_timeout
+                .map(|timeout| timeout.as_millis() as u64),
+            max_in_flight_per_client: self.config.max_in_flight_per_client,
+            throttle: self.config.throttle.as_ref().map(|throttle| RpcThrottleLimits {
+                cost_per_second: throttle.cost_per_second,
+                burst: throttle.burst,
+                slots_per_cost_unit: SLOTS_PER_COST_UNIT,
+                method_costs: throttle.method_costs.clone(),
+            }),
+        }
+    }
+
+    /// This build's version, unless overridden by the config
+    pub fn get_version(&self) -> RpcVersionInfo {
+        let version = solana_version::Version::default();
+        RpcVersionInfo {
+            solana_core: self
+                .config
+                .reported_version
+                .clone()
+                .unwrap_or_else(|| version.to_string()),
+            feature_set: Some(self.config.reported_feature_set.unwrap_or(version.feature_set)),
+        }
+    }
+
+    /// Summary of the APIs, storage and caches of this instance, as logged at startup
+    pub fn get_build_info(&self) -> RpcBuildInfo {
+        self.build_info.read().unwrap().clone()
+    }
+
+    /// Record the summary returned by `get_build_info`, completed with the `auth` mode
+    /// and `listen_addresses` only the service knows
+    pub fn init_build_info(&self, auth: &str, listen_addresses: Vec<String>) -> RpcBuildInfo {
+        let mut apis = vec!["minimal"];
+        if self.config.full_api {
+            apis.push("full");
         }
+        if self.config.admin_rpc {
+            apis.push("admin");
+        }
+        apis.extend(["v2", "rest"]);
+        let storage = self.metadata_storage.as_ref().map(|router| {
+            let primary = router.primary();
+            RpcStorageInfo {
+                backend: "mysql".to_string(),
+                server_version: primary.server_version().map(str::to_string),
+                canary_percent: router.canary_percent(),
+                optional_features: primary
+                    .schema_features()
+                    .into_iter()
+                    .filter(|feature| feature.enabled)
+                    .map(|feature| feature.name.to_string())
+                    .collect(),
+            }
+        });
+        let caches = [
+            ("negative", self.negative_cache.is_some()),
+            ("blockTime", self.block_time_cache.is_some()),
+            ("slotIndex", self.slot_index.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect();
+        let build_info = RpcBuildInfo {
+            version: solana_version::version!().to_string(),
+            apis: apis.into_iter().map(str::to_string).collect(),
+            storage,
+            caches,
+            auth: auth.to_string(),
+            listen_addresses,
+        };
+        *self.build_info.write().unwrap() = build_info.clone();
+        build_info
+    }
+
+    pub fn get_deprecated_arguments(&self) -> Vec<RpcDeprecatedArgument> {
+        self.config.deprecated_arguments.clone()
+    }
+
+    fn max_blocks_range(&self) -> u64 {
+        self.config
+            .max_blocks_range
+            .unwrap_or(MAX_GET_CONFIRMED_BLOCKS_RANGE)
+            .min(MAX_BLOCKS_RANGE_CAP)
+    }
+
+    fn max_blocks_limit(&self) -> usize {
+        self.config
+            .max_blocks_limit
+            .unwrap_or(MAX_GET_CONFIRMED_BLOCKS_RANGE as usize)
+            .min(MAX_BLOCKS_RANGE_CAP as usize)
+    }
+
+    /// Serve block times purged from storage from `archive`
+    #[cfg(feature = "archive")]
+    pub fn with_archive(mut self, archive: Arc<BlockArchive>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    #[cfg(feature = "archive")]
+    pub fn archive(&self) -> Option<Arc<BlockArchive>> {
+        self.archive.clone()
     }
 
-    fn check_storage_result<T>(
+    /// Slot index for the loader to fill, when enabled
+    pub fn slot_index(&self) -> Option<Arc<SlotIndex>> {
+        self.slot_index.clone()
+    }
+
+    /// Boundary shared with the retention purger
+    pub fn retention_boundary(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.retention_boundary)
+    }
+
+    /// Derive a copy of this processor whose storage calls are bounded by the client's
+    /// requested budget, capped by the configured request timeout.
+    ///
+    /// Lookups shared with other clients run under the configured timeout only; the
+    /// budget bounds how long this client waits for them. All storage calls of the
+    /// request go to the same backend.
+    pub fn with_timeout_budget(&self, budget: Option<Duration>) -> Self {
+        let mut processor = self.clone();
+        processor.metadata_storage = processor.metadata_storage.map(|storage| {
+            storage
+                .with_timeout(self.config.request_timeout)
+                .with_budget(budget)
+                .pin_backend()
+        });
+        processor
+    }
+
+    /// Run `lookup` on `metadata_storage` in `flight`, sharing it with concurrent identical
+    /// requests
+    async fn run_flight<K, T, F, Fut>(
         &self,
-        result: &std::result::Result<T, meta_storage::Error>,
-    ) -> Result<()> {
-        info!("Checking mysql block");
-        if let Err(e) = result {
-            info!("Block error: {}", e);
+        metadata_storage: &StorageRouter,
+        flight: &SingleFlight<K, SharedStorageResult<T>>,
+        key: K,
+        lookup: F,
+    ) -> SharedStorageResult<T>
+    where
+        K: std::hash::Hash + Eq + Clone,
+        T: Clone,
+        F: FnOnce(StorageRouter) -> Fut,
+        Fut: Future<Output = meta_storage::Result<T>>,
+    {
+        let shared_storage = metadata_storage.without_budget();
+        let result = flight.run(key, || async move { lookup(shared_storage).await.map_err(Arc::new) });
+        match metadata_storage.budget() {
+            Some(budget) => tokio::time::timeout(budget, result)
+                .await
+                .unwrap_or_else(|_| Err(Arc::new(meta_storage::Error::Timeout))),
+            None => result.await,
         }
-        if let Err(meta_storage::Error::BlockNotFound(slot)) = result {
-            return Err(RpcCustomError::LongTermStorageSlotSkipped { slot: *slot }.into());
+    }
+
+    /// Derive a copy of this processor attributing requests to `client_key`
+    pub fn with_client_key(mut self, client_key: Option<String>) -> Self {
+        self.client_key = client_key.map(Arc::from);
+        self
+    }
+
+    /// Derive a copy of this processor reporting storage latencies as exemplars of the
+    /// sampled trace `trace_id`, when trace exemplars are enabled
+    pub fn with_trace_id(mut self, trace_id: Option<String>) -> Self {
+        if self.config.trace_exemplars {
+            let trace_id = trace_id.map(Arc::from);
+            self.metadata_storage = self
+                .metadata_storage
+                .map(|storage| storage.with_trace_id(trace_id));
         }
-        info!("Block check successful");
-        Ok(())
+        self
+    }
+
+    /// API key or address identifying the client of the current request
+    pub fn client_key(&self) -> Option<&Arc<str>> {
+        self.client_key.as_ref()
     }
 
     pub async fn get_blocks(
         &self,
         start_slot: Slot,
-        // FIXME: Maybe make this non-optional?
         end_slot: Option<Slot>,
         config: Option<RpcContextConfig>,
     ) -> Result<Vec<Slot>> {
@@ -137,34 +869,155 @@ impl JsonRpcRequestProcessor {
         let commitment = config.commitment.unwrap_or_default();
         check_is_at_least_confirmed(commitment)?;
 
-        if end_slot.unwrap() < start_slot {
+        let max_blocks_range = self.max_blocks_range();
+        // Like a full node, an open range runs up to the latest slot within the maximum
+        let end_slot = match end_slot {
+            Some(end_slot) => end_slot,
+            None => {
+                let end_slot = start_slot.saturating_add(max_blocks_range);
+                match self.latest_stored_slot().await {
+                    Some(latest_slot) => end_slot.min(latest_slot),
+                    None => end_slot,
+                }
+            }
+        };
+        if end_slot < start_slot {
             return Ok(vec![]);
         }
-        if end_slot.unwrap() - start_slot > MAX_GET_CONFIRMED_BLOCKS_RANGE {
-            return Err(Error::invalid_params(format!(
-                "Slot range too large; max {MAX_GET_CONFIRMED_BLOCKS_RANGE}"
-            )));
+        check_blocks_range(start_slot, end_slot, max_blocks_range)?;
+
+        if let Some(slots) = self
+            .slot_index
+            .as_ref()
+            .and_then(|index| index.blocks(start_slot, end_slot))
+        {
+            return Ok(slots);
+        }
+
+        if let Some(slots) = self.prefetched_blocks(start_slot, end_slot) {
+            self.prefetch_next_range(start_slot, end_slot);
+            return Ok(slots);
         }
 
         if let Some(metadata_storage) = &self.metadata_storage {
-            return metadata_storage
-                .get_confirmed_blocks(start_slot, (end_slot.unwrap() - start_slot) as usize + 1) // increment limit by 1 to ensure returned range is inclusive of both start_slot and end_slot
+            let limit = (end_slot - start_slot) as usize + 1; // increment limit by 1 to ensure returned range is inclusive of both start_slot and end_slot
+            let blocks = metadata_storage
+                .route("getBlocks", move |storage| async move {
+                    storage.get_confirmed_blocks(start_slot, Some(end_slot), limit).await
+                })
                 .await
                 .map(|mut mysql_blocks| {
-                    mysql_blocks.retain(|&slot| slot <= end_slot.unwrap());
+                    mysql_blocks.retain(|&slot| slot <= end_slot);
                     mysql_blocks
                 })
-                .map_err(|_| {
-                    Error::invalid_params(
-                        "MySQL query failed (maybe timeout due to too large range?)"
-                            .to_string(),
-                    )
-                });
+                .map_err(|err| storage_error(&err))?;
+            self.prefetch_next_range(start_slot, end_slot);
+            return Ok(blocks);
         }
 
         Ok(vec![])
     }
 
+    fn prefetched_blocks(&self, start_slot: Slot, end_slot: Slot) -> Option<Vec<Slot>> {
+        let slots = self.scan_prefetcher.as_ref()?.get(start_slot, end_slot)?;
+        Some(slots.as_ref().clone())
+    }
+
+    /// If the client is scanning sequentially, fetch the range it will most likely
+    /// request next in the background
+    fn prefetch_next_range(&self, start_slot: Slot, end_slot: Slot) {
+        let (prefetcher, client_key) = match (&self.scan_prefetcher, &self.client_key) {
+            (Some(prefetcher), Some(client_key)) => (prefetcher.clone(), client_key),
+            _ => return,
+        };
+        let (next_start, next_end) = match prefetcher.record(client_key, start_slot, end_slot) {
+            Some(range) => range,
+            None => return,
+        };
+        let request_processor = self.clone();
+        tokio::spawn(async move {
+            // Ranges past the stored tip may still fill in, only cache complete ones
+            match request_processor.latest_stored_slot().await {
+                Some(latest_slot) if next_end <= latest_slot => {}
+                _ => return,
+            }
+            let metadata_storage = match &request_processor.metadata_storage {
+                Some(metadata_storage) => metadata_storage,
+                None => return,
+            };
+            let limit = (next_end - next_start) as usize + 1;
+            match metadata_storage
+                .route("prefetchBlocks", move |storage| async move {
+                    storage.get_confirmed_blocks(next_start, Some(next_end), limit).await
+                })
+                .await
+            {
+                Ok(mut slots) => {
+                    slots.retain(|&slot| slot <= next_end);
+                    prefetcher.insert(next_start, next_end, slots);
+                }
+                Err(err) => debug!("Prefetch of {}-{} failed: {}", next_start, next_end, err),
+            }
+        });
+    }
+
+    /// Number of stored blocks in `start_slot..=end_slot`, without listing them
+    pub async fn get_block_count(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+        config: Option<RpcContextConfig>,
+    ) -> Result<u64> {
+        let config = config.unwrap_or_default();
+        check_is_at_least_confirmed(config.commitment.unwrap_or_default())?;
+        if end_slot < start_slot {
+            return Ok(0);
+        }
+        if let Some(count) = self
+            .slot_index
+            .as_ref()
+            .and_then(|index| index.count(start_slot, end_slot))
+        {
+            return Ok(count);
+        }
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(0),
+        };
+        metadata_storage
+            .route("getBlockCount", move |storage| async move {
+                storage.count_blocks(start_slot, end_slot).await
+            })
+            .await
+            .map_err(|err| storage_error(&err))
+    }
+
+    /// Fetch up to `limit` stored slots in `start_slot..=end_slot`, for callers that
+    /// page through ranges larger than `MAX_GET_CONFIRMED_BLOCKS_RANGE`
+    pub async fn get_blocks_page(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+        limit: usize,
+    ) -> Result<Vec<Slot>> {
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) if start_slot <= end_slot => metadata_storage,
+            _ => return Ok(vec![]),
+        };
+        let limit = limit.min((end_slot - start_slot).saturating_add(1) as usize);
+        let storage_result = metadata_storage
+            .route("exportBlocks", move |storage| async move {
+                storage.get_confirmed_blocks(start_slot, Some(end_slot), limit).await
+            })
+            .await;
+        storage_result
+            .map(|mut slots| {
+                slots.retain(|&slot| slot <= end_slot);
+                slots
+            })
+            .map_err(|err| storage_error(&err))
+    }
+
     pub async fn get_blocks_with_limit(
         &self,
         start_slot: Slot,
@@ -174,83 +1027,1298 @@ impl JsonRpcRequestProcessor {
         let commitment = commitment.unwrap_or_default();
         check_is_at_least_confirmed(commitment)?;
 
-        if limit > MAX_GET_CONFIRMED_BLOCKS_RANGE as usize {
+        let max_blocks_limit = self.max_blocks_limit();
+        if limit > max_blocks_limit {
             return Err(Error::invalid_params(format!(
-                "Limit too large; max {MAX_GET_CONFIRMED_BLOCKS_RANGE}"
+                "Limit too large; max {max_blocks_limit}"
             )));
         }
 
         if let Some(metadata_storage) = &self.metadata_storage {
-            return Ok(metadata_storage
-                .get_confirmed_blocks(start_slot, limit)
+            return metadata_storage
+                .route("getBlocksWithLimit", move |storage| async move {
+                    storage.get_confirmed_blocks(start_slot, None, limit).await
+                })
                 .await
-                .unwrap_or_default());
+                .map_err(|err| storage_error(&err));
         }
 
         Ok(vec![])
     }
 
     pub async fn get_block_time(&self, slot: Slot) -> Result<Option<UnixTimestamp>> {
+        Ok(self
+            .get_block_time_millis(slot)
+            .await?
+            .map(|block_time| block_time.div_euclid(1000)))
+    }
+
+    /// Block time of `slot` in milliseconds. Precision beyond seconds depends on the
+    /// stored column; archived block times only have seconds.
+    async fn get_block_time_millis(&self, slot: Slot) -> Result<Option<i64>> {
         // Handle the special case for slot 0
         if slot == 0 {
-            return Ok(Some(self.genesis_creation_time()));
+            return Ok(Some(self.genesis_creation_time() * 1000));
+        }
+
+        // Slots recently found to be missing are answered without hitting storage
+        if let Some(negative_cache) = &self.negative_cache {
+            if negative_cache.get(&slot).is_some() {
+                return Err(RpcCustomError::LongTermStorageSlotSkipped { slot }.into());
+            }
+        }
+        if let Some(block_time) = self
+            .block_time_cache
+            .as_ref()
+            .and_then(|block_time_cache| block_time_cache.get(&slot))
+        {
+            return Ok(Some(block_time));
         }
 
         // Check if MySQL metadata storage is available
         if let Some(metadata_storage) = &self.metadata_storage {
 
-            let storage_result = metadata_storage.get_block_time(slot).await;
-            self.check_storage_result(&storage_result)?;
-            return Ok(storage_result
-                .ok()
-                .and_then(|naive_datetime| Some(naive_datetime.timestamp())));
+            let storage_result = self
+                .run_flight(metadata_storage, &self.storage_flights.block_time, slot, |metadata_storage| async move {
+                    metadata_storage
+                        .route("getBlockTime", move |storage| async move { storage.get_block_time(slot).await })
+                        .await
+                        .map(|block_time| block_time.timestamp_millis())
+                })
+                .await;
+            if let Err(err) = &storage_result {
+                if let meta_storage::Error::BlockNotFound(_) = **err {
+                    #[cfg(feature = "archive")]
+                    if let Some(block_time) = self.get_archived_block_time(slot).await {
+                        return Ok(Some(block_time * 1000));
+                    }
+                    self.cache_missing_block(slot).await;
+                }
+            }
+            return storage_result.map(Some).map_err(|err| storage_error(&err));
         }
 
         // Return None if MySQL metadata storage is not available
         Ok(None)
     }
 
-    pub async fn get_block_height(&self, _config: RpcContextConfig) -> Result<u64> {
-        // Check if MySQL metadata storage is available
-        if let Some(metadata_storage) = &self.metadata_storage {
+    pub async fn get_block_height(&self, config: RpcContextConfig) -> Result<u64> {
+        // Without storage the height is reported as 0, like the slot
+        if self.metadata_storage.is_none() {
+            return Ok(0);
+        }
+        self.get_block_height_strict(config).await
+    }
+
+    pub async fn get_first_available_block(&self) -> Slot {
+        self.stored_first_available_block()
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    /// Like `get_first_available_block`, but failing instead of answering 0 when storage is
+    /// unavailable or empty
+    pub async fn get_first_available_block_strict(&self) -> Result<Slot> {
+        match self.stored_first_available_block().await {
+            Ok(Some(slot)) => Ok(slot),
+            Ok(None) => Err(RpcCustomError::NoBlocksStored.into()),
+            Err(err) => Err(storage_error(&err)),
+        }
+    }
+
+    async fn stored_first_available_block(&self) -> SharedStorageResult<Option<Slot>> {
+        let metadata_storage = self
+            .metadata_storage
+            .as_ref()
+            .ok_or_else(|| Arc::new(meta_storage::Error::StorageUnavailable))?;
+        let first_slot = self
+            .run_flight(metadata_storage, &self.storage_flights.first_available_block, (), |metadata_storage| async move {
+                metadata_storage
+                    .route("getFirstAvailableBlock", move |storage| async move {
+                        storage.get_first_available_block().await
+                    })
+                    .await
+            })
+            .await?;
+        // Rows below the boundary may still be in the middle of being purged
+        Ok(first_slot.map(|first_slot| {
+            first_slot.max(self.retention_boundary.load(Ordering::Acquire))
+        }))
+    }
+
+    /// Report the ingestion state of every slot in `start_slot..=end_slot`, so clients can
+    /// tell skipped slots apart from slots that have not been loaded yet
+    pub async fn get_backfill_status(
+        &self,
+        start_slot: Slot,
+        end_slot: Option<Slot>,
+    ) -> Result<Vec<RpcBackfillRange>> {
+        let end_slot = end_slot.unwrap_or(start_slot);
+        if end_slot < start_slot {
+            return Err(Error::invalid_params("end_slot must not be below start_slot"));
+        }
+        if end_slot - start_slot > MAX_GET_CONFIRMED_BLOCKS_RANGE {
+            return Err(Error::invalid_params(format!(
+                "Slot range too large; max {MAX_GET_CONFIRMED_BLOCKS_RANGE}"
+            )));
+        }
 
-            let storage_result = metadata_storage.get_block_height().await;
-            self.check_storage_result(&storage_result)?;
-            if let Ok(block_height) = storage_result {
-                return Ok(block_height);
+        let ranges = match &self.metadata_storage {
+            Some(metadata_storage) => {
+                let storage_result = metadata_storage
+                    .route("getBackfillStatus", move |storage| async move {
+                        storage.get_backfill_status(start_slot, end_slot).await
+                    })
+                    .await;
+                storage_result.map_err(|err| storage_error(&err))?
             }
+            None => vec![],
+        };
+        Ok(cover_backfill_ranges(start_slot, end_slot, ranges))
+    }
+
+    pub async fn get_block_rewards(&self, slot: Slot) -> Result<Vec<RpcBlockReward>> {
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(vec![]),
+        };
+        let rewards = metadata_storage
+            .route("getBlockRewards", move |storage| async move {
+                storage.get_block_rewards(slot).await
+            })
+            .await
+            .map_err(|err| storage_error(&err))?;
+        Ok(rewards.into_iter().map(RpcBlockReward::from).collect())
+    }
+
+    /// Return whether a block is stored at each of `slots`, in order. Like `getBlocks`,
+    /// only the database is consulted, not the archive.
+    pub async fn blocks_exist(&self, slots: Vec<Slot>) -> Result<Vec<bool>> {
+        if slots.len() > MAX_BLOCKS_EXIST_SLOTS {
+            return Err(Error::invalid_params(format!(
+                "Too many inputs provided; max {MAX_BLOCKS_EXIST_SLOTS}"
+            )));
         }
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(vec![false; slots.len()]),
+        };
+        metadata_storage
+            .route("blocksExist", move |storage| async move {
+                storage.blocks_exist(&slots).await
+            })
+            .await
+            .map_err(|err| storage_error(&err))
+    }
 
-        // Return None if MySQL metadata storage is not available
-        Ok(0)
+    /// Return the metadata of the block at `slot` with its correction history, including
+    /// soft-deleted blocks that reads skip. `None` when the slot was never stored.
+    pub async fn get_block_meta_version(&self, slot: Slot) -> Result<Option<RpcBlockMetaVersion>> {
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(None),
+        };
+        let versions = metadata_storage
+            .route("getBlockMetaVersion", move |storage| async move {
+                storage.get_block_meta_versions(slot).await
+            })
+            .await;
+        match versions {
+            Ok(versions) => Ok(Some(RpcBlockMetaVersion::new(slot, versions))),
+            Err(meta_storage::Error::BlockNotFound(_)) => Ok(None),
+            Err(err) => Err(storage_error(&err)),
+        }
     }
 
-    pub async fn get_first_available_block(&self) -> Slot {
+    /// Return the height of the block at `slot`, `None` when no block is stored there
+    pub async fn get_block_height_for_slot(&self, slot: Slot) -> Result<Option<u64>> {
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(None),
+        };
+        let block_height = metadata_storage
+            .route("getBlockHeightForSlot", move |storage| async move {
+                storage.get_block_height_for_slot(slot).await
+            })
+            .await;
+        match block_height {
+            Ok(block_height) => Ok(Some(block_height)),
+            Err(meta_storage::Error::BlockNotFound(_)) => Ok(None),
+            Err(err) => Err(storage_error(&err)),
+        }
+    }
+
+    /// Return the slot of the block at `block_height`, `None` when no stored block has it
+    pub async fn get_slot_for_block_height(&self, block_height: u64) -> Result<Option<Slot>> {
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(None),
+        };
+        metadata_storage
+            .route("getSlotForBlockHeight", move |storage| async move {
+                storage.get_slot_for_block_height(block_height).await
+            })
+            .await
+            .map_err(|err| storage_error(&err))
+    }
+
+    /// Return the slot of the block hashed `blockhash`, `None` when no stored block has it
+    pub async fn get_slot_for_blockhash(&self, blockhash: String) -> Result<Option<Slot>> {
+        let blockhash = blockhash
+            .parse::<Hash>()
+            .map_err(|_| Error::invalid_params(format!("Invalid param: {blockhash}")))?;
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(None),
+        };
+        metadata_storage
+            .route("getSlotForBlockhash", move |storage| async move {
+                storage.get_slot_for_blockhash(&blockhash).await
+            })
+            .await
+            .map_err(|err| storage_error(&err))
+    }
+
+    /// Return the stored parts of the block at `slot`. Rewards are included when
+    /// requested and the storage schema has them.
+    ///
+    /// Served as `getBlockSummary`: with no blockhashes, parent slot or transactions this
+    /// is not the block `getBlock` returns.
+    pub async fn get_block_summary(
+        &self,
+        slot: Slot,
+        config: Option<RpcBlockConfig>,
+    ) -> Result<RpcBlockSummary> {
+        let config = config.unwrap_or_default();
+        check_is_at_least_confirmed(config.commitment.unwrap_or_default())?;
+
+        let block_time_ms = self.get_block_time_millis(slot).await?;
+        let rewards_available = self
+            .metadata_storage
+            .as_ref()
+            .is_some_and(|storage| storage.primary().rewards_available());
+        let rewards = if config.rewards.unwrap_or(true) && rewards_available {
+            Some(self.get_block_rewards(slot).await?)
+        } else {
+            None
+        };
+        Ok(RpcBlockSummary {
+            block_time: block_time_ms.map(|block_time| block_time.div_euclid(1000)),
+            block_time_ms: block_time_ms.filter(|_| self.config.block_time_millis),
+            rewards,
+        })
+    }
+
+    pub async fn get_transaction_count(&self, config: RpcContextConfig) -> Result<u64> {
+        check_is_at_least_confirmed(config.commitment.unwrap_or_default())?;
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(0),
+        };
+        metadata_storage
+            .route("getTransactionCount", move |storage| async move {
+                storage.get_transaction_count().await
+            })
+            .await
+            .map_err(|err| storage_error(&err))
+    }
+
+    /// Fee statistics of the block at `slot`, or `None` if it has no transactions
+    pub async fn get_block_fee_stats(&self, slot: Slot) -> Result<Option<RpcFeeStats>> {
+        let stats = self.query_fee_stats(slot, slot, true).await?;
+        Ok(stats.into_iter().next().map(RpcFeeStats::from))
+    }
+
+    /// Fee statistics aggregated over every block in `start_slot..=end_slot`
+    pub async fn get_fee_stats_range(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<RpcFeeStats> {
+        if end_slot < start_slot {
+            return Err(Error::invalid_params("end_slot must not be below start_slot"));
+        }
+        if end_slot - start_slot > MAX_FEE_STATS_RANGE {
+            return Err(Error::invalid_params(format!(
+                "Slot range too large; max {MAX_FEE_STATS_RANGE}"
+            )));
+        }
+        let stats = self.query_fee_stats(start_slot, end_slot, false).await?;
+        Ok(stats
+            .into_iter()
+            .next()
+            .map(RpcFeeStats::from)
+            .unwrap_or_else(|| RpcFeeStats {
+                slot: None,
+                transactions: 0,
+                total_fee: 0,
+                average_fee: 0.0,
+                prioritization_fee_percentiles: vec![],
+                max_prioritization_fee: 0,
+            }))
+    }
+
+    /// Lowest prioritization fee of each of the most recent stored blocks.
+    ///
+    /// Stored fees are not indexed by account, so requests naming `addresses` are
+    /// rejected rather than answered with fees that ignore them.
+    pub async fn get_recent_prioritization_fees(
+        &self,
+        addresses: Option<Vec<String>>,
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        if addresses.is_some_and(|addresses| !addresses.is_empty()) {
+            return Err(Error::invalid_params(
+                "Filtering prioritization fees by account is not supported; omit the addresses",
+            ));
+        }
+
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(vec![]),
+        };
+        let end_slot = match self.latest_stored_slot().await {
+            Some(latest_slot) => latest_slot,
+            None => return Ok(vec![]),
+        };
+        let start_slot = end_slot.saturating_sub(RECENT_PRIORITIZATION_FEE_SLOTS - 1);
+        let fees = metadata_storage
+            .route("getRecentPrioritizationFees", move |storage| async move {
+                storage.get_min_prioritization_fees(start_slot, end_slot).await
+            })
+            .await
+            .map_err(|err| storage_error(&err))?;
+        Ok(fees
+            .into_iter()
+            .map(|(slot, prioritization_fee)| RpcPrioritizationFee {
+                slot,
+                prioritization_fee,
+            })
+            .collect())
+    }
+
+    async fn query_fee_stats(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+        per_block: bool,
+    ) -> Result<Vec<FeeStats>> {
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(vec![]),
+        };
+        metadata_storage
+            .route("getFeeStats", move |storage| async move {
+                storage.get_fee_stats(start_slot, end_slot, per_block).await
+            })
+            .await
+            .map_err(|err| storage_error(&err))
+    }
+
+    /// Return the inflation reward of each address for an epoch, defaulting to the last
+    /// completed epoch
+    pub async fn get_inflation_reward(
+        &self,
+        addresses: Vec<String>,
+        config: Option<RpcEpochConfig>,
+    ) -> Result<Vec<Option<RpcInflationReward>>> {
+        let config = config.unwrap_or_default();
+        check_is_at_least_confirmed(config.commitment.unwrap_or_default())?;
+        if addresses.len() > MAX_MULTIPLE_ACCOUNTS {
+            return Err(Error::invalid_params(format!(
+                "Too many inputs provided; max {MAX_MULTIPLE_ACCOUNTS}"
+            )));
+        }
+        for address in &addresses {
+            address.parse::<Pubkey>().map_err(|_| {
+                Error::invalid_params(format!("Invalid param: {address}"))
+            })?;
+        }
+
+        let metadata_storage = match &self.metadata_storage {
+            Some(metadata_storage) => metadata_storage,
+            None => return Ok(vec![None; addresses.len()]),
+        };
+        let epoch = match config.epoch {
+            Some(epoch) => epoch,
+            None => {
+                let latest_slot = self.latest_stored_slot().await.unwrap_or_default();
+                EpochSchedule::without_warmup()
+                    .get_epoch(latest_slot)
+                    .saturating_sub(1)
+            }
+        };
+
+        let query_addresses = addresses.clone();
+        let rewards = metadata_storage
+            .route("getInflationReward", move |storage| async move {
+                storage.get_inflation_rewards(&query_addresses, epoch).await
+            })
+            .await
+            .map_err(|err| storage_error(&err))?;
+        Ok(addresses
+            .iter()
+            .map(|address| {
+                rewards
+                    .iter()
+                    .find(|reward| &reward.address == address)
+                    .map(|reward| RpcInflationReward {
+                        epoch: reward.epoch,
+                        effective_slot: reward.effective_slot,
+                        amount: reward.amount,
+                        post_balance: reward.post_balance,
+                        commission: reward.commission,
+                    })
+            })
+            .collect())
+    }
+
+    /// Replace the faults injected into storage calls, returning the active ones
+    #[cfg(feature = "fault-injection")]
+    pub fn set_fault_injection(
+        &self,
+        config: Option<FaultInjectionConfig>,
+    ) -> Result<Option<FaultInjectionConfig>> {
+        let metadata_storage = self
+            .metadata_storage
+            .as_ref()
+            .ok_or_else(Error::invalid_request)?;
+        if let Some(config) = &config {
+            config.validate().map_err(Error::invalid_params)?;
+        }
+        metadata_storage.faults().set(config);
+        Ok(metadata_storage.faults().get())
+    }
+
+    #[cfg(feature = "fault-injection")]
+    pub fn get_fault_injection(&self) -> Result<Option<FaultInjectionConfig>> {
+        let metadata_storage = self
+            .metadata_storage
+            .as_ref()
+            .ok_or_else(Error::invalid_request)?;
+        Ok(metadata_storage.faults().get())
+    }
+
+    /// Describe the schema of the primary storage and its shards, and the optional
+    /// features it enables
+    pub async fn get_schema_info(&self) -> Result<RpcSchemaInfo> {
+        let metadata_storage = self
+            .metadata_storage
+            .as_ref()
+            .ok_or_else(Error::invalid_request)?;
+        metadata_storage
+            .on_primary()
+            .route("getSchemaInfo", |storage| async move { storage.get_schema_info().await })
+            .await
+            .map(RpcSchemaInfo::from)
+            .map_err(|err| storage_error(&err))
+    }
+
+    /// Mark `slot` as orphaned or canonical on the primary storage
+    pub async fn set_slot_orphaned(&self, slot: Slot, orphaned: bool) -> Result<()> {
+        let metadata_storage = self
+            .metadata_storage
+            .as_ref()
+            .ok_or_else(Error::invalid_request)?;
+        let storage = metadata_storage.on_primary();
+        storage
+            .route("setSlotOrphaned", move |storage| async move {
+                storage.set_slot_orphaned(slot, orphaned).await
+            })
+            .await
+            .map_err(|err| match err {
+                meta_storage::Error::ReadOnly | meta_storage::Error::OrphanTrackingUnavailable => {
+                    Error {
+                        code: jsonrpc_core::ErrorCode::InvalidRequest,
+                        message: err.to_string(),
+                        data: None,
+                    }
+                }
+                err => storage_error(&err),
+            })?;
+        info!("Slot {} marked as {}", slot, if orphaned { "orphaned" } else { "canonical" });
+
+        // A restored slot must not keep being answered as missing
+        if let Some(negative_cache) = &self.negative_cache {
+            negative_cache.remove(&slot);
+        }
+        if let Some(block_time_cache) = &self.block_time_cache {
+            block_time_cache.remove(&slot);
+        }
+        if let Some(slot_index) = &self.slot_index {
+            if orphaned {
+                slot_index.set_present(slot, false);
+            } else {
+                // A restored slot is only indexed if its block is stored; when that can't
+                // be told the index is dropped rather than left guessing
+                let blocks = storage
+                    .route("getBlocks", move |storage| async move {
+                        storage.get_confirmed_blocks(slot, Some(slot), 1).await
+                    })
+                    .await;
+                match blocks {
+                    Ok(blocks) => slot_index.set_present(slot, !blocks.is_empty()),
+                    Err(err) => {
+                        warn!("Slot index invalidated, failed to look up slot {}: {}", slot, err);
+                        slot_index.invalidate();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Look up a block purged from storage in the archive
+    #[cfg(feature = "archive")]
+    async fn get_archived_block_time(&self, slot: Slot) -> Option<UnixTimestamp> {
+        let archive = self.archive.as_ref()?;
+        if slot >= self.get_first_available_block().await {
+            return None;
+        }
+        archive
+            .get_block_time(slot)
+            .await
+            .map_err(|err| warn!("Archive lookup for slot {} failed: {}", slot, err))
+            .ok()
+            .flatten()
+    }
+
+    /// Report hit/miss/eviction counters of every enabled cache
+    pub fn submit_cache_metrics(&self) {
+        if let Some(negative_cache) = &self.negative_cache {
+            negative_cache.submit_metrics();
+        }
+        if let Some(block_time_cache) = &self.block_time_cache {
+            block_time_cache.submit_metrics();
+        }
+        #[cfg(feature = "archive")]
+        if let Some(archive) = &self.archive {
+            archive.submit_metrics();
+        }
+        if let Some(scan_prefetcher) = &self.scan_prefetcher {
+            scan_prefetcher.submit_metrics();
+        }
         if let Some(metadata_storage) = &self.metadata_storage {
-            let first_slot = metadata_storage
-                .get_first_available_block()
+            metadata_storage.submit_metrics();
+        }
+    }
+
+    /// Preload the block times of the most recent `cache_warm_slots` slots, skipping slots
+    /// warmed by earlier runs
+    pub async fn warm_block_time_cache(&self) {
+        let (block_time_cache, warm_slots, metadata_storage) = match (
+            &self.block_time_cache,
+            self.config.cache_warm_slots,
+            &self.metadata_storage,
+        ) {
+            (Some(block_time_cache), Some(warm_slots), Some(metadata_storage)) => {
+                (block_time_cache, warm_slots, metadata_storage)
+            }
+            _ => return,
+        };
+        let latest_slot = match self.latest_stored_slot().await {
+            Some(latest_slot) => latest_slot,
+            None => return,
+        };
+        let start_slot = latest_slot
+            .saturating_sub(warm_slots.saturating_sub(1))
+            .max(self.warmed_slot.load(Ordering::Acquire).saturating_add(1));
+        if start_slot > latest_slot {
+            return;
+        }
+        let mut batch_start = start_slot;
+        while batch_start <= latest_slot {
+            let batch_end = batch_start.saturating_add(WARM_BATCH_SLOTS).min(latest_slot + 1);
+            let block_times = metadata_storage
+                .route("warmBlockTimes", move |storage| async move {
+                    storage
+                        .get_block_times_millis_in_range(batch_start, batch_end)
+                        .await
+                })
+                .await;
+            match block_times {
+                Ok(block_times) => {
+                    for (slot, block_time) in block_times {
+                        block_time_cache.insert(slot, block_time);
+                    }
+                    self.warmed_slot.fetch_max(batch_end - 1, Ordering::AcqRel);
+                }
+                Err(err) => {
+                    warn!("Failed to warm the block time cache: {}", err);
+                    return;
+                }
+            }
+            batch_start = batch_end;
+        }
+        debug!("Warmed the block times of slots {}-{}", start_slot, latest_slot);
+    }
+
+    /// Remember that `slot` is missing, unless it is beyond the latest stored slot and
+    /// may still be ingested
+    async fn cache_missing_block(&self, slot: Slot) {
+        if let Some(negative_cache) = &self.negative_cache {
+            match self.latest_stored_slot().await {
+                Some(latest_slot) if slot < latest_slot => negative_cache.insert(slot, ()),
+                _ => {}
+            }
+        }
+    }
+
+    async fn latest_stored_slot(&self) -> Option<Slot> {
+        self.stored_slot().await.ok().flatten()
+    }
+
+    async fn stored_slot(&self) -> SharedStorageResult<Option<Slot>> {
+        let metadata_storage = self
+            .metadata_storage
+            .as_ref()
+            .ok_or_else(|| Arc::new(meta_storage::Error::StorageUnavailable))?;
+        self.run_flight(metadata_storage, &self.storage_flights.slot, (), |metadata_storage| async move {
+            metadata_storage
+                .route("getSlot", move |storage| async move { storage.get_slot().await })
+                .await
+        })
+        .await
+    }
+
+    /// Like `get_slot`, but failing instead of answering 0 when storage is unavailable
+    /// or empty
+    pub async fn get_slot_strict(&self, _config: RpcContextConfig) -> Result<Slot> {
+        match self.stored_slot().await {
+            Ok(Some(slot)) => Ok(slot),
+            Ok(None) => Err(RpcCustomError::NoBlocksStored.into()),
+            Err(err) => Err(storage_error(&err)),
+        }
+    }
+
+    /// Like `get_block_height`, but surfacing every storage failure
+    pub async fn get_block_height_strict(&self, _config: RpcContextConfig) -> Result<u64> {
+        let metadata_storage = self
+            .metadata_storage
+            .as_ref()
+            .ok_or_else(|| Error::from(RpcCustomError::StorageUnavailable))?;
+        self.run_flight(metadata_storage, &self.storage_flights.block_height, (), |metadata_storage| async move {
+            metadata_storage
+                .route("getBlockHeight", move |storage| async move { storage.get_block_height().await })
                 .await
-                .unwrap_or(None)
-                .unwrap_or(Slot::default());
+        })
+        .await
+        .map_err(|err| storage_error(&err))
+    }
 
-            return first_slot;
+    /// Return up to `limit` stored slots from `start_slot` on, with the slot the next
+    /// page starts at
+    pub async fn get_blocks_paginated(
+        &self,
+        start_slot: Slot,
+        end_slot: Option<Slot>,
+        config: Option<RpcBlocksPageConfig>,
+    ) -> Result<RpcSlotPage> {
+        let config = config.unwrap_or_default();
+        check_is_at_least_confirmed(config.commitment.unwrap_or_default())?;
+        let limit = config.limit.unwrap_or(MAX_GET_CONFIRMED_BLOCKS_RANGE as usize);
+        if limit == 0 || limit > MAX_GET_CONFIRMED_BLOCKS_RANGE as usize {
+            return Err(Error::invalid_params(format!(
+                "Limit must be between 1 and {MAX_GET_CONFIRMED_BLOCKS_RANGE}"
+            )));
         }
-        Slot::default()
+        if self.metadata_storage.is_none() {
+            return Err(RpcCustomError::StorageUnavailable.into());
+        }
+
+        let end_slot = end_slot.unwrap_or(Slot::MAX);
+        let slots = self.get_blocks_page(start_slot, end_slot, limit).await?;
+        let next_start_slot = next_page_start(&slots, limit, end_slot);
+        Ok(RpcSlotPage {
+            slots,
+            next_start_slot,
+        })
+    }
+
+    /// Reload the stored slot range advertised in response headers. The last known
+    /// range is kept while storage is unreachable, which degrades the health state.
+    pub async fn refresh_stored_range(&self) {
+        let (first_slot, latest_slot) = match (
+            self.stored_first_available_block().await,
+            self.stored_slot().await,
+        ) {
+            (Ok(Some(first_slot)), Ok(Some(latest_slot))) => (first_slot, latest_slot),
+            (Ok(None), _) | (_, Ok(None)) => {
+                *self.stored_range.write().unwrap() = None;
+                self.stored_range_stale.store(false, Ordering::Relaxed);
+                return;
+            }
+            _ => {
+                let stale = self.stored_range().is_some();
+                self.stored_range_stale.store(stale, Ordering::Relaxed);
+                return;
+            }
+        };
+        let block_height = self
+            .get_block_height_strict(RpcContextConfig::default())
+            .await
+            .ok();
+        *self.stored_range.write().unwrap() = Some(StoredRange {
+            first_slot,
+            latest_slot,
+            block_height,
+        });
+        self.stored_range_stale.store(false, Ordering::Relaxed);
+    }
+
+    /// Stored slot range as of the last `refresh_stored_range`
+    pub fn stored_range(&self) -> Option<StoredRange> {
+        *self.stored_range.read().unwrap()
+    }
+
+    pub fn rpc_slot_headers(&self) -> bool {
+        self.config.rpc_slot_headers
     }
 
     pub async fn get_slot(&self, _config: RpcContextConfig) -> Result<Slot> {
-        if let Some(metadata_storage) = &self.metadata_storage {
-            let first_slot = metadata_storage
-                .get_slot()
+        Ok(self.latest_stored_slot().await.unwrap_or_default())
+    }
+
+    /// Succeeds once storage holds the data range required by the readiness config
+    pub async fn check_readiness(&self) -> Result<()> {
+        let readiness = &self.config.readiness;
+        if !readiness.is_enabled() {
+            return Ok(());
+        }
+        let not_ready = |reason: String| Error::from(RpcCustomError::NodeNotReady { reason });
+        let first_slot = match readiness.min_slot {
+            Some(_) => self
+                .stored_first_available_block()
                 .await
-                .unwrap_or(None)
-                .unwrap_or(Slot::default());
+                .map_err(|err| not_ready(err.to_string()))?,
+            None => None,
+        };
+        let latest_block_time = match readiness.max_staleness {
+            Some(_) => match self.stored_slot().await {
+                Ok(Some(slot)) => self.get_block_time(slot).await?,
+                Ok(None) => None,
+                Err(err) => return Err(not_ready(err.to_string())),
+            },
+            None => None,
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as UnixTimestamp;
+        readiness
+            .check(first_slot, latest_block_time, now)
+            .map_err(not_ready)
+    }
+
+    /// How far the latest stored slot trails the cluster; `None` when either is unknown
+    pub async fn health_lag(&self) -> Option<HealthLag> {
+        let latest_slot = match self.stored_slot().await {
+            Ok(slot) => slot?,
+            Err(err) => {
+                warn!("health check: {}", err);
+                return None;
+            }
+        };
+        let reference_slot = match self.reference_slot().await {
+            Some(slot) => slot,
+            None => {
+                let block_time_ms = self.get_block_time_millis(latest_slot).await.ok()??;
+                let now_ms = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as i64;
+                self.config
+                    .health
+                    .estimate_slot(latest_slot, block_time_ms, now_ms)
+            }
+        };
+        Some(HealthLag::new(latest_slot, reference_slot))
+    }
+
+    /// Handle on the JSON-RPC listener, set by the service once it listens
+    pub fn listener(&self) -> ListenerControl {
+        self.listener.clone()
+    }
+
+    /// Close the JSON-RPC listener and bind a new one, with reloaded settings when the
+    /// service has a loader for them. In-flight requests on other connections may be cut.
+    pub fn restart_rpc(&self) -> Result<()> {
+        if !self.listener.request_restart() {
+            return Err(Error::invalid_request());
+        }
+        warn!("JSON RPC listener restart requested");
+        Ok(())
+    }
 
-            return Ok(first_slot);
+    /// Record whether the JSON-RPC listener is dead, which makes the service unhealthy
+    pub fn set_listener_down(&self, down: bool) {
+        self.listener_down.store(down, Ordering::Relaxed);
+    }
+
+    /// Classify the service as ok, degraded or unhealthy. The lag is only measured when
+    /// health checking is configured.
+    pub async fn health_status(&self) -> (RpcHealthStatus, Option<HealthLag>) {
+        let storage_reachable = self.stored_slot().await.is_ok();
+        let health = &self.config.health;
+        let lag = if storage_reachable && health.is_enabled() {
+            self.health_lag().await
+        } else {
+            None
+        };
+        let behind = storage_reachable
+            && health.max_slots_behind.is_some()
+            && !lag.as_ref().is_some_and(|lag| health.is_healthy(lag));
+        if let (true, Some(lag)) = (behind, &lag) {
+            info!("health check: behind by {} slots", lag.slots_behind);
         }
-        Ok(Slot::default())
+        let replicas_down = self
+            .metadata_storage
+            .as_ref()
+            .map_or(0, |storage| storage.primary().unhealthy_replicas());
+        let (state, reasons) = HealthChecks {
+            listener_down: self.listener_down.load(Ordering::Relaxed),
+            storage_reachable,
+            behind,
+            replicas_down,
+            stale_stored_range: self.stored_range_stale.load(Ordering::Relaxed),
+        }
+        .assess();
+        let status = RpcHealthStatus {
+            state,
+            reasons,
+            replicas_down,
+            slots_behind: lag.map(|lag| lag.slots_behind),
+            identity: self.config.identity.clone(),
+        };
+        (status, lag)
+    }
+
+    /// Report the health state and the lag behind the cluster as gauges, along with the
+    /// instance labels
+    pub async fn submit_health_metrics(&self) {
+        self.config.identity.submit_metrics();
+        let (status, lag) = self.health_status().await;
+        solana_metrics::datapoint_info!(
+            "block-meta-rpc-health-state",
+            ("state", status.state.as_str(), String),
+            ("level", status.state as i64, i64),
+            ("replicas_down", status.replicas_down as i64, i64),
+        );
+        if let Some(lag) = lag {
+            solana_metrics::datapoint_info!(
+                "block-meta-rpc-health",
+                ("latest_slot", lag.latest_slot as i64, i64),
+                ("reference_slot", lag.reference_slot as i64, i64),
+                ("slots_behind", lag.slots_behind as i64, i64),
+            );
+        }
+    }
+
+    /// Slot of the reference RPC, asked at most once per `REFERENCE_SLOT_TTL`; `None`
+    /// falls back to the slot estimated from block times
+    async fn reference_slot(&self) -> Option<Slot> {
+        let client = self.health_reference.as_ref()?;
+        let fetch = || async move {
+            match tokio::time::timeout(HEALTH_REFERENCE_TIMEOUT, client.get_slot(None)).await {
+                Ok(Ok(slot)) => Some(slot),
+                Ok(Err(err)) => {
+                    warn!("health check: reference RPC {} failed: {}", client.url(), err);
+                    None
+                }
+                Err(_) => {
+                    warn!("health check: reference RPC {} timed out", client.url());
+                    None
+                }
+            }
+        };
+        self.reference_slot_cache
+            .get_or_fetch(REFERENCE_SLOT_TTL, fetch)
+            .await
     }
 }
 
+/// Translate a storage failure into the JSON-RPC error returned to clients
+fn storage_error(err: &meta_storage::Error) -> Error {
+    match err {
+        meta_storage::Error::BlockNotFound(slot) => {
+            RpcCustomError::LongTermStorageSlotSkipped { slot: *slot }.into()
+        }
+        meta_storage::Error::Timeout => RpcCustomError::StorageTimeout.into(),
+        meta_storage::Error::StorageUnavailable => RpcCustomError::StorageUnavailable.into(),
+        meta_storage::Error::StorageBusy => RpcCustomError::StorageBusy.into(),
+        meta_storage::Error::RewardsUnavailable => {
+            RpcCustomError::UnsupportedBySchema { feature: "Rewards" }.into()
+        }
+        meta_storage::Error::BlockMetaVersionsUnavailable => {
+            RpcCustomError::UnsupportedBySchema { feature: "Block metadata versions" }.into()
+        }
+        meta_storage::Error::InflationRewardsUnavailable => {
+            RpcCustomError::UnsupportedBySchema { feature: "Inflation rewards" }.into()
+        }
+        meta_storage::Error::FeesUnavailable => {
+            RpcCustomError::UnsupportedBySchema { feature: "Fee statistics" }.into()
+        }
+        meta_storage::Error::TransactionCountUnavailable => {
+            RpcCustomError::UnsupportedBySchema { feature: "Transaction counts" }.into()
+        }
+        meta_storage::Error::BlockHeightUnavailable => {
+            RpcCustomError::UnsupportedBySchema { feature: "Block heights" }.into()
+        }
+        meta_storage::Error::BlockhashUnavailable => {
+            RpcCustomError::UnsupportedBySchema { feature: "Blockhash lookups" }.into()
+        }
+        meta_storage::Error::SchemaDrift(message) => RpcCustomError::SchemaDrift {
+            message: message.clone(),
+        }
+        .into(),
+        meta_storage::Error::RangeTooLarge(max_rows) => RpcCustomError::RangeTooLarge {
+            max_rows: *max_rows,
+        }
+        .into(),
+        err => RpcCustomError::MySQLError {
+            message: err.to_string(),
+        }
+        .into(),
+    }
+}
+
+/// Fail when the inclusive range `start_slot..=end_slot` spans more than
+/// `max_blocks_range` slots past `start_slot`
+fn check_blocks_range(start_slot: Slot, end_slot: Slot, max_blocks_range: u64) -> Result<()> {
+    if end_slot.saturating_sub(start_slot) > max_blocks_range {
+        return Err(Error::invalid_params(format!(
+            "Slot range too large; max {max_blocks_range}"
+        )));
+    }
+    Ok(())
+}
+
+/// Slot the page after `slots` starts at, `None` when the page was not full or reached
+/// `end_slot`
+fn next_page_start(slots: &[Slot], limit: usize, end_slot: Slot) -> Option<Slot> {
+    match slots.last() {
+        Some(&last_slot) if slots.len() == limit && last_slot < end_slot => Some(last_slot + 1),
+        _ => None,
+    }
+}
 
+/// Clip `ranges` to `start_slot..=end_slot` and fill the gaps between them with
+/// `unknown` ranges, so the result covers the requested range exactly
+fn cover_backfill_ranges(
+    start_slot: Slot,
+    end_slot: Slot,
+    ranges: Vec<BackfillRange>,
+) -> Vec<RpcBackfillRange> {
+    let mut covered = vec![];
+    let mut next_slot = start_slot;
+    for range in ranges {
+        if range.end_slot < next_slot || range.start_slot > end_slot {
+            continue;
+        }
+        if range.start_slot > next_slot {
+            covered.push(RpcBackfillRange {
+                start_slot: next_slot,
+                end_slot: range.start_slot - 1,
+                status: RpcBackfillState::Unknown,
+            });
+        }
+        let range_end = range.end_slot.min(end_slot);
+        covered.push(RpcBackfillRange {
+            start_slot: range.start_slot.max(next_slot),
+            end_slot: range_end,
+            status: range.state.into(),
+        });
+        if range_end == end_slot {
+            return covered;
+        }
+        next_slot = range_end + 1;
+    }
+    covered.push(RpcBackfillRange {
+        start_slot: next_slot,
+        end_slot,
+        status: RpcBackfillState::Unknown,
+    });
+    covered
+}
 
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            custom_error::JSON_RPC_RANGE_TOO_LARGE,
+            storage_stub::{sample_stub, stub_router, StorageStub},
+        },
+        proptest::prelude::*,
+    };
+
+    #[test]
+    fn test_get_version_override() {
+        let processor = |config| {
+            JsonRpcRequestProcessor::new(config, Arc::new(RwLock::new(Exit::default())), None)
+        };
+        let version = solana_version::Version::default();
+        assert_eq!(
+            processor(JsonRpcConfig::default()).get_version(),
+            RpcVersionInfo {
+                solana_core: version.to_string(),
+                feature_set: Some(version.feature_set),
+            }
+        );
+        let config = JsonRpcConfig {
+            reported_version: Some("1.18.22".to_string()),
+            reported_feature_set: Some(42),
+            ..JsonRpcConfig::default()
+        };
+        assert_eq!(
+            processor(config).get_version(),
+            RpcVersionInfo {
+                solana_core: "1.18.22".to_string(),
+                feature_set: Some(42),
+            }
+        );
+    }
+
+    #[test]
+    fn test_block_meta_version() {
+        let revision = |block_time, deleted, updated_at| BlockMetaRevision {
+            block_time: Some(block_time),
+            deleted,
+            updated_at,
+        };
+        let version = RpcBlockMetaVersion::new(
+            7,
+            BlockMetaVersions {
+                current: revision(101, true, Some(2_000)),
+                history: vec![revision(100, false, None)],
+            },
+        );
+        assert_eq!(
+            serde_json::to_value(version).unwrap(),
+            serde_json::json!({
+                "slot": 7,
+                "version": 2,
+                "blockTime": 101,
+                "deleted": true,
+                "updatedAt": 2000,
+                "history": [{"blockTime": 100, "deleted": false, "updatedAt": null}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_cover_backfill_ranges() {
+        let range = |start_slot, end_slot, state| BackfillRange {
+            start_slot,
+            end_slot,
+            state,
+        };
+        let rpc_range = |start_slot, end_slot, status| RpcBackfillRange {
+            start_slot,
+            end_slot,
+            status,
+        };
+
+        assert_eq!(
+            cover_backfill_ranges(10, 20, vec![]),
+            vec![rpc_range(10, 20, RpcBackfillState::Unknown)]
+        );
+        assert_eq!(
+            cover_backfill_ranges(
+                10,
+                40,
+                vec![
+                    range(0, 14, BackfillState::Loaded),
+                    range(20, 29, BackfillState::Pending),
+                    range(30, 100, BackfillState::Failed),
+                ]
+            ),
+            vec![
+                rpc_range(10, 14, RpcBackfillState::Loaded),
+                rpc_range(15, 19, RpcBackfillState::Unknown),
+                rpc_range(20, 29, RpcBackfillState::Pending),
+                rpc_range(30, 40, RpcBackfillState::Failed),
+            ]
+        );
+    }
+
+    /// Sorted, disjoint backfill ranges built from gaps and lengths
+    fn backfill_ranges() -> impl Strategy<Value = Vec<BackfillRange>> {
+        prop::collection::vec((0u64..50, 0u64..50, 0u8..3), 0..20).prop_map(|spans| {
+            let mut next_slot = 0;
+            spans
+                .into_iter()
+                .map(|(gap, len, state)| {
+                    let start_slot = next_slot + gap;
+                    next_slot = start_slot + len + 1;
+                    BackfillRange {
+                        start_slot,
+                        end_slot: start_slot + len,
+                        state: match state {
+                            0 => BackfillState::Loaded,
+                            1 => BackfillState::Pending,
+                            _ => BackfillState::Failed,
+                        },
+                    }
+                })
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_cover_backfill_ranges_is_contiguous(
+            start_slot in 0u64..1_000,
+            len in 0u64..1_000,
+            ranges in backfill_ranges(),
+        ) {
+            let end_slot = start_slot + len;
+            let covered = cover_backfill_ranges(start_slot, end_slot, ranges);
+            prop_assert_eq!(covered.first().map(|range| range.start_slot), Some(start_slot));
+            prop_assert_eq!(covered.last().map(|range| range.end_slot), Some(end_slot));
+            for range in &covered {
+                prop_assert!(range.start_slot <= range.end_slot);
+            }
+            for pair in covered.windows(2) {
+                prop_assert_eq!(pair[0].end_slot + 1, pair[1].start_slot);
+            }
+        }
+
+        #[test]
+        fn test_check_blocks_range(
+            start_slot in any::<Slot>(),
+            len in 0u64..1_000_000,
+            max_blocks_range in 0u64..1_000_000,
+        ) {
+            let end_slot = start_slot.saturating_add(len);
+            prop_assert_eq!(
+                check_blocks_range(start_slot, end_slot, max_blocks_range).is_ok(),
+                end_slot - start_slot <= max_blocks_range
+            );
+            // The bound itself is inclusive
+            let widest_end_slot = start_slot.saturating_add(max_blocks_range);
+            prop_assert!(check_blocks_range(start_slot, widest_end_slot, max_blocks_range).is_ok());
+        }
+
+        #[test]
+        fn test_next_page_start(
+            slots in prop::collection::btree_set(0u64..10_000, 0..100),
+            limit in 1usize..100,
+            end_slot in 0u64..10_000,
+        ) {
+            let page: Vec<Slot> = slots
+                .into_iter()
+                .filter(|slot| *slot <= end_slot)
+                .take(limit)
+                .collect();
+            match next_page_start(&page, limit, end_slot) {
+                Some(next_slot) => {
+                    prop_assert_eq!(page.len(), limit);
+                    // Pages neither overlap nor skip slots, and stay within the range
+                    prop_assert_eq!(Some(next_slot - 1), page.last().copied());
+                    prop_assert!(next_slot <= end_slot);
+                }
+                None => prop_assert!(page.len() < limit || page.last() == Some(&end_slot)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_range_too_large() {
+        let range_too_large = || Err::<Vec<Slot>, _>(meta_storage::Error::RangeTooLarge(1_000));
+        let stub = StorageStub::default()
+            .with("getBlocks", range_too_large)
+            .with("getBlocksWithLimit", range_too_large);
+        let processor = JsonRpcRequestProcessor::new(
+            JsonRpcConfig::default(),
+            Arc::new(RwLock::new(Exit::default())),
+            Some(stub_router(stub).await),
+        );
+
+        let errors = [
+            processor.get_blocks(0, Some(10), None).await.unwrap_err(),
+            processor.get_blocks_with_limit(0, 10, None).await.unwrap_err(),
+        ];
+        for err in errors {
+            assert_eq!(
+                err.code,
+                jsonrpc_core::ErrorCode::ServerError(JSON_RPC_RANGE_TOO_LARGE)
+            );
+            assert_eq!(err.data, Some(json!({ "maxRows": 1_000 })));
+        }
+    }
+
+    #[cfg(feature = "fault-injection")]
+    #[tokio::test]
+    async fn test_storage_busy() {
+        use crate::{
+            custom_error::JSON_RPC_STORAGE_BUSY,
+            fault_injection::{FaultInjectionConfig, FaultKind},
+        };
+
+        let router = stub_router(sample_stub()).await;
+        router.faults().set(Some(FaultInjectionConfig {
+            error_rate: 1.0,
+            error: FaultKind::Busy,
+            ..FaultInjectionConfig::default()
+        }));
+        let processor = JsonRpcRequestProcessor::new(
+            JsonRpcConfig::default(),
+            Arc::new(RwLock::new(Exit::default())),
+            Some(router),
+        );
+
+        let errors = [
+            processor.get_block_time(5).await.unwrap_err(),
+            processor.get_blocks(0, Some(10), None).await.unwrap_err(),
+            processor.get_blocks_with_limit(0, 10, None).await.unwrap_err(),
+        ];
+        for err in errors {
+            assert_eq!(
+                err.code,
+                jsonrpc_core::ErrorCode::ServerError(JSON_RPC_STORAGE_BUSY)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stale_stored_range() {
+        let failing = Arc::new(AtomicBool::new(false));
+        let stub = sample_stub().with("getFirstAvailableBlock", {
+            let failing = failing.clone();
+            move || {
+                if failing.load(Ordering::Relaxed) {
+                    Err(meta_storage::Error::StorageBusy)
+                } else {
+                    Ok(Some::<Slot>(5))
+                }
+            }
+        });
+        let processor = JsonRpcRequestProcessor::new(
+            JsonRpcConfig::default(),
+            Arc::new(RwLock::new(Exit::default())),
+            Some(stub_router(stub).await),
+        );
+        let state = |processor: JsonRpcRequestProcessor| async move {
+            let (status, _) = processor.health_status().await;
+            (status.state, status.reasons)
+        };
+
+        processor.refresh_stored_range().await;
+        assert_eq!(state(processor.clone()).await, (HealthState::Ok, vec![]));
+
+        // The last known range keeps being served while it cannot be refreshed
+        failing.store(true, Ordering::Relaxed);
+        processor.refresh_stored_range().await;
+        assert_eq!(processor.stored_range().map(|range| range.first_slot), Some(5));
+        assert_eq!(
+            state(processor.clone()).await,
+            (HealthState::Degraded, vec!["stored range stale".to_string()])
+        );
+
+        failing.store(false, Ordering::Relaxed);
+        processor.refresh_stored_range().await;
+        assert_eq!(state(processor.clone()).await, (HealthState::Ok, vec![]));
+    }
+}
@@ -0,0 +1,111 @@
+use {
+    crate::{custom_error::RpcCustomError, request_processor::JsonRpcRequestProcessor},
+    jsonrpc_core::{
+        futures::{
+            future::Either,
+            FutureExt,
+        },
+        middleware::{Middleware, NoopFuture},
+        BoxFuture, Call, Failure, Output,
+    },
+    serde::Serialize,
+    std::{future::Future, io},
+};
+
+/// Counts serialized bytes, failing once `limit` is exceeded so oversized responses
+/// are never fully serialized
+struct SizeLimitedWriter {
+    written: usize,
+    limit: usize,
+}
+
+impl io::Write for SizeLimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written += buf.len();
+        if self.written > self.limit {
+            return Err(io::Error::new(io::ErrorKind::Other, "response too large"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn exceeds<T: Serialize>(value: &T, limit: usize) -> bool {
+    let mut writer = SizeLimitedWriter { written: 0, limit };
+    serde_json::to_writer(&mut writer, value).is_err()
+}
+
+/// JSON-RPC middleware replacing responses larger than a configured size with an
+/// error asking the client to narrow its request
+#[derive(Clone, Default)]
+pub struct ResponseSizeMiddleware {
+    max_response_size: Option<usize>,
+}
+
+impl ResponseSizeMiddleware {
+    pub fn new(max_response_size: Option<usize>) -> Self {
+        Self { max_response_size }
+    }
+}
+
+impl Middleware<JsonRpcRequestProcessor> for ResponseSizeMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = BoxFuture<Option<Output>>;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let max_response_size = match self.max_response_size {
+            Some(max_response_size) => max_response_size,
+            None => return Either::Right(next(call, meta)),
+        };
+        Either::Left(
+            next(call, meta)
+                .map(move |output| match output {
+                    Some(Output::Success(success)) if exceeds(&success, max_response_size) => {
+                        warn!(
+                            "Response to request {:?} exceeds {} bytes",
+                            success.id, max_response_size
+                        );
+                        Some(Output::Failure(Failure {
+                            jsonrpc: success.jsonrpc,
+                            error: RpcCustomError::ResponseTooLarge { max_response_size }.into(),
+                            id: success.id,
+                        }))
+                    }
+                    output => output,
+                })
+                .boxed(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        jsonrpc_core::{Id, Success, Value, Version},
+    };
+
+    #[test]
+    fn test_exceeds() {
+        let output = Output::Success(Success {
+            jsonrpc: Some(Version::V2),
+            result: Value::Array((0..100).map(Value::from).collect()),
+            id: Id::Num(1),
+        });
+        let size = serde_json::to_vec(&output).unwrap().len();
+        assert!(!exceeds(&output, size));
+        assert!(exceeds(&output, size - 1));
+    }
+}
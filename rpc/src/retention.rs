@@ -0,0 +1,153 @@
+#[cfg(feature = "archive")]
+use crate::archive::{BlockArchive, ARCHIVE_PARTITION_SLOTS};
+use {
+    solana_sdk::clock::Slot,
+    solana_storage_mysql::meta_storage::{self, MetaStorage},
+    std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// How much block metadata to keep and how fast to delete the rest
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    /// Keep this many slots behind the latest stored slot
+    pub slots: Option<u64>,
+    /// Keep blocks whose block time is within this window
+    pub max_age: Option<Duration>,
+    /// Rows deleted per statement
+    pub batch_size: usize,
+    /// Pause between batches, bounding the write load put on the database
+    pub batch_interval: Duration,
+    /// How often the retention boundary is recomputed
+    pub check_interval: Duration,
+}
+
+/// S3 location of the archive purged block times are exported to and served from
+#[derive(Debug, Clone)]
+pub struct ArchiveConfig {
+    pub s3_bucket: String,
+    /// Key prefix of the archive files inside the bucket
+    pub s3_prefix: String,
+}
+
+/// Periodically deletes block metadata that falls outside the retention window.
+///
+/// The boundary is published before any row is deleted, so `getFirstAvailableBlock`
+/// never reports a slot that is about to be purged.
+///
+/// With an archive configured, whole archive partitions are exported before their rows
+/// are deleted, so the boundary is rounded down to a partition start.
+pub struct Purger {
+    storage: MetaStorage,
+    config: RetentionConfig,
+    boundary: Arc<AtomicU64>,
+    #[cfg(feature = "archive")]
+    archive: Option<Arc<BlockArchive>>,
+}
+
+impl Purger {
+    pub fn new(storage: MetaStorage, config: RetentionConfig, boundary: Arc<AtomicU64>) -> Self {
+        Self {
+            storage,
+            config,
+            boundary,
+            #[cfg(feature = "archive")]
+            archive: None,
+        }
+    }
+
+    #[cfg(feature = "archive")]
+    pub fn with_archive(mut self, archive: Arc<BlockArchive>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.config.check_interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.purge().await {
+                warn!("Block metadata purge failed: {}", err);
+            }
+        }
+    }
+
+    async fn purge(&self) -> meta_storage::Result<()> {
+        let boundary = match self.retention_boundary().await? {
+            Some(boundary) => boundary,
+            None => return Ok(()),
+        };
+        #[cfg(feature = "archive")]
+        let boundary = match &self.archive {
+            Some(archive) => {
+                let boundary = boundary - boundary % ARCHIVE_PARTITION_SLOTS;
+                if let Some(first_slot) = self.storage.get_first_available_block().await? {
+                    for partition in BlockArchive::partition_of(first_slot)
+                        ..BlockArchive::partition_of(boundary)
+                    {
+                        if let Err(err) = archive.archive_partition(&self.storage, partition).await {
+                            // Never delete rows that are not safely archived
+                            warn!("Failed to archive partition {}: {}", partition, err);
+                            return Ok(());
+                        }
+                    }
+                }
+                boundary
+            }
+            None => boundary,
+        };
+        // The boundary never moves backwards, even if the retention window is widened
+        let boundary = self.boundary.fetch_max(boundary, Ordering::AcqRel).max(boundary);
+
+        let mut deleted = 0;
+        loop {
+            let batch = self
+                .storage
+                .purge_blocks_below(boundary, self.config.batch_size)
+                .await?;
+            deleted += batch;
+            if batch < self.config.batch_size as u64 {
+                break;
+            }
+            tokio::time::sleep(self.config.batch_interval).await;
+        }
+
+        if deleted > 0 {
+            info!("Purged {} blocks below slot {}", deleted, boundary);
+        }
+        solana_metrics::datapoint_info!(
+            "block-meta-rpc-purge",
+            ("boundary_slot", boundary as i64, i64),
+            ("rows_deleted", deleted as i64, i64),
+        );
+        Ok(())
+    }
+
+    /// The lowest slot to keep: the stricter of the slot and the age window
+    async fn retention_boundary(&self) -> meta_storage::Result<Option<Slot>> {
+        let by_slots = match self.config.slots {
+            Some(slots) => self
+                .storage
+                .get_slot()
+                .await?
+                .map(|latest_slot| latest_slot.saturating_sub(slots)),
+            None => None,
+        };
+        let by_age = match self.config.max_age {
+            Some(max_age) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                let cutoff = now.saturating_sub(max_age).as_secs() as i64;
+                self.storage.get_first_slot_since(cutoff).await?
+            }
+            None => None,
+        };
+        Ok(by_slots.max(by_age))
+    }
+}
@@ -1,6 +1,21 @@
 use {
     crate::{
-        request_processor::JsonRpcRequestProcessor,
+        build_info::RpcBuildInfo,
+        custom_error::RpcCustomError,
+        health::HealthState,
+        request_processor::{
+            JsonRpcRequestProcessor,
+            RpcBackfillRange,
+            RpcBlockMetaVersion,
+            RpcBlockReward,
+            RpcBlockSummary,
+            RpcBlocksPageConfig,
+            RpcDeprecatedArgument,
+            RpcFeeStats,
+            RpcLimits,
+            RpcSchemaInfo,
+            RpcSlotPage,
+        },
     },
     jsonrpc_core::{
         BoxFuture,
@@ -30,7 +45,7 @@ pub mod storage_rpc_minimal {
         type Metadata;
 
         #[rpc(meta, name = "getHealth")]
-        fn get_health(&self, meta: Self::Metadata) -> Result<String>;
+        fn get_health(&self, meta: Self::Metadata) -> BoxFuture<Result<String>>;
 
         #[rpc(meta, name = "getSlot")]
         fn get_slot(&self, meta: Self::Metadata, config: Option<RpcContextConfig>) -> BoxFuture<Result<Slot>>;
@@ -42,16 +57,36 @@ pub mod storage_rpc_minimal {
             config: Option<RpcContextConfig>,
         ) -> BoxFuture<Result<u64>>;
 
+        #[rpc(meta, name = "getTransactionCount")]
+        fn get_transaction_count(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcContextConfig>,
+        ) -> BoxFuture<Result<u64>>;
+
         #[rpc(meta, name = "getVersion")]
         fn get_version(&self, meta: Self::Metadata) -> Result<RpcVersionInfo>;
+
+        #[rpc(meta, name = "getLimits")]
+        fn get_limits(&self, meta: Self::Metadata) -> Result<RpcLimits>;
+
+        #[rpc(meta, name = "getBuildInfo")]
+        fn get_build_info(&self, meta: Self::Metadata) -> Result<RpcBuildInfo>;
     }
 
     pub struct MinimalImpl;
     impl Minimal for MinimalImpl {
         type Metadata = JsonRpcRequestProcessor;
 
-        fn get_health(&self, _meta: Self::Metadata) -> Result<String> {
-            Ok("ok".to_string())
+        fn get_health(&self, meta: Self::Metadata) -> BoxFuture<Result<String>> {
+            Box::pin(async move {
+                meta.check_readiness().await?;
+                let (status, lag) = meta.health_status().await;
+                match status.state {
+                    HealthState::Unhealthy => Err(RpcCustomError::NodeUnhealthy { lag }.into()),
+                    state => Ok(state.as_str().to_string()),
+                }
+            })
         }
 
         fn get_slot(&self, meta: Self::Metadata, config: Option<RpcContextConfig>) -> BoxFuture<Result<Slot>> {
@@ -68,13 +103,28 @@ pub mod storage_rpc_minimal {
             Box::pin( async move { meta.get_block_height(config.unwrap_or_default()).await } )
         }
 
-        fn get_version(&self, _: Self::Metadata) -> Result<RpcVersionInfo> {
+        fn get_transaction_count(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcContextConfig>,
+        ) -> BoxFuture<Result<u64>> {
+            debug!("get_transaction_count rpc request received");
+            Box::pin(async move { meta.get_transaction_count(config.unwrap_or_default()).await })
+        }
+
+        fn get_version(&self, meta: Self::Metadata) -> Result<RpcVersionInfo> {
             debug!("get_version rpc request received");
-            let version = solana_version::Version::default();
-            Ok(RpcVersionInfo {
-                solana_core: version.to_string(),
-                feature_set: Some(version.feature_set),
-            })
+            Ok(meta.get_version())
+        }
+
+        fn get_limits(&self, meta: Self::Metadata) -> Result<RpcLimits> {
+            debug!("get_limits rpc request received");
+            Ok(meta.get_limits())
+        }
+
+        fn get_build_info(&self, meta: Self::Metadata) -> Result<RpcBuildInfo> {
+            debug!("get_build_info rpc request received");
+            Ok(meta.get_build_info())
         }
     }
 }
@@ -114,8 +164,105 @@ pub mod storage_rpc_full {
             commitment: Option<CommitmentConfig>,
         ) -> BoxFuture<Result<Vec<Slot>>>;
 
+        #[rpc(meta, name = "getBlockCount")]
+        fn get_block_count(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Slot,
+            end_slot: Slot,
+            config: Option<RpcContextConfig>,
+        ) -> BoxFuture<Result<u64>>;
+
         #[rpc(meta, name = "getFirstAvailableBlock")]
         fn get_first_available_block(&self, meta: Self::Metadata) -> BoxFuture<Result<Slot>>;
+
+        #[rpc(meta, name = "getBlockSummary")]
+        fn get_block_summary(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+            config: Option<RpcBlockConfig>,
+        ) -> BoxFuture<Result<RpcBlockSummary>>;
+
+        #[rpc(meta, name = "getBlockRewards")]
+        fn get_block_rewards(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> BoxFuture<Result<Vec<RpcBlockReward>>>;
+
+        #[rpc(meta, name = "blocksExist")]
+        fn blocks_exist(
+            &self,
+            meta: Self::Metadata,
+            slots: Vec<Slot>,
+        ) -> BoxFuture<Result<Vec<bool>>>;
+
+        #[rpc(meta, name = "getBlockMetaVersion")]
+        fn get_block_meta_version(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> BoxFuture<Result<Option<RpcBlockMetaVersion>>>;
+
+        #[rpc(meta, name = "getBlockHeightForSlot")]
+        fn get_block_height_for_slot(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> BoxFuture<Result<Option<u64>>>;
+
+        #[rpc(meta, name = "getSlotForBlockHeight")]
+        fn get_slot_for_block_height(
+            &self,
+            meta: Self::Metadata,
+            block_height: u64,
+        ) -> BoxFuture<Result<Option<Slot>>>;
+
+        #[rpc(meta, name = "getSlotForBlockhash")]
+        fn get_slot_for_blockhash(
+            &self,
+            meta: Self::Metadata,
+            blockhash: String,
+        ) -> BoxFuture<Result<Option<Slot>>>;
+
+        #[rpc(meta, name = "getBlockFeeStats")]
+        fn get_block_fee_stats(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> BoxFuture<Result<Option<RpcFeeStats>>>;
+
+        #[rpc(meta, name = "getFeeStatsRange")]
+        fn get_fee_stats_range(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Slot,
+            end_slot: Slot,
+        ) -> BoxFuture<Result<RpcFeeStats>>;
+
+        #[rpc(meta, name = "getRecentPrioritizationFees")]
+        fn get_recent_prioritization_fees(
+            &self,
+            meta: Self::Metadata,
+            pubkey_strs: Option<Vec<String>>,
+        ) -> BoxFuture<Result<Vec<RpcPrioritizationFee>>>;
+
+        #[rpc(meta, name = "getInflationReward")]
+        fn get_inflation_reward(
+            &self,
+            meta: Self::Metadata,
+            address_strs: Vec<String>,
+            config: Option<RpcEpochConfig>,
+        ) -> BoxFuture<Result<Vec<Option<RpcInflationReward>>>>;
+
+        #[rpc(meta, name = "getBackfillStatus")]
+        fn get_backfill_status(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Slot,
+            end_slot: Option<Slot>,
+        ) -> BoxFuture<Result<Vec<RpcBackfillRange>>>;
     }
 
     pub struct FullImpl;
@@ -141,6 +288,20 @@ pub mod storage_rpc_full {
             })
         }
 
+        fn get_block_count(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Slot,
+            end_slot: Slot,
+            config: Option<RpcContextConfig>,
+        ) -> BoxFuture<Result<u64>> {
+            debug!(
+                "get_block_count rpc request received: {}-{}",
+                start_slot, end_slot
+            );
+            Box::pin(async move { meta.get_block_count(start_slot, end_slot, config).await })
+        }
+
         fn get_blocks_with_limit(
             &self,
             meta: Self::Metadata,
@@ -170,5 +331,317 @@ pub mod storage_rpc_full {
             debug!("get_first_available_block rpc request received");
             Box::pin(async move { Ok(meta.get_first_available_block().await) })
         }
+
+        fn get_block_summary(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+            config: Option<RpcBlockConfig>,
+        ) -> BoxFuture<Result<RpcBlockSummary>> {
+            debug!("get_block_summary rpc request received: {}", slot);
+            Box::pin(async move { meta.get_block_summary(slot, config).await })
+        }
+
+        fn get_block_rewards(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> BoxFuture<Result<Vec<RpcBlockReward>>> {
+            debug!("get_block_rewards rpc request received: {}", slot);
+            Box::pin(async move { meta.get_block_rewards(slot).await })
+        }
+
+        fn blocks_exist(
+            &self,
+            meta: Self::Metadata,
+            slots: Vec<Slot>,
+        ) -> BoxFuture<Result<Vec<bool>>> {
+            debug!("blocks_exist rpc request received: {} slots", slots.len());
+            Box::pin(async move { meta.blocks_exist(slots).await })
+        }
+
+        fn get_block_meta_version(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> BoxFuture<Result<Option<RpcBlockMetaVersion>>> {
+            debug!("get_block_meta_version rpc request received: {}", slot);
+            Box::pin(async move { meta.get_block_meta_version(slot).await })
+        }
+
+        fn get_block_height_for_slot(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> BoxFuture<Result<Option<u64>>> {
+            debug!("get_block_height_for_slot rpc request received: {}", slot);
+            Box::pin(async move { meta.get_block_height_for_slot(slot).await })
+        }
+
+        fn get_slot_for_block_height(
+            &self,
+            meta: Self::Metadata,
+            block_height: u64,
+        ) -> BoxFuture<Result<Option<Slot>>> {
+            debug!("get_slot_for_block_height rpc request received: {}", block_height);
+            Box::pin(async move { meta.get_slot_for_block_height(block_height).await })
+        }
+
+        fn get_slot_for_blockhash(
+            &self,
+            meta: Self::Metadata,
+            blockhash: String,
+        ) -> BoxFuture<Result<Option<Slot>>> {
+            debug!("get_slot_for_blockhash rpc request received: {}", blockhash);
+            Box::pin(async move { meta.get_slot_for_blockhash(blockhash).await })
+        }
+
+        fn get_block_fee_stats(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> BoxFuture<Result<Option<RpcFeeStats>>> {
+            debug!("get_block_fee_stats rpc request received: {}", slot);
+            Box::pin(async move { meta.get_block_fee_stats(slot).await })
+        }
+
+        fn get_fee_stats_range(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Slot,
+            end_slot: Slot,
+        ) -> BoxFuture<Result<RpcFeeStats>> {
+            debug!(
+                "get_fee_stats_range rpc request received: {}-{}",
+                start_slot, end_slot
+            );
+            Box::pin(async move { meta.get_fee_stats_range(start_slot, end_slot).await })
+        }
+
+        fn get_recent_prioritization_fees(
+            &self,
+            meta: Self::Metadata,
+            pubkey_strs: Option<Vec<String>>,
+        ) -> BoxFuture<Result<Vec<RpcPrioritizationFee>>> {
+            debug!(
+                "get_recent_prioritization_fees rpc request received: {:?} pubkeys",
+                pubkey_strs.as_ref().map(|pubkey_strs| pubkey_strs.len())
+            );
+            Box::pin(async move { meta.get_recent_prioritization_fees(pubkey_strs).await })
+        }
+
+        fn get_inflation_reward(
+            &self,
+            meta: Self::Metadata,
+            address_strs: Vec<String>,
+            config: Option<RpcEpochConfig>,
+        ) -> BoxFuture<Result<Vec<Option<RpcInflationReward>>>> {
+            debug!(
+                "get_inflation_reward rpc request received: {:?}",
+                address_strs.len()
+            );
+            Box::pin(async move { meta.get_inflation_reward(address_strs, config).await })
+        }
+
+        fn get_backfill_status(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Slot,
+            end_slot: Option<Slot>,
+        ) -> BoxFuture<Result<Vec<RpcBackfillRange>>> {
+            debug!(
+                "get_backfill_status rpc request received: {}-{:?}",
+                start_slot, end_slot
+            );
+            Box::pin(async move { meta.get_backfill_status(start_slot, end_slot).await })
+        }
+    }
+}
+
+// Version 2 of the interface, served under `/v2`. Methods fail instead of answering
+// zero-defaults when storage is unavailable or empty, and range methods are paginated.
+pub mod storage_rpc_v2 {
+    use super::*;
+    #[rpc]
+    pub trait V2 {
+        type Metadata;
+
+        #[rpc(meta, name = "getSlot")]
+        fn get_slot(&self, meta: Self::Metadata, config: Option<RpcContextConfig>) -> BoxFuture<Result<Slot>>;
+
+        #[rpc(meta, name = "getBlockHeight")]
+        fn get_block_height(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcContextConfig>,
+        ) -> BoxFuture<Result<u64>>;
+
+        #[rpc(meta, name = "getFirstAvailableBlock")]
+        fn get_first_available_block(&self, meta: Self::Metadata) -> BoxFuture<Result<Slot>>;
+
+        #[rpc(meta, name = "getBlockTime")]
+        fn get_block_time(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> BoxFuture<Result<Option<UnixTimestamp>>>;
+
+        #[rpc(meta, name = "getBlocks")]
+        fn get_blocks(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Slot,
+            end_slot: Option<Slot>,
+            config: Option<RpcBlocksPageConfig>,
+        ) -> BoxFuture<Result<RpcSlotPage>>;
     }
-}
\ No newline at end of file
+
+    pub struct V2Impl;
+    impl V2 for V2Impl {
+        type Metadata = JsonRpcRequestProcessor;
+
+        fn get_slot(&self, meta: Self::Metadata, config: Option<RpcContextConfig>) -> BoxFuture<Result<Slot>> {
+            debug!("v2 get_slot rpc request received");
+            Box::pin(async move { meta.get_slot_strict(config.unwrap_or_default()).await })
+        }
+
+        fn get_block_height(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcContextConfig>,
+        ) -> BoxFuture<Result<u64>> {
+            debug!("v2 get_block_height rpc request received");
+            Box::pin(async move { meta.get_block_height_strict(config.unwrap_or_default()).await })
+        }
+
+        fn get_first_available_block(&self, meta: Self::Metadata) -> BoxFuture<Result<Slot>> {
+            debug!("v2 get_first_available_block rpc request received");
+            Box::pin(async move { meta.get_first_available_block_strict().await })
+        }
+
+        fn get_block_time(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+        ) -> BoxFuture<Result<Option<UnixTimestamp>>> {
+            debug!("v2 get_block_time rpc request received: {}", slot);
+            Box::pin(async move {
+                match meta.get_block_time(slot).await? {
+                    Some(block_time) => Ok(Some(block_time)),
+                    None => Err(RpcCustomError::StorageUnavailable.into()),
+                }
+            })
+        }
+
+        fn get_blocks(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Slot,
+            end_slot: Option<Slot>,
+            config: Option<RpcBlocksPageConfig>,
+        ) -> BoxFuture<Result<RpcSlotPage>> {
+            debug!(
+                "v2 get_blocks rpc request received: {}-{:?}",
+                start_slot, end_slot
+            );
+            Box::pin(async move { meta.get_blocks_paginated(start_slot, end_slot, config).await })
+        }
+    }
+}
+
+// Admin RPC interface, only exposed when explicitly enabled
+pub mod storage_rpc_admin {
+    use super::*;
+    #[rpc]
+    pub trait Admin {
+        type Metadata;
+
+        #[rpc(meta, name = "markSlotOrphaned")]
+        fn mark_slot_orphaned(&self, meta: Self::Metadata, slot: Slot) -> BoxFuture<Result<()>>;
+
+        #[rpc(meta, name = "unmarkSlotOrphaned")]
+        fn unmark_slot_orphaned(&self, meta: Self::Metadata, slot: Slot) -> BoxFuture<Result<()>>;
+
+        #[rpc(meta, name = "getDeprecatedArguments")]
+        fn get_deprecated_arguments(&self, meta: Self::Metadata) -> Result<Vec<RpcDeprecatedArgument>>;
+
+        #[rpc(meta, name = "getSchemaInfo")]
+        fn get_schema_info(&self, meta: Self::Metadata) -> BoxFuture<Result<RpcSchemaInfo>>;
+
+        /// Close the HTTP listener and bind a new one, picking up reloaded listener
+        /// settings
+        #[rpc(meta, name = "restartRpc")]
+        fn restart_rpc(&self, meta: Self::Metadata) -> Result<()>;
+    }
+
+    pub struct AdminImpl;
+    impl Admin for AdminImpl {
+        type Metadata = JsonRpcRequestProcessor;
+
+        fn mark_slot_orphaned(&self, meta: Self::Metadata, slot: Slot) -> BoxFuture<Result<()>> {
+            debug!("mark_slot_orphaned rpc request received: {}", slot);
+            Box::pin(async move { meta.set_slot_orphaned(slot, true).await })
+        }
+
+        fn unmark_slot_orphaned(&self, meta: Self::Metadata, slot: Slot) -> BoxFuture<Result<()>> {
+            debug!("unmark_slot_orphaned rpc request received: {}", slot);
+            Box::pin(async move { meta.set_slot_orphaned(slot, false).await })
+        }
+
+        fn get_deprecated_arguments(&self, meta: Self::Metadata) -> Result<Vec<RpcDeprecatedArgument>> {
+            debug!("get_deprecated_arguments rpc request received");
+            Ok(meta.get_deprecated_arguments())
+        }
+
+        fn get_schema_info(&self, meta: Self::Metadata) -> BoxFuture<Result<RpcSchemaInfo>> {
+            debug!("get_schema_info rpc request received");
+            Box::pin(async move { meta.get_schema_info().await })
+        }
+
+        fn restart_rpc(&self, meta: Self::Metadata) -> Result<()> {
+            debug!("restart_rpc rpc request received");
+            meta.restart_rpc()
+        }
+    }
+}
+
+// Storage fault injection, exposed with the admin interface in builds with the
+// `fault-injection` feature
+#[cfg(feature = "fault-injection")]
+pub mod storage_rpc_fault_injection {
+    use {super::*, crate::fault_injection::FaultInjectionConfig};
+    #[rpc]
+    pub trait FaultInjection {
+        type Metadata;
+
+        /// Inject `config` into every storage call; `null` turns injection off
+        #[rpc(meta, name = "setFaultInjection")]
+        fn set_fault_injection(
+            &self,
+            meta: Self::Metadata,
+            config: Option<FaultInjectionConfig>,
+        ) -> Result<Option<FaultInjectionConfig>>;
+
+        #[rpc(meta, name = "getFaultInjection")]
+        fn get_fault_injection(&self, meta: Self::Metadata) -> Result<Option<FaultInjectionConfig>>;
+    }
+
+    pub struct FaultInjectionImpl;
+    impl FaultInjection for FaultInjectionImpl {
+        type Metadata = JsonRpcRequestProcessor;
+
+        fn set_fault_injection(
+            &self,
+            meta: Self::Metadata,
+            config: Option<FaultInjectionConfig>,
+        ) -> Result<Option<FaultInjectionConfig>> {
+            debug!("set_fault_injection rpc request received: {:?}", config);
+            meta.set_fault_injection(config)
+        }
+
+        fn get_fault_injection(&self, meta: Self::Metadata) -> Result<Option<FaultInjectionConfig>> {
+            debug!("get_fault_injection rpc request received");
+            meta.get_fault_injection()
+        }
+    }
+}
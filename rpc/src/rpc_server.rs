@@ -1,37 +1,80 @@
 use {
     crate::{
+        interceptor::Interceptor,
+        listener::ListenerConfig,
+        middleware::RestHandler,
         request_processor::{JsonRpcConfig},
-        rpc_service::JsonRpcService,
+        rpc_service::{JsonRpcService, ServiceExtensions},
+        storage_router::StorageRouter,
     },
     log::*,
     solana_sdk::exit::Exit,
+    solana_storage_mysql::meta_storage::MetaStorage,
     std::{
-        net::{IpAddr, Ipv4Addr, SocketAddr},
+        error::Error,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
         path::{Path, PathBuf},
-        process::exit,
-        sync::{
-            atomic::{AtomicBool, Ordering},
-            Arc, RwLock,
-        },
+        sync::{Arc, RwLock},
     },
+    tokio::sync::watch,
 };
 
+/// Token stopping an `RpcServer` from any thread or task, and letting other
+/// components wait for the server to be stopped
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    exit: Arc<RwLock<Exit>>,
+    stopped: watch::Receiver<bool>,
+}
+
+impl ShutdownHandle {
+    /// Ask the server to stop; returns immediately
+    pub fn shutdown(&self) {
+        self.exit.write().unwrap().exit();
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        *self.stopped.borrow()
+    }
+
+    /// Resolves once shutdown has been triggered, by this or any other handle
+    pub async fn wait(&self) {
+        let mut stopped = self.stopped.clone();
+        // The sender lives in the registered exit callback, an error means it already ran
+        let _ = stopped.wait_for(|stopped| *stopped).await;
+    }
+}
+
 /// Central struct for managing the RPC server.
+///
+/// Also usable as a library: every failure is returned to the caller, storage can be
+/// provided instead of configured, and `shutdown` stops the server from async code.
 pub struct RpcServer {
     config: RpcServerConfig,
     exit: Arc<RwLock<Exit>>,
     json_rpc_service: Option<JsonRpcService>,
     actual_rpc_addr: Option<SocketAddr>,
+    extensions: ServiceExtensions,
+    stopped: watch::Receiver<bool>,
 }
 
 impl RpcServer {
     /// Create a new server with default config
     pub fn new() -> Self {
+        let exit: Arc<RwLock<Exit>> = Arc::default();
+        let (stopped_sender, stopped) = watch::channel(false);
+        exit.write()
+            .unwrap()
+            .register_exit(Box::new(move || {
+                stopped_sender.send_replace(true);
+            }));
         Self {
             config: RpcServerConfig::default(),
-            exit: Arc::default(),
+            exit,
             json_rpc_service: None,
             actual_rpc_addr: None,
+            extensions: ServiceExtensions::default(),
+            stopped,
         }
     }
 
@@ -53,42 +96,97 @@ impl RpcServer {
         self
     }
 
-    /// Start the server, spawning the JSON-RPC thread
-    pub fn start(&mut self, log_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    /// Serve from an already initialized storage instead of the MySQL config
+    pub fn with_meta_storage(mut self, meta_storage: MetaStorage) -> Self {
+        self.extensions.metadata_storage = Some(StorageRouter::new(meta_storage));
+        self
+    }
+
+    /// Serve from an already initialized router, e.g. one with a canary backend
+    pub fn with_storage_router(mut self, storage_router: StorageRouter) -> Self {
+        self.extensions.metadata_storage = Some(storage_router);
+        self
+    }
+
+    /// Serve an additional REST endpoint, see `RestHandlers::register`
+    pub fn with_rest_handler(
+        mut self,
+        path: impl Into<String>,
+        handler: impl RestHandler + 'static,
+    ) -> Self {
+        self.extensions.rest_handlers.register(path, handler);
+        self
+    }
+
+    /// Run `interceptor` around every HTTP request. Interceptors see requests in the
+    /// order they are added, and responses in reverse order.
+    pub fn with_interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.extensions.interceptors.push(interceptor);
+        self
+    }
+
+    /// Reload the CORS, API key and trusted proxy settings with `loader` when the `restartRpc`
+    /// admin method rebinds the listener
+    pub fn with_listener_config_loader(
+        mut self,
+        loader: impl Fn() -> Result<ListenerConfig, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.extensions.listener_config_loader = Some(Arc::new(loader));
+        self
+    }
+
+    /// Start the server, spawning the JSON-RPC thread.
+    ///
+    /// The server runs on its own runtime, so this must not be called from async code;
+    /// use `tokio::task::spawn_blocking` there.
+    pub fn start(&mut self, log_path: &Path) -> Result<(), Box<dyn Error>> {
         let log_path = Self::init_log_dir(log_path)?;
 
         let rpc_addr = SocketAddr::new(self.config.bind_ip_addr, self.config.rpc_port);
 
         info!("Starting RPC server at {}", rpc_addr);
 
-        // Register an exit signal
-        let exit_flag = Arc::new(AtomicBool::new(false));
-        {
-            let exit_flag = exit_flag.clone();
-            self.exit
-                .write()
-                .unwrap()
-                .register_exit(Box::new(move || exit_flag.store(true, Ordering::Relaxed)));
-        }
-
         // Start the JSON RPC service
         let json_rpc_service = JsonRpcService::new(
             rpc_addr,
             self.config.rpc_config.clone(),
             &log_path,
             self.exit.clone(),
+            std::mem::take(&mut self.extensions),
         )?;
 
+        // With port 0 the OS picks the port, only known once bound
+        self.actual_rpc_addr = Some(json_rpc_service.rpc_addr());
         self.json_rpc_service = Some(json_rpc_service);
-        self.actual_rpc_addr = Some(rpc_addr);
 
         Ok(())
     }
 
-    /// If you need to know the URL from outside
+    /// URL of the running server, with the actual port when bound to port 0. An
+    /// unspecified bind address is reported as loopback so the URL can be dialed.
     pub fn rpc_url(&self) -> Option<String> {
+        self.actual_rpc_addr.map(|mut addr| {
+            if addr.ip().is_unspecified() {
+                addr.set_ip(match addr.ip() {
+                    IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                });
+            }
+            format!("http://{}", addr)
+        })
+    }
+
+    /// Address the server is bound to
+    pub fn rpc_addr(&self) -> Option<SocketAddr> {
         self.actual_rpc_addr
-            .map(|addr| format!("http://{}", addr))
+    }
+
+    /// Handle stopping this server from elsewhere, e.g. another component's task
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            exit: self.exit.clone(),
+            stopped: self.stopped.clone(),
+        }
     }
 
     /// Block until the RPC service stops
@@ -98,13 +196,30 @@ impl RpcServer {
         }
     }
 
+    /// Wait, without blocking the async runtime, until the RPC service stops
+    pub async fn join_async(mut self) -> Result<(), String> {
+        let service = match self.json_rpc_service.take() {
+            Some(service) => service,
+            None => return Ok(()),
+        };
+        tokio::task::spawn_blocking(move || service.join())
+            .await
+            .map_err(|err| err.to_string())?
+            .map_err(|_| "RPC service thread panicked".to_string())
+    }
+
+    /// Stop serving and wait until the service thread has exited
+    pub async fn shutdown(self) -> Result<(), String> {
+        self.shutdown_handle().shutdown();
+        self.join_async().await
+    }
+
     /// Validate or create log path
-    fn init_log_dir(path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    fn init_log_dir(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
         if !path.exists() {
-            if let Err(err) = std::fs::create_dir_all(path) {
-                eprintln!("Error creating log directory: {path:?}: {err}");
-                exit(1);
-            }
+            std::fs::create_dir_all(path).map_err(|err| {
+                format!("Error creating log directory {}: {err}", path.display())
+            })?;
         }
         Ok(path.to_path_buf())
     }
@@ -1,31 +1,62 @@
+#[cfg(feature = "archive")]
+use crate::archive::BlockArchive;
+#[cfg(feature = "fault-injection")]
+use crate::rpc::storage_rpc_fault_injection::*;
 use {
     crate::{
+        cache_control::RpcCachePolicy,
+        concurrency::ConcurrencyLimitMiddleware,
+        error_metrics::ErrorMetricsMiddleware,
+        feature_flags::{FeatureFlagMiddleware, FeatureFlags, FileFeatureFlagProvider},
+        interceptor::Interceptors,
+        listener::{ListenerConfig, ListenerConfigLoader, ListenerControl},
+        params::ParamsMiddleware,
+        response_limit::ResponseSizeMiddleware,
         rpc::{
+            storage_rpc_admin::*,
             storage_rpc_full::*,
+            storage_rpc_v2::*,
             storage_rpc_minimal::*,
         },
         request_processor::*,
-        middleware::RpcRequestMiddleware,
+        retention::Purger,
+        slot_index::SlotIndexLoader,
+        middleware::{
+            client_key,
+            timeout_budget,
+            trace_id,
+            RestHandlers,
+            RpcRequestMiddleware,
+        },
+        middleware_metrics::MiddlewareMetrics,
+        storage_router::StorageRouter,
+        throttle::ThrottleMiddleware,
     },
     crossbeam_channel::unbounded,
     jsonrpc_core::{
         MetaIoHandler
     },
     jsonrpc_http_server::{
-        hyper, AccessControlAllowOrigin, CloseHandle, DomainsValidation,
-        ServerBuilder,
+        hyper, Server, ServerBuilder,
     },
     solana_perf::thread::renice_this_thread,
     solana_sdk::{
         exit::Exit,
     },
     solana_storage_mysql::{
+        meta_storage::{
+            MetaStorage,
+            MetaStorageConfig,
+        },
         mysql::{
             MySQLConfig,
         }
     },
     std::{
+        any::Any,
+        future::Future,
         net::SocketAddr,
+        panic::{self, AssertUnwindSafe},
         path::{
             Path,
         },
@@ -33,29 +64,91 @@ use {
             Arc, RwLock,
         },
         thread::{self, Builder, JoinHandle},
+        time::Duration,
     },
+    tokio::sync::watch,
 };
 
+pub use crate::middleware::TIMEOUT_BUDGET_HEADER;
+
+const CACHE_METRICS_INTERVAL: Duration = Duration::from_secs(10);
+const REPLICA_PROBE_INTERVAL: Duration = Duration::from_secs(5);
+/// Longest delay before the first blocks of a new monthly partition are served
+const PARTITION_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+const HEALTH_METRICS_INTERVAL: Duration = Duration::from_secs(10);
+const CACHE_WARM_INTERVAL: Duration = Duration::from_secs(5);
+const STORED_RANGE_INTERVAL: Duration = Duration::from_secs(1);
+const MIDDLEWARE_METRICS_INTERVAL: Duration = Duration::from_secs(10);
+/// Pause before each attempt to bring a dead listener back
+const LISTENER_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// JSON-RPC dispatcher shared by the HTTP, binary and `/v2` endpoints
+pub type RpcIoHandler = MetaIoHandler<JsonRpcRequestProcessor, RpcMiddleware>;
+
+/// Dispatch middleware: feature flags, config fields, the client's cost budget and
+/// concurrency cap are checked before the call, the response size after it. Failures of
+/// any of them are counted by the outermost one.
+///
+/// `jsonrpc_core` chains tuples of at most four middlewares, so the middlewares the
+/// error metrics wrap and the per-client limits are grouped.
+pub type RpcMiddleware = (
+    ErrorMetricsMiddleware,
+    (
+        FeatureFlagMiddleware,
+        ParamsMiddleware,
+        (ThrottleMiddleware, ConcurrencyLimitMiddleware),
+        ResponseSizeMiddleware,
+    ),
+);
+
+/// Components provided by an embedding application instead of built from the config
+#[derive(Default)]
+pub struct ServiceExtensions {
+    /// Storage to serve from, instead of connecting to the configured MySQL backends
+    pub metadata_storage: Option<StorageRouter>,
+    /// REST endpoints served in addition to the built-in routes
+    pub rest_handlers: RestHandlers,
+    /// Hooks run around every HTTP request
+    pub interceptors: Interceptors,
+    /// Listener settings to apply when `restartRpc` rebinds, instead of the ones the
+    /// service was started with
+    pub listener_config_loader: Option<ListenerConfigLoader>,
+}
+
 pub struct JsonRpcService {
     thread_hdl: JoinHandle<()>,
 
     #[cfg(test)]
     pub request_processor: JsonRpcRequestProcessor,
 
-    close_handle: Option<CloseHandle>,
+    listener: ListenerControl,
+    shutdown: Arc<watch::Sender<bool>>,
+    rpc_addr: SocketAddr,
 }
 
 impl JsonRpcService {
+    /// Start serving on `rpc_addr`. Storage is built from `config` unless
+    /// `extensions` provides it.
+    ///
+    /// Must be called outside of an async context: the service runs on its own runtime.
     pub fn new(
         rpc_addr: SocketAddr,
         config: JsonRpcConfig,
         log_path: &Path,
         rpc_service_exit: Arc<RwLock<Exit>>,
+        extensions: ServiceExtensions,
     ) -> Result<Self, String> {
-        info!("rpc bound to {:?}", rpc_addr);
+        let ServiceExtensions {
+            metadata_storage,
+            rest_handlers,
+            interceptors,
+            listener_config_loader,
+        } = extensions;
+        info!("rpc binding to {:?}", rpc_addr);
         info!("rpc configuration: {:?}", config);
         let rpc_threads = 1.max(config.rpc_threads);
         let rpc_niceness_adj = config.rpc_niceness_adj;
+        let storage_niceness_adj = config.storage_niceness_adj;
 
         let runtime = Arc::new(
             tokio::runtime::Builder::new_multi_thread()
@@ -64,43 +157,115 @@ impl JsonRpcService {
                 .thread_name("solRpcEl")
                 .enable_all()
                 .build()
-                .expect("Runtime"),
+                .map_err(|err| format!("Failed to build the RPC runtime: {err}"))?,
         );
+        let storage_runtime = match config.storage_threads {
+            0 => None,
+            storage_threads => Some(
+                tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(storage_threads)
+                    .on_thread_start(move || renice_this_thread(storage_niceness_adj).unwrap())
+                    .thread_name("solRpcStorage")
+                    .enable_all()
+                    .build()
+                    .map_err(|err| format!("Failed to build the storage runtime: {err}"))?,
+            ),
+        };
+        let (shutdown, shutdown_receiver) = watch::channel(false);
+        let shutdown = Arc::new(shutdown);
 
-        let mysql_metadata_storage =
-            if let Some(MySQLConfig {
-                            ref host,
-                            ref port,
-                            ref username,
-                            ref password,
-                            ref db_name,
-                            timeout,
-                        }) = config.rpc_mysql_config
-            {
-                let mysql_config = solana_storage_mysql::meta_storage::MetaStorageConfig {
-                    read_only: true,
-                    timeout,
-                    host: host.clone(),
-                    port: port.clone(),
-                    username: username.clone(),
-                    password: password.clone(),
-                    db_name: db_name.clone(),
-                };
-                runtime
-                    .block_on(solana_storage_mysql::meta_storage::MetaStorage::new_with_config(mysql_config))
-                    .map(|mysql_metadata_storage| {
-                        info!("MySQL metadata storage initialized");
-                        Some(mysql_metadata_storage)
-                    })
-                    .unwrap_or_else(|err| {
-                        error!("Failed to initialize MySQL metadata storage: {:?}", err);
-                        None
-                    })
-            } else {
-                None
-            };
+        let mysql_metadata_storage = match metadata_storage {
+            Some(storage) => {
+                info!("Using the provided metadata storage");
+                Some(storage)
+            }
+            None => config
+                .rpc_mysql_config
+                .as_ref()
+                .and_then(|mysql_config| {
+                    let read_only = !config.admin_rpc && config.retention.is_none();
+                    init_meta_storage(&runtime, mysql_config, "primary", read_only, &shutdown_receiver)
+                })
+                .map(|primary| {
+                    let router = StorageRouter::new(primary);
+                    match config.rpc_mysql_canary_config.as_ref() {
+                        Some(canary_config) if config.canary_traffic_percent > 0 => {
+                            match init_meta_storage(
+                                &runtime,
+                                canary_config,
+                                "canary",
+                                true,
+                                &shutdown_receiver,
+                            ) {
+                                Some(canary) => {
+                                    info!(
+                                        "Routing {}% of storage reads to the canary backend",
+                                        config.canary_traffic_percent
+                                    );
+                                    router.with_canary(canary, config.canary_traffic_percent)
+                                }
+                                None => router,
+                            }
+                        }
+                        _ => router,
+                    }
+                }),
+        };
+        let mysql_metadata_storage = mysql_metadata_storage
+            .map(|router| router.with_latency_buckets(config.storage_latency_buckets.clone()));
+        let mysql_metadata_storage = match &storage_runtime {
+            Some(storage_runtime) => {
+                info!("Running storage calls on {} dedicated threads", config.storage_threads);
+                mysql_metadata_storage.map(|router| router.with_runtime(storage_runtime.handle().clone()))
+            }
+            None => mysql_metadata_storage,
+        };
 
+        let feature_flags = match config.feature_flags_path.as_ref() {
+            Some(path) => {
+                let provider = FileFeatureFlagProvider::new(path.clone())?;
+                info!("Method feature flags loaded from {}", path.display());
+                Some(FeatureFlags::new(Arc::new(provider)))
+            }
+            None => None,
+        };
+
+        // A configured archive that cannot be reached would silently drop purged blocks
+        // from responses, so refuse to start instead
+        #[cfg(feature = "archive")]
+        let archive = config
+            .archive
+            .as_ref()
+            .map(|archive_config| {
+                BlockArchive::new(archive_config)
+                    .map(Arc::new)
+                    .map_err(|err| format!("Failed to initialize the block archive: {}", err))
+            })
+            .transpose()?;
+        #[cfg(not(feature = "archive"))]
+        if config.archive.is_some() {
+            return Err("The block archive requires a build with the `archive` feature".to_string());
+        }
+
+        let cache_warming = config.cache_warm_slots.is_some();
+        let retention = config.retention.clone();
+        let primary_storage = mysql_metadata_storage
+            .as_ref()
+            .map(|router| router.primary().clone());
         let full_api = config.full_api;
+        let admin_rpc = config.admin_rpc;
+        let rest_max_age = config.rest_max_age;
+        let rpc_cache_policy = RpcCachePolicy::new(config.rpc_cache_max_ages.clone());
+        let mut listener_config = ListenerConfig {
+            cors: config.cors.clone(),
+            api_keys: config.api_keys.clone(),
+            trusted_proxies: config.trusted_proxies.clone(),
+        };
+        let max_response_size = config.max_response_size;
+        let strict_params = config.strict_params;
+        let restart_listener = config.restart_listener;
+        let max_in_flight_per_client = config.max_in_flight_per_client;
+        let throttle = config.throttle.clone();
         let max_request_body_size = config
             .max_request_body_size
             .unwrap_or(MAX_REQUEST_BODY_SIZE);
@@ -109,77 +274,313 @@ impl JsonRpcService {
             rpc_service_exit.clone(),
             mysql_metadata_storage,
         );
+        #[cfg(feature = "archive")]
+        let request_processor = match archive {
+            Some(archive) => request_processor.with_archive(archive),
+            None => request_processor,
+        };
+        let build_info_request_processor = request_processor.clone();
+        let auth = if interceptors.is_empty() { "none" } else { "interceptors" };
 
         #[cfg(test)]
             let test_request_processor = request_processor.clone();
 
+        {
+            let request_processor = request_processor.clone();
+            runtime.spawn(until_shutdown(shutdown_receiver.clone(), async move {
+                let mut interval = tokio::time::interval(CACHE_METRICS_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    request_processor.submit_cache_metrics();
+                }
+            }));
+        }
+
+        let middleware_metrics = Arc::new(MiddlewareMetrics::default());
+        {
+            let middleware_metrics = middleware_metrics.clone();
+            runtime.spawn(until_shutdown(shutdown_receiver.clone(), async move {
+                let mut interval = tokio::time::interval(MIDDLEWARE_METRICS_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    middleware_metrics.submit_metrics();
+                }
+            }));
+        }
+
+        {
+            let request_processor = request_processor.clone();
+            runtime.spawn(until_shutdown(shutdown_receiver.clone(), async move {
+                let mut interval = tokio::time::interval(HEALTH_METRICS_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    request_processor.submit_health_metrics().await;
+                }
+            }));
+        }
+
+        {
+            let request_processor = request_processor.clone();
+            runtime.spawn(until_shutdown(shutdown_receiver.clone(), async move {
+                let mut interval = tokio::time::interval(STORED_RANGE_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    request_processor.refresh_stored_range().await;
+                }
+            }));
+        }
+
+        if cache_warming {
+            let request_processor = request_processor.clone();
+            runtime.spawn(until_shutdown(shutdown_receiver.clone(), async move {
+                let mut interval = tokio::time::interval(CACHE_WARM_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    request_processor.warm_block_time_cache().await;
+                }
+            }));
+        }
+
+        if let (Some(slot_index), Some(storage)) =
+            (request_processor.slot_index(), primary_storage.clone())
+        {
+            info!("Slot index enabled");
+            let loader = SlotIndexLoader::new(storage, slot_index);
+            // Loading scans the block table, keep it off the request workers
+            let handle = storage_runtime
+                .as_ref()
+                .map_or_else(|| runtime.handle().clone(), |runtime| runtime.handle().clone());
+            let shutdown_receiver = shutdown_receiver.clone();
+            handle.clone().spawn_blocking(move || {
+                handle.block_on(until_shutdown(shutdown_receiver, loader.run()))
+            });
+        }
+
+        if let (Some(retention), Some(storage)) = (retention, primary_storage) {
+            info!("Block metadata retention enabled: {:?}", retention);
+            let purger = Purger::new(
+                storage,
+                retention,
+                request_processor.retention_boundary(),
+            );
+            #[cfg(feature = "archive")]
+            let purger = match request_processor.archive() {
+                Some(archive) => purger.with_archive(archive),
+                None => purger,
+            };
+            // Deletes are synchronous MySQL calls, keep them off the request workers
+            let handle = storage_runtime
+                .as_ref()
+                .map_or_else(|| runtime.handle().clone(), |runtime| runtime.handle().clone());
+            let shutdown_receiver = shutdown_receiver.clone();
+            handle.clone().spawn_blocking(move || {
+                handle.block_on(until_shutdown(shutdown_receiver, purger.run()))
+            });
+        }
+
         let log_path = log_path.to_path_buf();
+        let middleware_request_processor = request_processor.clone();
+        let listener_request_processor = request_processor.clone();
+        let listener = request_processor.listener();
+        let thread_listener = listener.clone();
+        let listener_shutdown = shutdown_receiver;
 
-        let (close_handle_sender, close_handle_receiver) = unbounded();
+        let (addr_sender, addr_receiver) = unbounded();
         let thread_hdl = Builder::new()
             .name("solJsonRpcSvc".to_string())
             .spawn(move || {
                 renice_this_thread(rpc_niceness_adj).unwrap();
+                // Dropped with the RPC runtime once the server stops
+                let _storage_runtime = storage_runtime;
 
-                let mut io = MetaIoHandler::default();
+                let params_middleware = ParamsMiddleware::new(strict_params);
+                let rpc_middleware = (
+                    ErrorMetricsMiddleware,
+                    (
+                        FeatureFlagMiddleware::new(feature_flags),
+                        params_middleware.clone(),
+                        (
+                            ThrottleMiddleware::new(throttle),
+                            ConcurrencyLimitMiddleware::new(max_in_flight_per_client),
+                        ),
+                        ResponseSizeMiddleware::new(max_response_size),
+                    ),
+                );
+                // Restarted listeners share the middleware state of the first one
+                let start_server = |addr: &SocketAddr, listener_config: &ListenerConfig| {
+                    let io = build_io_handler(full_api, admin_rpc, rpc_middleware.clone());
 
-                io.extend_with(MinimalImpl.to_delegate());
-                if full_api {
-                    io.extend_with(FullImpl.to_delegate());
-                }
+                    let request_middleware = RpcRequestMiddleware::new(
+                        log_path.clone(),
+                        middleware_request_processor.clone(),
+                        rest_max_age,
+                        max_request_body_size,
+                    )
+                    .with_binary_rpc(Arc::new(build_io_handler(
+                        full_api,
+                        admin_rpc,
+                        rpc_middleware.clone(),
+                    )))
+                    .with_v2_rpc(Arc::new(build_v2_io_handler((
+                        rpc_middleware.0.clone(),
+                        (
+                            rpc_middleware.1.0.clone(),
+                            params_middleware.for_v2(),
+                            rpc_middleware.1.2.clone(),
+                            rpc_middleware.1.3.clone(),
+                        ),
+                    ))))
+                    .with_rest_handlers(rest_handlers.clone())
+                    .with_interceptors(interceptors.clone())
+                    .with_rpc_cache_policy(rpc_cache_policy.clone())
+                    .with_metrics(middleware_metrics.clone())
+                    .with_api_keys(listener_config.api_keys.clone())
+                    .with_trusted_proxies(listener_config.trusted_proxies.clone());
+                    let request_processor = request_processor.clone();
+                    let api_keys = listener_config.api_keys.clone();
+                    let trusted_proxies = listener_config.trusted_proxies.clone();
+                    ServerBuilder::with_meta_extractor(
+                        io,
+                        move |req: &hyper::Request<hyper::Body>| {
+                            request_processor
+                                .with_timeout_budget(timeout_budget(req.headers()))
+                                .with_client_key(client_key(req.headers(), &api_keys, &trusted_proxies))
+                                .with_trace_id(trace_id(req.headers()))
+                        },
+                    )
+                        .event_loop_executor(runtime.handle().clone())
+                        .threads(1)
+                        .cors(listener_config.cors.domains_validation())
+                        .cors_allow_headers(listener_config.cors.allow_headers())
+                        .cors_max_age(listener_config.cors.max_age())
+                        .request_middleware(request_middleware)
+                        .max_request_body_size(max_request_body_size)
+                        .start_http(addr)
+                };
+                let listen = |server: &Server| {
+                    thread_listener.set(server.close_handle());
+                    // Exit may have run while the listener was down
+                    if *listener_shutdown.borrow() {
+                        thread_listener.close();
+                    }
+                };
 
-                let request_middleware = RpcRequestMiddleware::new(
-                    log_path,
-                );
-                let server = ServerBuilder::with_meta_extractor(
-                    io,
-                    move |_req: &hyper::Request<hyper::Body>| request_processor.clone(),
-                )
-                    .event_loop_executor(runtime.handle().clone())
-                    .threads(1)
-                    .cors(DomainsValidation::AllowOnly(vec![
-                        AccessControlAllowOrigin::Any,
-                    ]))
-                    .cors_max_age(86400)
-                    .request_middleware(request_middleware)
-                    .max_request_body_size(max_request_body_size)
-                    .start_http(&rpc_addr);
-
-                if let Err(e) = server {
-                    warn!(
-                        "JSON RPC service unavailable error: {:?}. \n\
-                           Also, check that port {} is not already in use by another application",
-                        e,
-                        rpc_addr.port()
+                let server = match start_server(&rpc_addr, &listener_config) {
+                    Ok(server) => server,
+                    Err(e) => {
+                        warn!(
+                            "JSON RPC service unavailable error: {:?}. \n\
+                               Also, check that port {} is not already in use by another application",
+                            e,
+                            rpc_addr.port()
+                        );
+                        addr_sender.send(Err(e.to_string())).unwrap();
+                        return;
+                    }
+                };
+                thread_listener.set(server.close_handle());
+                addr_sender.send(Ok(*server.address())).unwrap();
+                // Rebind to the port first bound, which the OS picked if 0 was requested
+                let bind_addr = *server.address();
+
+                let mut next_server = Some(server);
+                while let Some(server) = next_server.take() {
+                    let outcome = panic::catch_unwind(AssertUnwindSafe(move || server.wait()));
+                    if *listener_shutdown.borrow() {
+                        break;
+                    }
+                    let reason = match outcome {
+                        Ok(()) if thread_listener.take_restart_request() => {
+                            if let Some(loader) = &listener_config_loader {
+                                match loader() {
+                                    Ok(config) => listener_config = config,
+                                    Err(err) => warn!(
+                                        "Failed to reload the JSON RPC listener config, \
+                                         keeping the previous one: {}",
+                                        err
+                                    ),
+                                }
+                            }
+                            match start_server(&bind_addr, &listener_config) {
+                                Ok(server) => {
+                                    info!("JSON RPC listener rebound on {} on request", server.address());
+                                    listen(&server);
+                                    next_server = Some(server);
+                                    continue;
+                                }
+                                Err(err) => format!("failed to rebind on request: {err}"),
+                            }
+                        }
+                        Ok(()) => "stopped".to_string(),
+                        Err(panic) => format!("panicked: {}", panic_message(&*panic)),
+                    };
+                    error!(
+                        "JSON RPC listener {} while the service is running, nothing is served{}",
+                        reason,
+                        if restart_listener { " until it restarts" } else { "" }
                     );
-                    close_handle_sender.send(Err(e.to_string())).unwrap();
-                    return;
+                    listener_request_processor.set_listener_down(true);
+                    solana_metrics::datapoint_error!(
+                        "block-meta-rpc-listener-down",
+                        ("reason", reason, String),
+                        ("restarting", restart_listener, bool),
+                    );
+                    if !restart_listener {
+                        break;
+                    }
+                    while !*listener_shutdown.borrow() {
+                        thread::sleep(LISTENER_RESTART_DELAY);
+                        match start_server(&bind_addr, &listener_config) {
+                            Ok(server) => {
+                                info!("JSON RPC listener restarted on {}", server.address());
+                                listen(&server);
+                                listener_request_processor.set_listener_down(false);
+                                next_server = Some(server);
+                                break;
+                            }
+                            Err(err) => warn!("Failed to restart the JSON RPC listener: {}", err),
+                        }
+                    }
                 }
-
-                let server = server.unwrap();
-                close_handle_sender.send(Ok(server.close_handle())).unwrap();
-                server.wait();
             })
-            .unwrap();
+            .map_err(|err| format!("Failed to spawn the RPC service thread: {err}"))?;
 
-        let close_handle = close_handle_receiver.recv().unwrap()?;
-        let close_handle_ = close_handle.clone();
+        let rpc_addr = addr_receiver
+            .recv()
+            .map_err(|err| format!("RPC service thread exited before starting: {err}"))??;
+        info!("rpc listening on {}", rpc_addr);
+        let build_info =
+            build_info_request_processor.init_build_info(auth, vec![rpc_addr.to_string()]);
+        info!("Serving {}", build_info);
+        let listener_ = listener.clone();
+        let shutdown_ = shutdown.clone();
         rpc_service_exit
             .write()
             .unwrap()
-            .register_exit(Box::new(move || close_handle_.close()));
+            .register_exit(Box::new(move || {
+                shutdown_.send_replace(true);
+                listener_.close();
+            }));
         Ok(Self {
             thread_hdl,
             #[cfg(test)]
             request_processor: test_request_processor,
-            close_handle: Some(close_handle),
+            listener,
+            shutdown,
+            rpc_addr,
         })
     }
 
+    /// Address the server is bound to, with the port the OS assigned when 0 was requested
+    pub fn rpc_addr(&self) -> SocketAddr {
+        self.rpc_addr
+    }
+
+    /// Stop the background tasks and close the server; `join` returns once the runtime
+    /// has wound down
     pub fn exit(&mut self) {
-        if let Some(c) = self.close_handle.take() {
-            c.close()
-        }
+        self.shutdown.send_replace(true);
+        self.listener.close();
     }
 
     pub fn join(self) -> thread::Result<()> {
@@ -187,3 +588,221 @@ impl JsonRpcService {
     }
 }
 
+/// Dispatcher of the methods served on `/`, also used to drive the dispatch without
+/// a server
+pub fn build_io_handler(
+    full_api: bool,
+    admin_rpc: bool,
+    rpc_middleware: RpcMiddleware,
+) -> RpcIoHandler {
+    let mut io = MetaIoHandler::with_middleware(rpc_middleware);
+    io.extend_with(MinimalImpl.to_delegate());
+    if full_api {
+        io.extend_with(FullImpl.to_delegate());
+    }
+    if admin_rpc {
+        io.extend_with(AdminImpl.to_delegate());
+        #[cfg(feature = "fault-injection")]
+        io.extend_with(FaultInjectionImpl.to_delegate());
+    }
+    io
+}
+
+/// Dispatcher of the methods served on `/v2`
+pub fn build_v2_io_handler(rpc_middleware: RpcMiddleware) -> RpcIoHandler {
+    let mut io = MetaIoHandler::with_middleware(rpc_middleware);
+    io.extend_with(V2Impl.to_delegate());
+    io
+}
+
+/// Message a thread panicked with, when it is a string
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause")
+}
+
+/// Run `task` until the service shuts down, so the runtime can be dropped without
+/// waiting on endless background loops
+async fn until_shutdown(mut shutdown: watch::Receiver<bool>, task: impl Future<Output = ()>) {
+    tokio::select! {
+        _ = task => {}
+        _ = shutdown.wait_for(|shutdown| *shutdown) => {}
+    }
+}
+
+fn init_meta_storage(
+    runtime: &tokio::runtime::Runtime,
+    mysql_config: &MySQLConfig,
+    backend: &str,
+    read_only: bool,
+    shutdown: &watch::Receiver<bool>,
+) -> Option<MetaStorage> {
+    let MySQLConfig {
+        ref host,
+        port,
+        ref username,
+        ref password,
+        ref db_name,
+        timeout,
+        ref dsn,
+        prewarm_connections,
+        ref reconnect_gate,
+        acquire_timeout,
+        ref replica_dsns,
+        hedge_delay,
+        max_replica_lag,
+        ref shards,
+        block_time_format,
+        ref block_partitions,
+        ref index_hints,
+        isolation_level,
+        max_rows,
+    } = *mysql_config;
+
+    let meta_storage_config = MetaStorageConfig {
+        read_only,
+        timeout,
+        host: host.clone(),
+        port,
+        username: username.clone(),
+        password: password.clone(),
+        db_name: db_name.clone(),
+        dsn: dsn.clone(),
+        prewarm_connections,
+        reconnect_gate: reconnect_gate.clone(),
+        acquire_timeout,
+        replica_dsns: replica_dsns.clone(),
+        hedge_delay,
+        max_replica_lag,
+        shards: shards.clone(),
+        block_time_format,
+        block_partitions: block_partitions.clone(),
+        index_hints: index_hints.clone(),
+        isolation_level,
+        max_rows,
+    };
+    runtime
+        .block_on(MetaStorage::new_with_config(meta_storage_config))
+        .inspect(|meta_storage| {
+            info!("MySQL metadata storage initialized ({})", backend);
+            if meta_storage.has_replicas() {
+                let storage = meta_storage.clone();
+                let handle = runtime.handle().clone();
+                let shutdown = shutdown.clone();
+                // Probes are synchronous MySQL calls, keep them off the request workers
+                runtime.spawn_blocking(move || {
+                    handle.block_on(until_shutdown(shutdown, async move {
+                        let mut interval = tokio::time::interval(REPLICA_PROBE_INTERVAL);
+                        loop {
+                            interval.tick().await;
+                            storage.probe_replicas().await;
+                        }
+                    }))
+                });
+            }
+            if meta_storage.is_partitioned() {
+                let storage = meta_storage.clone();
+                let handle = runtime.handle().clone();
+                let shutdown = shutdown.clone();
+                runtime.spawn_blocking(move || {
+                    handle.block_on(until_shutdown(shutdown, async move {
+                        let mut interval = tokio::time::interval(PARTITION_REFRESH_INTERVAL);
+                        interval.tick().await;
+                        loop {
+                            interval.tick().await;
+                            if let Err(err) = storage.refresh_partitions().await {
+                                warn!("Failed to refresh block partitions: {}", err);
+                            }
+                        }
+                    }))
+                });
+            }
+        })
+        .map_err(|err| {
+            error!("Failed to initialize MySQL metadata storage ({}): {:?}", backend, err);
+        })
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::storage_stub::{sample_stub, stub_router},
+        serde_json::Value,
+        std::collections::HashSet,
+    };
+
+    const GOLDEN_RESPONSES: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/rpc_responses.json");
+
+    /// Exact responses of every registered method, served from the fixed data of
+    /// `sample_stub` so they are deterministic. Cases naming a `feature` only run when it
+    /// is enabled. After an intended change, rewrite the file with
+    /// `UPDATE_GOLDEN=1 cargo test --features fault-injection`.
+    #[tokio::test]
+    async fn test_golden_responses() {
+        let io = build_io_handler(true, true, RpcMiddleware::default());
+        let v2_io = build_v2_io_handler(RpcMiddleware::default());
+        let request_processor = JsonRpcRequestProcessor::new(
+            JsonRpcConfig::default_for_storage_rpc(),
+            Arc::new(RwLock::new(Exit::default())),
+            Some(stub_router(sample_stub()).await),
+        );
+        request_processor.init_build_info("none", vec!["127.0.0.1:8899".to_string()]);
+        let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+        let mut cases: Vec<Value> =
+            serde_json::from_str(&std::fs::read_to_string(GOLDEN_RESPONSES).unwrap()).unwrap();
+
+        let mut mismatches = vec![];
+        let mut covered = HashSet::new();
+        for case in &mut cases {
+            let enabled = match case["feature"].as_str() {
+                Some("fault-injection") => cfg!(feature = "fault-injection"),
+                Some(_) => false,
+                None => true,
+            };
+            if !enabled {
+                continue;
+            }
+            let endpoint = case["endpoint"].as_str().unwrap_or("/").to_string();
+            let io = match endpoint.as_str() {
+                "/v2" => &v2_io,
+                _ => &io,
+            };
+            covered.insert((endpoint, case["request"]["method"].as_str().map(str::to_string)));
+            let response = io
+                .handle_request(&case["request"].to_string(), request_processor.clone())
+                .await
+                .map(|response| serde_json::from_str::<Value>(&response).unwrap())
+                .unwrap_or_default();
+            if update {
+                case["response"] = response;
+            } else if case["response"] != response {
+                mismatches.push(format!(
+                    "{} {}\n  expected: {}\n  actual:   {}",
+                    case["endpoint"], case["request"], case["response"], response
+                ));
+            }
+        }
+
+        if update {
+            let golden = serde_json::to_string_pretty(&cases).unwrap() + "\n";
+            std::fs::write(GOLDEN_RESPONSES, golden).unwrap();
+        }
+        assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+
+        let uncovered: Vec<_> = [("/", &io), ("/v2", &v2_io)]
+            .into_iter()
+            .flat_map(|(endpoint, io)| {
+                io.iter()
+                    .map(move |(method, _)| (endpoint.to_string(), Some(method.clone())))
+            })
+            .filter(|method| !covered.contains(method))
+            .collect();
+        assert!(uncovered.is_empty(), "methods without a golden case: {:?}", uncovered);
+    }
+}
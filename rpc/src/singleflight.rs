@@ -0,0 +1,97 @@
+use {
+    std::{
+        collections::HashMap,
+        future::Future,
+        hash::Hash,
+        sync::{Arc, Mutex},
+    },
+    tokio::sync::OnceCell,
+};
+
+/// Coalesces concurrent identical lookups so that they share one in-flight call.
+///
+/// The first caller for a key runs the lookup, every caller arriving while it is
+/// still running awaits the same result. Once the result is available the key is
+/// released, so later callers always trigger a fresh lookup.
+pub struct SingleFlight<K, V> {
+    in_flight: Mutex<HashMap<K, Arc<OnceCell<V>>>>,
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub async fn run<F, Fut>(&self, key: K, f: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let cell = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+
+        let value = cell.get_or_init(f).await.clone();
+
+        // Whoever finishes first releases the key; the cell may already have been
+        // replaced by a newer flight, which must be left alone.
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+        {
+            in_flight.remove(&key);
+        }
+
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_lookup() {
+        let flight = Arc::new(SingleFlight::<(), u64>::default());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let flight = flight.clone();
+                let calls = calls.clone();
+                tokio::spawn(async move {
+                    flight
+                        .run((), || async move {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                            42
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), 42);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // The key is released once the flight completes
+        assert_eq!(flight.run((), || async { 7 }).await, 7);
+    }
+}
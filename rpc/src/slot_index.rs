@@ -0,0 +1,286 @@
+use {
+    roaring::RoaringBitmap,
+    solana_sdk::clock::Slot,
+    solana_storage_mysql::meta_storage::{self, MetaStorage},
+    std::{
+        sync::{Arc, RwLock},
+        time::{Duration, Instant},
+    },
+};
+
+/// How often slots stored since the last load are added
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// How often the whole index is reloaded, picking up backfilled and orphaned slots
+const REBUILD_INTERVAL: Duration = Duration::from_secs(3600);
+/// Slots fetched per storage query while loading
+const LOAD_BATCH_SLOTS: u64 = 100_000;
+/// Highest slot the 32-bit bitmap can hold
+const MAX_INDEXED_SLOT: Slot = u32::MAX as Slot;
+
+#[derive(Default)]
+struct IndexedSlots {
+    slots: RoaringBitmap,
+    /// Range whose stored slots are all in `slots`; unset until the first load
+    covered: Option<(Slot, Slot)>,
+}
+
+impl IndexedSlots {
+    /// Bitmap bounds of `start_slot..=end_slot` when the range is covered
+    fn bounds(&self, start_slot: Slot, end_slot: Slot) -> Option<(u32, u32)> {
+        let (first, last) = self.covered?;
+        if start_slot < first || end_slot > last || end_slot < start_slot {
+            return None;
+        }
+        Some((start_slot as u32, end_slot as u32))
+    }
+
+    /// Keep at most `max_slots` slots below the end of the covered range
+    fn trim(&mut self, max_slots: u64) {
+        if let Some((first, last)) = self.covered {
+            let first = first.max(last.saturating_sub(max_slots.saturating_sub(1)));
+            self.slots.remove_range(..first as u32);
+            self.covered = Some((first, last));
+        }
+    }
+}
+
+/// Compressed bitmap of the stored slots in a bounded range, answering `getBlocks` and
+/// `getBlockCount` from memory. Ranges outside the indexed one fall back to storage.
+pub struct SlotIndex {
+    max_slots: u64,
+    indexed: RwLock<IndexedSlots>,
+}
+
+impl SlotIndex {
+    pub fn new(max_slots: u64) -> Self {
+        Self {
+            max_slots,
+            indexed: RwLock::default(),
+        }
+    }
+
+    /// Stored slots in `start_slot..=end_slot`, `None` unless the range is indexed
+    pub fn blocks(&self, start_slot: Slot, end_slot: Slot) -> Option<Vec<Slot>> {
+        let indexed = self.indexed.read().unwrap();
+        let (start, end) = indexed.bounds(start_slot, end_slot)?;
+        let mut range = RoaringBitmap::new();
+        range.insert_range(start..=end);
+        Some((&indexed.slots & &range).iter().map(Slot::from).collect())
+    }
+
+    /// Number of stored slots in `start_slot..=end_slot`, `None` unless the range is
+    /// indexed
+    pub fn count(&self, start_slot: Slot, end_slot: Slot) -> Option<u64> {
+        let indexed = self.indexed.read().unwrap();
+        let (start, end) = indexed.bounds(start_slot, end_slot)?;
+        let below = start
+            .checked_sub(1)
+            .map_or(0, |slot| indexed.slots.rank(slot));
+        Some(indexed.slots.rank(end) - below)
+    }
+
+    /// Record `slot` being orphaned or restored
+    pub fn set_present(&self, slot: Slot, present: bool) {
+        let mut indexed = self.indexed.write().unwrap();
+        if indexed.bounds(slot, slot).is_some() {
+            if present {
+                indexed.slots.insert(slot as u32);
+            } else {
+                indexed.slots.remove(slot as u32);
+            }
+        }
+    }
+
+    /// Drop the whole index, whose slots are unknown until the loader rebuilds it at its
+    /// next refresh. Meanwhile every range falls back to storage.
+    pub fn invalidate(&self) {
+        *self.indexed.write().unwrap() = IndexedSlots::default();
+    }
+
+    fn covered(&self) -> Option<(Slot, Slot)> {
+        self.indexed.read().unwrap().covered
+    }
+
+    /// Replace the index with `slots`, every stored slot of `start_slot..=end_slot`
+    fn replace(&self, start_slot: Slot, end_slot: Slot, slots: RoaringBitmap) {
+        let mut indexed = self.indexed.write().unwrap();
+        *indexed = IndexedSlots {
+            slots,
+            covered: Some((start_slot, end_slot)),
+        };
+        indexed.trim(self.max_slots);
+    }
+
+    /// Add `slots`, every stored slot of `start_slot..=end_slot`, which must directly
+    /// follow the indexed range. Slots below `first_slot` were purged and are dropped.
+    fn extend(&self, start_slot: Slot, end_slot: Slot, slots: RoaringBitmap, first_slot: Slot) {
+        let mut indexed = self.indexed.write().unwrap();
+        let first = match indexed.covered {
+            Some((first, last)) if last.checked_add(1) == Some(start_slot) => first,
+            // Rebuilt in between
+            _ => return,
+        };
+        let first = first.max(first_slot.min(end_slot));
+        indexed.slots |= slots;
+        indexed.slots.remove_range(..first as u32);
+        indexed.covered = Some((first, end_slot));
+        indexed.trim(self.max_slots);
+    }
+
+    pub fn submit_metrics(&self) {
+        let indexed = self.indexed.read().unwrap();
+        let (first, last) = indexed.covered.unwrap_or_default();
+        solana_metrics::datapoint_info!(
+            "block-meta-rpc-slot-index",
+            ("slots", indexed.slots.len() as i64, i64),
+            ("first_slot", first as i64, i64),
+            ("last_slot", last as i64, i64),
+            ("bytes", indexed.slots.serialized_size() as i64, i64),
+        );
+    }
+}
+
+/// Loads the slot index from storage and keeps it current. Slots are appended as the
+/// chain advances; slots written below the indexed tip, such as backfills, show up at
+/// the next periodic rebuild.
+pub struct SlotIndexLoader {
+    storage: MetaStorage,
+    index: Arc<SlotIndex>,
+}
+
+impl SlotIndexLoader {
+    pub fn new(storage: MetaStorage, index: Arc<SlotIndex>) -> Self {
+        Self { storage, index }
+    }
+
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        let mut rebuilt_at: Option<Instant> = None;
+        loop {
+            interval.tick().await;
+            let rebuild = rebuilt_at.map_or(true, |at| at.elapsed() >= REBUILD_INTERVAL);
+            let result = if rebuild {
+                self.rebuild().await
+            } else {
+                self.refresh().await
+            };
+            match result {
+                Ok(()) if rebuild => rebuilt_at = Some(Instant::now()),
+                Ok(()) => {}
+                Err(err) => warn!("Slot index refresh failed: {}", err),
+            }
+            self.index.submit_metrics();
+        }
+    }
+
+    async fn rebuild(&self) -> meta_storage::Result<()> {
+        let (first_slot, latest_slot) = match (
+            self.storage.get_first_available_block().await?,
+            self.storage.get_slot().await?,
+        ) {
+            (Some(first_slot), Some(latest_slot)) => {
+                (first_slot, latest_slot.min(MAX_INDEXED_SLOT))
+            }
+            _ => return Ok(()),
+        };
+        let start_slot =
+            first_slot.max(latest_slot.saturating_sub(self.index.max_slots.saturating_sub(1)));
+        if start_slot > latest_slot {
+            return Ok(());
+        }
+        let start = Instant::now();
+        let slots = self.load(start_slot, latest_slot).await?;
+        info!(
+            "Slot index loaded {} slots of {}-{} in {:?}",
+            slots.len(),
+            start_slot,
+            latest_slot,
+            start.elapsed()
+        );
+        self.index.replace(start_slot, latest_slot, slots);
+        Ok(())
+    }
+
+    async fn refresh(&self) -> meta_storage::Result<()> {
+        let next_slot = match self.index.covered() {
+            Some((_, last_slot)) => last_slot + 1,
+            None => return self.rebuild().await,
+        };
+        let (first_slot, latest_slot) = match (
+            self.storage.get_first_available_block().await?,
+            self.storage.get_slot().await?,
+        ) {
+            (Some(first_slot), Some(latest_slot)) => {
+                (first_slot, latest_slot.min(MAX_INDEXED_SLOT))
+            }
+            _ => return Ok(()),
+        };
+        if latest_slot < next_slot {
+            return Ok(());
+        }
+        let slots = self.load(next_slot, latest_slot).await?;
+        self.index.extend(next_slot, latest_slot, slots, first_slot);
+        Ok(())
+    }
+
+    /// Read the stored slots of `start_slot..=end_slot` in batches
+    async fn load(&self, start_slot: Slot, end_slot: Slot) -> meta_storage::Result<RoaringBitmap> {
+        let mut slots = RoaringBitmap::new();
+        let mut batch_start = start_slot;
+        while batch_start <= end_slot {
+            let batch_end = batch_start
+                .saturating_add(LOAD_BATCH_SLOTS - 1)
+                .min(end_slot);
+            let limit = (batch_end - batch_start + 1) as usize;
+            let blocks = self
+                .storage
+                .get_confirmed_blocks(batch_start, Some(batch_end), limit)
+                .await?;
+            slots.extend(
+                blocks
+                    .into_iter()
+                    .filter(|slot| *slot <= batch_end)
+                    .map(|slot| slot as u32),
+            );
+            batch_start = batch_end + 1;
+        }
+        Ok(slots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_index() {
+        let index = SlotIndex::new(1_000);
+        assert_eq!(index.blocks(100, 200), None);
+
+        index.replace(100, 199, [100, 105, 150, 199].into_iter().collect());
+        assert_eq!(index.blocks(100, 150), Some(vec![100, 105, 150]));
+        assert_eq!(index.count(101, 199), Some(3));
+        assert_eq!(index.blocks(150, 200), None);
+
+        index.extend(200, 299, [200, 250].into_iter().collect(), 120);
+        assert_eq!(index.blocks(120, 299), Some(vec![150, 199, 200, 250]));
+        assert_eq!(index.count(100, 299), None);
+
+        // Not adjacent to the indexed range
+        index.extend(400, 499, [400].into_iter().collect(), 120);
+        assert_eq!(index.blocks(400, 400), None);
+
+        index.set_present(150, false);
+        assert_eq!(index.count(120, 299), Some(3));
+
+        // Only the most recent slots are kept
+        index.extend(300, 1_199, [1_000].into_iter().collect(), 120);
+        assert_eq!(index.covered(), Some((200, 1_199)));
+        assert_eq!(index.blocks(200, 1_199), Some(vec![200, 250, 1_000]));
+        assert_eq!(index.blocks(199, 1_199), None);
+
+        index.invalidate();
+        assert_eq!(index.covered(), None);
+        assert_eq!(index.blocks(200, 250), None);
+    }
+}
@@ -0,0 +1,382 @@
+#[cfg(feature = "fault-injection")]
+use crate::fault_injection::FaultInjector;
+#[cfg(any(test, feature = "stub-storage"))]
+use crate::storage_stub::StorageStub;
+use {
+    solana_storage_mysql::{
+        meta_storage::{
+            self,
+            MetaStorage,
+        },
+        mysql::{with_query_budget, with_query_label},
+    },
+    std::{
+        future::Future,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    },
+    tokio::{runtime::Handle, task::JoinHandle},
+};
+
+/// Which of the configured backends served a storage call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    Primary,
+    Canary,
+}
+
+impl StorageBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageBackend::Primary => "primary",
+            StorageBackend::Canary => "canary",
+        }
+    }
+}
+
+/// Upper bounds, in milliseconds, of the default storage latency buckets
+pub const DEFAULT_LATENCY_BUCKETS_MS: &[u64] = &[
+    1, 2, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000,
+];
+
+/// Latency ranges storage calls are tagged with, so that dashboards can count calls per
+/// range instead of relying on aggregates that lose the tail
+#[derive(Debug, Clone)]
+pub struct LatencyBuckets {
+    bounds: Arc<[Duration]>,
+}
+
+impl Default for LatencyBuckets {
+    fn default() -> Self {
+        Self::new(DEFAULT_LATENCY_BUCKETS_MS.iter().map(|ms| Duration::from_millis(*ms)))
+    }
+}
+
+impl LatencyBuckets {
+    pub fn new(bounds: impl IntoIterator<Item = Duration>) -> Self {
+        let mut bounds: Vec<Duration> = bounds.into_iter().collect();
+        bounds.sort();
+        bounds.dedup();
+        Self {
+            bounds: bounds.into(),
+        }
+    }
+
+    /// Tag of the smallest bucket holding `elapsed`, e.g. `le_25ms`, or `inf` past the
+    /// last bound
+    pub fn bucket(&self, elapsed: Duration) -> String {
+        self.bounds
+            .iter()
+            .find(|bound| elapsed <= **bound)
+            .map(|bound| format!("le_{}ms", bound.as_millis()))
+            .unwrap_or_else(|| "inf".to_string())
+    }
+}
+
+/// Routes read traffic between the primary storage and an optional canary backend.
+///
+/// A configured percentage of calls is sent to the canary and its results are
+/// returned to the client, which allows gradual cutovers between databases.
+#[derive(Clone)]
+pub struct StorageRouter {
+    primary: MetaStorage,
+    canary: Option<MetaStorage>,
+    canary_percent: u8,
+    counter: Arc<AtomicU64>,
+    /// Backend every call of this router goes to, chosen once per request
+    pinned: Option<StorageBackend>,
+    timeout: Option<Duration>,
+    /// Time the client is willing to wait, bounding the calls on top of `timeout`
+    budget: Option<Duration>,
+    /// Dedicated runtime for storage calls; they run on the caller's runtime when unset
+    runtime: Option<Handle>,
+    latency_buckets: LatencyBuckets,
+    /// Set while a schema validation triggered by a drift error runs
+    validating_schema: Arc<AtomicBool>,
+    /// Sampled trace of the current request, reported as an exemplar of the call latency
+    trace_id: Option<Arc<str>>,
+    #[cfg(feature = "fault-injection")]
+    faults: FaultInjector,
+    /// Fixed results answering every call in place of the backends
+    #[cfg(any(test, feature = "stub-storage"))]
+    stub: Option<Arc<StorageStub>>,
+}
+
+impl StorageRouter {
+    pub fn new(primary: MetaStorage) -> Self {
+        Self {
+            primary,
+            canary: None,
+            canary_percent: 0,
+            counter: Arc::default(),
+            pinned: None,
+            timeout: None,
+            budget: None,
+            runtime: None,
+            latency_buckets: LatencyBuckets::default(),
+            validating_schema: Arc::default(),
+            trace_id: None,
+            #[cfg(feature = "fault-injection")]
+            faults: FaultInjector::default(),
+            #[cfg(any(test, feature = "stub-storage"))]
+            stub: None,
+        }
+    }
+
+    /// Fail storage calls that take longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Fail storage calls that take longer than the client's `budget`, and have the
+    /// server stop their queries once it elapses
+    pub fn with_budget(mut self, budget: Option<Duration>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn budget(&self) -> Option<Duration> {
+        self.budget
+    }
+
+    /// Copy of this router bounded by its timeout only, for calls shared between clients
+    pub fn without_budget(&self) -> Self {
+        self.clone().with_budget(None)
+    }
+
+    /// Run storage calls on `runtime`, so that slow queries cannot starve the HTTP
+    /// event loop
+    pub fn with_runtime(mut self, runtime: Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Attribute storage calls to the sampled trace `trace_id`
+    pub fn with_trace_id(mut self, trace_id: Option<Arc<str>>) -> Self {
+        self.trace_id = trace_id;
+        self
+    }
+
+    pub fn with_latency_buckets(mut self, latency_buckets: LatencyBuckets) -> Self {
+        self.latency_buckets = latency_buckets;
+        self
+    }
+
+    /// Answer every call from `stub` instead of the backends
+    #[cfg(any(test, feature = "stub-storage"))]
+    pub fn with_stub(mut self, stub: StorageStub) -> Self {
+        self.stub = Some(Arc::new(stub));
+        self
+    }
+
+    /// Send `percent` (0-100) of the read traffic to `canary`
+    pub fn with_canary(mut self, canary: MetaStorage, percent: u8) -> Self {
+        self.canary = Some(canary);
+        self.canary_percent = percent.min(100);
+        self
+    }
+
+    /// Copy of this router sending all of its calls to one backend, picked by the
+    /// canary split, so that a request does not mix results from both databases
+    pub fn pin_backend(&self) -> Self {
+        let mut router = self.clone();
+        router.pinned = Some(self.select().0);
+        router
+    }
+
+    /// Copy of this router sending all of its calls to the primary, for admin calls
+    pub fn on_primary(&self) -> Self {
+        let mut router = self.clone();
+        router.pinned = Some(StorageBackend::Primary);
+        router
+    }
+
+    pub fn primary(&self) -> &MetaStorage {
+        &self.primary
+    }
+
+    /// Share of reads sent to the canary, when there is one
+    pub fn canary_percent(&self) -> Option<u8> {
+        self.canary.as_ref().map(|_| self.canary_percent)
+    }
+
+    /// Faults injected into every call of this router and its clones
+    #[cfg(feature = "fault-injection")]
+    pub fn faults(&self) -> &FaultInjector {
+        &self.faults
+    }
+
+    /// Report the prepared statement counters of each backend
+    pub fn submit_metrics(&self) {
+        let backends = std::iter::once((StorageBackend::Primary, &self.primary))
+            .chain(self.canary.iter().map(|canary| (StorageBackend::Canary, canary)));
+        for (backend, storage) in backends {
+            let stats = storage.statement_stats();
+            solana_metrics::datapoint_info!(
+                "block-meta-rpc-statements",
+                "backend" => backend.as_str(),
+                ("executions", stats.executions as i64, i64),
+                ("prepares", stats.prepares as i64, i64),
+            );
+        }
+    }
+
+    /// Pick the backend for the next call, which is the pinned one if any.
+    ///
+    /// A rolling counter is used instead of a random roll so that the split is
+    /// exact over every 100 consecutive calls.
+    pub fn select(&self) -> (StorageBackend, &MetaStorage) {
+        if let Some(backend) = self.pinned {
+            return match (backend, &self.canary) {
+                (StorageBackend::Canary, Some(canary)) => (StorageBackend::Canary, canary),
+                _ => (StorageBackend::Primary, &self.primary),
+            };
+        }
+        if let Some(canary) = &self.canary {
+            let bucket = self.counter.fetch_add(1, Ordering::Relaxed) % 100;
+            if bucket < self.canary_percent as u64 {
+                return (StorageBackend::Canary, canary);
+            }
+        }
+        (StorageBackend::Primary, &self.primary)
+    }
+
+    /// Run a storage call on the selected backend, reporting its latency and outcome.
+    ///
+    /// With a dedicated storage runtime the call is spawned there. Otherwise, when a
+    /// timeout is set, the call runs on the blocking pool, as the MySQL client is
+    /// synchronous and would otherwise hold the worker until the query returns.
+    pub async fn route<T, F, Fut>(&self, method: &'static str, f: F) -> meta_storage::Result<T>
+    where
+        F: FnOnce(MetaStorage) -> Fut + Send + 'static,
+        Fut: Future<Output = meta_storage::Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (backend, storage) = self.select();
+        let start = Instant::now();
+        let storage = storage.clone();
+        let schema_storage = storage.clone();
+        // Queries carry the method in a comment, for attribution on the database side
+        let call = with_query_budget(self.budget, with_query_label(Some(method), f(storage)));
+        #[cfg(any(test, feature = "stub-storage"))]
+        let call = {
+            let stubbed = self.stub.as_ref().map(|stub| stub.respond::<T>(method));
+            async move {
+                match stubbed {
+                    Some(result) => result,
+                    None => call.await,
+                }
+            }
+        };
+        #[cfg(feature = "fault-injection")]
+        let call = {
+            let faults = self.faults.clone();
+            async move {
+                faults.inject(method).await?;
+                call.await
+            }
+        };
+        let timeout = match (self.timeout, self.budget) {
+            (Some(timeout), Some(budget)) => Some(timeout.min(budget)),
+            (timeout, budget) => timeout.or(budget),
+        };
+        let result = match (&self.runtime, timeout) {
+            (Some(runtime), timeout) => join_call(runtime.spawn(call), timeout).await,
+            (None, Some(timeout)) => {
+                let handle = Handle::current();
+                let call = tokio::task::spawn_blocking(move || handle.block_on(call));
+                join_call(call, Some(timeout)).await
+            }
+            (None, None) => call.await,
+        };
+        let elapsed = start.elapsed();
+
+        solana_metrics::datapoint_info!(
+            "block-meta-rpc-storage",
+            "backend" => backend.as_str(),
+            "method" => method,
+            "latency_bucket" => self.latency_buckets.bucket(elapsed).as_str(),
+            ("latency_us", elapsed.as_micros() as i64, i64),
+            ("error", result.is_err(), bool),
+        );
+        // Exemplars go to their own measurement so that the trace ID, a field, does not
+        // thin out the aggregated series
+        if let Some(trace_id) = &self.trace_id {
+            solana_metrics::datapoint_info!(
+                "block-meta-rpc-storage-exemplar",
+                "backend" => backend.as_str(),
+                "method" => method,
+                "latency_bucket" => self.latency_buckets.bucket(elapsed).as_str(),
+                ("latency_us", elapsed.as_micros() as i64, i64),
+                ("trace_id", trace_id, String),
+            );
+        }
+        if let Err(err) = &result {
+            debug!("{} storage error in {}: {}", backend.as_str(), method, err);
+            if matches!(err, meta_storage::Error::SchemaDrift(_)) {
+                self.validate_schema(backend, schema_storage);
+            }
+        }
+
+        result
+    }
+
+    /// Re-run schema validation in the background after a drift error, logging the
+    /// columns that went missing. Drift errors arriving meanwhile do not start another run.
+    fn validate_schema(&self, backend: StorageBackend, storage: MetaStorage) {
+        if self.validating_schema.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let validating_schema = self.validating_schema.clone();
+        tokio::spawn(async move {
+            let missing = storage.validate_schema().await;
+            if missing.is_empty() {
+                warn!(
+                    "{} storage schema validation found every expected column; \
+                     a column type may have changed",
+                    backend.as_str()
+                );
+            } else {
+                warn!(
+                    "{} storage schema validation: missing {}",
+                    backend.as_str(),
+                    missing.join(", ")
+                );
+            }
+            validating_schema.store(false, Ordering::Release);
+        });
+    }
+}
+
+/// Wait for a storage call spawned on another task, failing after `timeout`
+async fn join_call<T>(
+    call: JoinHandle<meta_storage::Result<T>>,
+    timeout: Option<Duration>,
+) -> meta_storage::Result<T> {
+    let result = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, call).await {
+            Ok(result) => result,
+            Err(_) => return Err(meta_storage::Error::Timeout),
+        },
+        None => call.await,
+    };
+    result.unwrap_or_else(|err| Err(meta_storage::Error::TokioJoinError(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_bucket() {
+        let buckets = LatencyBuckets::new([30_000, 1, 25].map(Duration::from_millis));
+        assert_eq!(buckets.bucket(Duration::from_micros(500)), "le_1ms");
+        assert_eq!(buckets.bucket(Duration::from_millis(25)), "le_25ms");
+        assert_eq!(buckets.bucket(Duration::from_millis(26)), "le_30000ms");
+        assert_eq!(buckets.bucket(Duration::from_secs(31)), "inf");
+    }
+}
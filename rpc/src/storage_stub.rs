@@ -0,0 +1,192 @@
+//! Fixed storage results served in place of the database, so that the request path can
+//! be exercised without MySQL. Built for tests and with the `stub-storage` feature.
+
+use {
+    crate::storage_router::StorageRouter,
+    chrono::{TimeZone, Utc},
+    solana_sdk::clock::Slot,
+    solana_storage_mysql::{
+        meta_storage::{
+            self, BackfillRange, BackfillState, BlockMetaRevision, BlockMetaVersions,
+            BlockReward, FeeStats, InflationReward, MetaStorage, MetaStorageConfig, SchemaFeature,
+            SchemaInfo, TableInfo,
+        },
+        mysql::{ReconnectGate, ReconnectLimits},
+    },
+    std::{
+        any::{Any, TypeId},
+        collections::HashMap,
+        sync::Arc,
+        time::Duration,
+    },
+};
+
+type StubResponse = Arc<dyn Fn() -> Box<dyn Any + Send> + Send + Sync>;
+
+/// Results of storage calls by method label and result type, shared by the clones of a
+/// `StorageRouter`
+#[derive(Clone, Default)]
+pub struct StorageStub {
+    responses: HashMap<(&'static str, TypeId), StubResponse>,
+}
+
+impl StorageStub {
+    /// Answer the calls labelled `method` that return a `T` with the result of `response`
+    pub fn with<T: Send + 'static>(
+        mut self,
+        method: &'static str,
+        response: impl Fn() -> meta_storage::Result<T> + Send + Sync + 'static,
+    ) -> Self {
+        self.responses.insert(
+            (method, TypeId::of::<T>()),
+            Arc::new(move || Box::new(response()) as Box<dyn Any + Send>),
+        );
+        self
+    }
+
+    /// Result of a call of `method`. Calls without a stubbed result fail as unavailable,
+    /// so the database behind the stub is never queried.
+    pub fn respond<T: Send + 'static>(&self, method: &str) -> meta_storage::Result<T> {
+        self.responses
+            .get(&(method, TypeId::of::<T>()))
+            .and_then(|response| response().downcast::<meta_storage::Result<T>>().ok())
+            .map_or(Err(meta_storage::Error::StorageUnavailable), |result| *result)
+    }
+}
+
+/// Router answering every call from `stub`. Its storage points at a closed local port,
+/// which is only probed for optional tables when it is created.
+pub async fn stub_router(stub: StorageStub) -> StorageRouter {
+    let storage = MetaStorage::new_with_config(MetaStorageConfig {
+        dsn: Some("mysql://127.0.0.1:1/stub?pool_min=0".to_string()),
+        reconnect_gate: Arc::new(ReconnectGate::new(ReconnectLimits {
+            min_interval: Duration::ZERO,
+            ..ReconnectLimits::default()
+        })),
+        ..MetaStorageConfig::default()
+    })
+    .await
+    .expect("stub storage");
+    StorageRouter::new(storage).with_stub(stub)
+}
+
+/// Stub of a small chain holding blocks 5 to 8, answering every storage call of the
+/// request processor with the same result whatever its arguments
+pub fn sample_stub() -> StorageStub {
+    let slots = || Ok::<Vec<Slot>, _>(vec![5, 6, 8]);
+    let revision = |block_time, updated_at| BlockMetaRevision {
+        block_time: Some(block_time),
+        deleted: false,
+        updated_at,
+    };
+    StorageStub::default()
+        .with("getSlot", || Ok(Some::<Slot>(8)))
+        .with("getFirstAvailableBlock", || Ok(Some::<Slot>(5)))
+        .with("getBlockHeight", || Ok(7u64))
+        .with("getBlockTime", || Ok(Utc.timestamp_opt(1_700_000_000, 0).unwrap()))
+        .with("warmBlockTimes", || Ok::<Vec<(Slot, i64)>, _>(vec![]))
+        .with("getBlocks", slots)
+        .with("getBlocksWithLimit", slots)
+        .with("prefetchBlocks", slots)
+        .with("exportBlocks", slots)
+        .with("getBlockCount", || Ok(3u64))
+        .with("blocksExist", || Ok(vec![true, true]))
+        .with("getBackfillStatus", || {
+            Ok(vec![BackfillRange {
+                start_slot: 5,
+                end_slot: 8,
+                state: BackfillState::Loaded,
+            }])
+        })
+        .with("getBlockRewards", || {
+            Ok(vec![BlockReward {
+                pubkey: "Vote111111111111111111111111111111111111111".to_string(),
+                lamports: 1_000,
+                post_balance: 501_000,
+                reward_type: Some("fee".to_string()),
+                commission: None,
+            }])
+        })
+        .with("getBlockMetaVersion", move || {
+            Ok(BlockMetaVersions {
+                current: revision(1_700_000_000, Some(1_700_000_100)),
+                history: vec![revision(1_699_999_999, None)],
+            })
+        })
+        .with("getBlockHeightForSlot", || Ok(4u64))
+        .with("getSlotForBlockHeight", || Ok(Some::<Slot>(5)))
+        .with("getSlotForBlockhash", || Ok(Some::<Slot>(6)))
+        .with("getTransactionCount", || Ok(1_234u64))
+        .with("getRecentPrioritizationFees", || {
+            Ok::<Vec<(Slot, u64)>, _>(vec![(5, 0), (6, 100), (8, 250)])
+        })
+        .with("getFeeStats", || {
+            Ok(vec![FeeStats {
+                slot: Some(5),
+                transactions: 4,
+                total_fee: 20_000,
+                average_fee: 5_000.0,
+                prioritization_fee_percentiles: vec![0, 100, 250, 250],
+                max_prioritization_fee: 250,
+            }])
+        })
+        .with("getInflationReward", || {
+            Ok(vec![InflationReward {
+                address: "11111111111111111111111111111111".to_string(),
+                epoch: 0,
+                effective_slot: 6,
+                amount: 2_500,
+                post_balance: 1_002_500,
+                commission: Some(5),
+            }])
+        })
+        .with("getSchemaInfo", || {
+            Ok(SchemaInfo {
+                tables: vec![TableInfo {
+                    database: "primary".to_string(),
+                    name: "sol_mainnet_block".to_string(),
+                    columns: vec!["id".to_string(), "block_time".to_string()],
+                    estimated_rows: Some(4),
+                }],
+                features: vec![SchemaFeature {
+                    name: "rewards",
+                    enabled: true,
+                    source: Some("block_rewards".to_string()),
+                }],
+            })
+        })
+        .with("setSlotOrphaned", || Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stub_router() {
+        let stub = StorageStub::default()
+            .with("getSlot", || Ok(Some(42u64)))
+            .with("getFirstAvailableBlock", || {
+                Err::<Option<u64>, _>(meta_storage::Error::StorageBusy)
+            });
+        let router = stub_router(stub).await;
+
+        let slot = router
+            .route("getSlot", |storage| async move { storage.get_slot().await })
+            .await;
+        assert_eq!(slot.unwrap(), Some(42));
+        let first_block = router
+            .route("getFirstAvailableBlock", |storage| async move {
+                storage.get_first_available_block().await
+            })
+            .await;
+        assert!(matches!(first_block, Err(meta_storage::Error::StorageBusy)));
+        // Calls without a stubbed result never reach the database
+        let block_time = router
+            .route("getBlockTime", |storage| async move {
+                storage.get_block_time(5).await
+            })
+            .await;
+        assert!(matches!(block_time, Err(meta_storage::Error::StorageUnavailable)));
+    }
+}
@@ -0,0 +1,285 @@
+use {
+    crate::{
+        custom_error::RpcCustomError, middleware::ANONYMOUS_CLIENT_KEY,
+        request_processor::JsonRpcRequestProcessor,
+    },
+    jsonrpc_core::{
+        futures::{
+            future::{self, Either},
+            FutureExt,
+        },
+        middleware::{Middleware, NoopCallFuture, NoopFuture},
+        Call, Failure, Output, Params, Value,
+    },
+    solana_rpc_client_api::request::MAX_GET_CONFIRMED_BLOCKS_RANGE,
+    std::{
+        collections::HashMap,
+        future::Future,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// Slots of a range request that cost as much as one call to a single-slot method
+pub const SLOTS_PER_COST_UNIT: u64 = 100;
+
+/// Clients tracked before buckets that have refilled are dropped
+const MAX_TRACKED_CLIENTS: usize = 100_000;
+
+/// Cost-based throttling: each call costs its method's weight, multiplied for range
+/// methods by the number of started `SLOTS_PER_COST_UNIT` slots, and each client may
+/// spend `cost_per_second`
+#[derive(Debug, Clone)]
+pub struct ThrottleConfig {
+    pub cost_per_second: f64,
+    /// Cost a client may spend at once after being idle
+    pub burst: f64,
+    /// Weight per method; 1 for methods not listed
+    pub method_costs: HashMap<String, f64>,
+}
+
+/// Parse `METHOD=COST`
+pub fn parse_method_cost(value: &str) -> Result<(String, f64), String> {
+    let (method, cost) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected METHOD=COST, got {value}"))?;
+    let cost = cost
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|cost| cost.is_finite() && *cost >= 0.0)
+        .ok_or_else(|| format!("invalid cost for {method}: {cost}"))?;
+    Ok((method.trim().to_string(), cost))
+}
+
+/// Slots spanned by a range call, `None` for methods that are not range scans
+fn range_slots(method: &str, params: &Params) -> Option<u64> {
+    let params = match params {
+        Params::Array(params) => params,
+        _ => return None,
+    };
+    let slot = |index: usize| params.get(index).and_then(Value::as_u64);
+    match method {
+        "getBlocksWithLimit" => slot(1),
+        "getBlocks" | "getBlockCount" | "getFeeStatsRange" | "getBackfillStatus" => {
+            let start_slot = slot(0)?;
+            Some(match slot(1) {
+                Some(end_slot) => end_slot.saturating_sub(start_slot).saturating_add(1),
+                // Open ranges may span up to the default maximum
+                None => MAX_GET_CONFIRMED_BLOCKS_RANGE,
+            })
+        }
+        _ => None,
+    }
+}
+
+impl ThrottleConfig {
+    pub fn cost(&self, method: &str, params: &Params) -> f64 {
+        let weight = self.method_costs.get(method).copied().unwrap_or(1.0);
+        let units = range_slots(method, params)
+            .map(|slots| slots.div_ceil(SLOTS_PER_COST_UNIT).max(1))
+            .unwrap_or(1);
+        weight * units as f64
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Per-client token buckets refilling at the configured cost per second
+pub struct CostThrottle {
+    config: ThrottleConfig,
+    buckets: Mutex<HashMap<Arc<str>, Bucket>>,
+}
+
+impl CostThrottle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::default(),
+        }
+    }
+
+    /// Charge `cost` to `client_key`, returning how long to wait when its budget is
+    /// exhausted. A call costing more than the burst passes once the bucket is full and
+    /// leaves it in debt.
+    pub fn charge(&self, client_key: &Arc<str>, cost: f64, now: Instant) -> Result<(), Duration> {
+        let ThrottleConfig {
+            cost_per_second,
+            burst,
+            ..
+        } = self.config;
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| {
+                bucket.tokens
+                    + now.saturating_duration_since(bucket.updated).as_secs_f64() * cost_per_second
+                    < burst
+            });
+        }
+        let bucket = buckets.entry(client_key.clone()).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * cost_per_second).min(burst);
+        bucket.updated = now;
+
+        let required = cost.min(burst);
+        if bucket.tokens < required {
+            let wait = (required - bucket.tokens) / cost_per_second;
+            return Err(Duration::from_secs_f64(wait));
+        }
+        bucket.tokens -= cost;
+        Ok(())
+    }
+}
+
+/// JSON-RPC middleware rejecting calls of clients that exceed their cost budget.
+/// Clients are identified by an issued API key or proxy-reported address; requests
+/// carrying neither, including those with an unknown key, share one budget.
+#[derive(Clone, Default)]
+pub struct ThrottleMiddleware {
+    throttle: Option<Arc<CostThrottle>>,
+}
+
+impl ThrottleMiddleware {
+    pub fn new(config: Option<ThrottleConfig>) -> Self {
+        Self {
+            throttle: config.map(|config| Arc::new(CostThrottle::new(config))),
+        }
+    }
+}
+
+impl Middleware<JsonRpcRequestProcessor> for ThrottleMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = NoopCallFuture;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        if let (Some(throttle), Call::MethodCall(method_call)) = (&self.throttle, &call) {
+            let client_key = meta
+                .client_key()
+                .cloned()
+                .unwrap_or_else(|| Arc::from(ANONYMOUS_CLIENT_KEY));
+            let cost = throttle.config.cost(&method_call.method, &method_call.params);
+            if let Err(retry_after) = throttle.charge(&client_key, cost, Instant::now()) {
+                debug!("{} throttled on {} costing {}", client_key, method_call.method, cost);
+                return Either::Left(future::ready(Some(Output::Failure(Failure {
+                    jsonrpc: method_call.jsonrpc,
+                    error: RpcCustomError::Throttled {
+                        retry_after_ms: retry_after.as_millis() as u64,
+                    }
+                    .into(),
+                    id: method_call.id.clone(),
+                }))).boxed());
+            }
+        }
+        Either::Right(next(call, meta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            client_ip::TrustedProxies,
+            custom_error::JSON_RPC_THROTTLED,
+            middleware::{client_key, ApiKeys, API_KEY_HEADER},
+            request_processor::JsonRpcConfig,
+        },
+        jsonrpc_core::MetaIoHandler,
+        jsonrpc_http_server::hyper,
+        solana_sdk::exit::Exit,
+        std::sync::RwLock,
+    };
+
+    fn params(value: Value) -> Params {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_cost() {
+        let config = ThrottleConfig {
+            cost_per_second: 10.0,
+            burst: 10.0,
+            method_costs: HashMap::from([("getBlockSummary".to_string(), 2.0)]),
+        };
+        assert_eq!(config.cost("getSlot", &params(json!([]))), 1.0);
+        assert_eq!(config.cost("getBlockSummary", &params(json!([5]))), 2.0);
+        assert_eq!(config.cost("getBlocks", &params(json!([0, 99]))), 1.0);
+        assert_eq!(config.cost("getBlocks", &params(json!([0, 1_000]))), 11.0);
+        assert_eq!(config.cost("getBlocksWithLimit", &params(json!([0, 500]))), 5.0);
+        assert_eq!(config.cost("getBlocks", &params(json!([0]))), 5_000.0);
+    }
+
+    #[test]
+    fn test_charge() {
+        let throttle = CostThrottle::new(ThrottleConfig {
+            cost_per_second: 10.0,
+            burst: 10.0,
+            method_costs: HashMap::new(),
+        });
+        let client: Arc<str> = Arc::from("key:alice");
+        let now = Instant::now();
+        assert!(throttle.charge(&client, 6.0, now).is_ok());
+        assert_eq!(
+            throttle.charge(&client, 6.0, now),
+            Err(Duration::from_millis(200))
+        );
+        assert!(throttle.charge(&client, 6.0, now + Duration::from_millis(200)).is_ok());
+
+        // Calls above the burst pass on a full bucket and leave it in debt
+        let bob: Arc<str> = Arc::from("key:bob");
+        assert!(throttle.charge(&bob, 50.0, now).is_ok());
+        assert!(throttle.charge(&bob, 1.0, now + Duration::from_secs(4)).is_err());
+        assert!(throttle.charge(&bob, 1.0, now + Duration::from_secs(5)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rotated_api_keys_are_throttled() {
+        let mut io = MetaIoHandler::with_middleware(ThrottleMiddleware::new(Some(ThrottleConfig {
+            cost_per_second: 1.0,
+            burst: 2.0,
+            method_costs: HashMap::new(),
+        })));
+        io.add_method_with_meta("ping", |_, _: JsonRpcRequestProcessor| {
+            future::ready(Ok(Value::Null))
+        });
+        let processor = JsonRpcRequestProcessor::new(
+            JsonRpcConfig::default(),
+            Arc::new(RwLock::new(Exit::default())),
+            None,
+        );
+        let api_keys = ApiKeys::new(vec!["alice".to_string()]);
+        let ping = |api_key: String| {
+            let mut headers = hyper::HeaderMap::new();
+            headers.insert(API_KEY_HEADER, api_key.parse().unwrap());
+            let meta = processor
+                .clone()
+                .with_client_key(client_key(&headers, &api_keys, &TrustedProxies::default()));
+            io.handle_request(r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#, meta)
+        };
+
+        for attempt in 0..2 {
+            let response = ping(format!("made-up-{attempt}")).await.unwrap();
+            assert!(response.contains(r#""result":null"#), "{response}");
+        }
+        // A new unknown key draws on the same exhausted budget
+        let response = ping("made-up-2".to_string()).await.unwrap();
+        assert!(response.contains(&JSON_RPC_THROTTLED.to_string()), "{response}");
+        let response = ping("alice".to_string()).await.unwrap();
+        assert!(response.contains(r#""result":null"#), "{response}");
+    }
+}
@@ -3,21 +3,52 @@ use {
         mysql::{
             DEFAULT_PORT,
             DEFAULT_HOST,
+            MySQLClient,
+            MySQLConfig,
             MySQLConnection,
+            BlockPartitionScheme,
+            BlockTimeFormat,
+            IsolationLevel,
+            ReconnectGate,
+            SlotShardConfig,
+            FromTypedRow,
+            RowReader,
+            StatementStats,
+            force_index,
+            map_row,
+            query_budget,
+            redact_dsn,
+            redact_password,
+            query_label,
+            take_column,
+            with_query_budget,
+            with_query_label,
         },
     },
     log::*,
+    mysql::Params,
     solana_sdk::{
         clock::{
             Slot,
         },
+        hash::Hash,
     },
     std::{
         boxed::Box,
+        collections::{HashMap, HashSet},
+        future::Future,
         str::FromStr,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, RwLock,
+        },
     },
     thiserror::Error,
-    tokio::task::JoinError,
+    tokio::{
+        runtime::Handle,
+        sync::{OwnedSemaphorePermit, Semaphore},
+        task::JoinError,
+    },
     time::PrimitiveDateTime,
     chrono::{DateTime, Utc},
 };
@@ -25,7 +56,7 @@ use {
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Storage Error: {0}")]
-    StorageBackendError(Box<dyn std::error::Error + Send>),
+    StorageBackendError(Box<dyn std::error::Error + Send + Sync>),
 
     #[error("I/O Error: {0}")]
     IoError(std::io::Error),
@@ -41,10 +72,62 @@ pub enum Error {
 
     #[error("tokio error")]
     TokioJoinError(JoinError),
+
+    #[error("Storage request timed out")]
+    Timeout,
+
+    #[error("Storage is not available")]
+    StorageUnavailable,
+
+    #[error("Storage is read-only")]
+    ReadOnly,
+
+    #[error("Storage is busy: no connection became available in time")]
+    StorageBusy,
+
+    #[error("Orphan tracking is not available: missing orphaned_slots table")]
+    OrphanTrackingUnavailable,
+
+    #[error("Block metadata versions are not available: missing block_meta_versions table")]
+    BlockMetaVersionsUnavailable,
+
+    #[error("Rewards are not available: missing block_rewards table")]
+    RewardsUnavailable,
+
+    #[error("Inflation rewards are not available: missing inflation_rewards table")]
+    InflationRewardsUnavailable,
+
+    #[error("Fee statistics are not available: missing block_fees table")]
+    FeesUnavailable,
+
+    #[error("Transaction counts are not available: missing transaction count columns")]
+    TransactionCountUnavailable,
+
+    #[error("Block heights are not available: missing block_height column and solana_blocks table")]
+    BlockHeightUnavailable,
+
+    #[error("Blockhash lookups are not available: missing blockhash column")]
+    BlockhashUnavailable,
+
+    #[error("Schema drift: {0}")]
+    SchemaDrift(String),
+
+    #[error("Range too large: the query matched more than {0} rows")]
+    RangeTooLarge(usize),
 }
 
 impl From<crate::mysql::Error> for Error {
     fn from(err: crate::mysql::Error) -> Self {
+        match err {
+            crate::mysql::Error::ReadOnly => return Self::ReadOnly,
+            crate::mysql::Error::PoolExhausted => return Self::StorageBusy,
+            crate::mysql::Error::RangeTooLarge { max_rows } => return Self::RangeTooLarge(max_rows),
+            _ => {}
+        }
+        if err.is_schema_drift() {
+            warn!("MySQL schema drift detected: {}", err);
+            return Self::SchemaDrift(err.to_string());
+        }
         Self::StorageBackendError(Box::new(err))
     }
 }
@@ -57,6 +140,344 @@ impl From<std::io::Error> for Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Block table of a database whose blocks are not partitioned by month
+pub const BLOCK_TABLE: &str = "sol_mainnet_block";
+
+/// Table tracking ingestion progress, one row per slot range:
+///
+/// `backfill_status (start_slot BIGINT UNSIGNED, end_slot BIGINT UNSIGNED,
+///   status ENUM('loaded', 'pending', 'failed'), PRIMARY KEY (start_slot))`
+pub const BACKFILL_STATUS_TABLE: &str = "backfill_status";
+
+/// Maximum number of backfill ranges returned by a single lookup
+pub const MAX_BACKFILL_RANGES: usize = 1000;
+
+/// Ingestion state of a slot range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackfillState {
+    Loaded,
+    Pending,
+    Failed,
+}
+
+impl FromStr for BackfillState {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "loaded" => Ok(Self::Loaded),
+            "pending" => Ok(Self::Pending),
+            "failed" => Ok(Self::Failed),
+            other => Err(format!("unknown backfill status: {other}")),
+        }
+    }
+}
+
+/// Inclusive slot range with its ingestion state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillRange {
+    pub start_slot: Slot,
+    pub end_slot: Slot,
+    pub state: BackfillState,
+}
+
+impl FromTypedRow for BackfillRange {
+    fn from_row(row: &mut RowReader) -> crate::mysql::Result<Self> {
+        Ok(Self {
+            start_slot: row.get("start_slot")?,
+            end_slot: row.get("end_slot")?,
+            state: row.parse("status")?,
+        })
+    }
+}
+
+/// Side table listing slots that were ingested from a fork and must not be served:
+///
+/// `orphaned_slots (slot BIGINT UNSIGNED PRIMARY KEY)`
+pub const ORPHANED_SLOTS_TABLE: &str = "orphaned_slots";
+
+/// Condition excluding orphaned slots from queries keyed by slot `id`
+const NOT_ORPHANED: &str = "id NOT IN (SELECT slot FROM orphaned_slots)";
+
+/// Optional `sol_mainnet_block` flag, `is_deleted TINYINT(1) NOT NULL DEFAULT 0`, set
+/// by the warehouse instead of deleting a row found to be wrong. Flagged rows are left
+/// out of every read but stay available for audits.
+pub const SOFT_DELETE_COLUMN: &str = "is_deleted";
+
+/// Optional `sol_mainnet_block` column with the time a row was last corrected
+pub const UPDATED_AT_COLUMN: &str = "updated_at";
+
+/// Condition excluding soft-deleted rows
+const NOT_DELETED: &str = "is_deleted = 0";
+
+const NOT_ORPHANED_OR_DELETED: &str =
+    "id NOT IN (SELECT slot FROM orphaned_slots) AND is_deleted = 0";
+
+/// Condition excluding orphaned slots from side tables keeping the slot in `column`
+fn not_orphaned_slot(column: &str) -> String {
+    format!("`{}` NOT IN (SELECT slot FROM `{}`)", column, ORPHANED_SLOTS_TABLE)
+}
+
+/// Optional table keeping the superseded versions of `sol_mainnet_block` rows, appended
+/// by the warehouse before each correction:
+///
+/// `block_meta_versions (slot BIGINT UNSIGNED, block_time <as in sol_mainnet_block>,
+///   is_deleted TINYINT(1), updated_at DATETIME, KEY (slot))`
+pub const BLOCK_META_VERSIONS_TABLE: &str = "block_meta_versions";
+
+/// One version of the stored metadata of a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockMetaRevision {
+    /// Block time in unix seconds
+    pub block_time: Option<i64>,
+    pub deleted: bool,
+    /// Time of the correction in unix seconds, when recorded
+    pub updated_at: Option<i64>,
+}
+
+/// Read from `(block_time, is_deleted, updated_at)` columns
+impl FromTypedRow for BlockMetaRevision {
+    fn from_row(row: &mut RowReader) -> crate::mysql::Result<Self> {
+        Ok(Self {
+            block_time: row.at(0)?,
+            deleted: row.at::<Option<bool>>(1)?.unwrap_or_default(),
+            updated_at: row.at(2)?,
+        })
+    }
+}
+
+/// Current metadata of a block and the versions it superseded, oldest first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockMetaVersions {
+    pub current: BlockMetaRevision,
+    pub history: Vec<BlockMetaRevision>,
+}
+
+/// Optional table holding the rewards paid out in each block:
+///
+/// `block_rewards (slot BIGINT UNSIGNED, pubkey VARCHAR(44), lamports BIGINT,
+///   post_balance BIGINT UNSIGNED, reward_type VARCHAR(16) NULL,
+///   commission TINYINT UNSIGNED NULL, KEY (slot))`
+pub const BLOCK_REWARDS_TABLE: &str = "block_rewards";
+
+/// Reward paid to an account in a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockReward {
+    pub pubkey: String,
+    pub lamports: i64,
+    pub post_balance: u64,
+    pub reward_type: Option<String>,
+    pub commission: Option<u8>,
+}
+
+impl FromTypedRow for BlockReward {
+    fn from_row(row: &mut RowReader) -> crate::mysql::Result<Self> {
+        Ok(Self {
+            pubkey: row.get("pubkey")?,
+            lamports: row.get("lamports")?,
+            post_balance: row.get("post_balance")?,
+            reward_type: row.get("reward_type")?,
+            commission: row.get("commission")?,
+        })
+    }
+}
+
+/// Optional per-address epoch rewards index:
+///
+/// `inflation_rewards (address VARCHAR(44), epoch BIGINT UNSIGNED,
+///   effective_slot BIGINT UNSIGNED, amount BIGINT UNSIGNED, post_balance BIGINT UNSIGNED,
+///   commission TINYINT UNSIGNED NULL, PRIMARY KEY (address, epoch))`
+pub const INFLATION_REWARDS_TABLE: &str = "inflation_rewards";
+
+/// Inflation reward credited to an address for an epoch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InflationReward {
+    pub address: String,
+    pub epoch: u64,
+    pub effective_slot: Slot,
+    pub amount: u64,
+    pub post_balance: u64,
+    pub commission: Option<u8>,
+}
+
+impl FromTypedRow for InflationReward {
+    fn from_row(row: &mut RowReader) -> crate::mysql::Result<Self> {
+        Ok(Self {
+            address: row.get("address")?,
+            epoch: row.get("epoch")?,
+            effective_slot: row.get("effective_slot")?,
+            amount: row.get("amount")?,
+            post_balance: row.get("post_balance")?,
+            commission: row.get("commission")?,
+        })
+    }
+}
+
+/// Optional table with the fee paid by each transaction:
+///
+/// `block_fees (slot BIGINT UNSIGNED, fee BIGINT UNSIGNED,
+///   prioritization_fee BIGINT UNSIGNED, KEY (slot))`
+///
+/// `prioritization_fee` is in micro-lamports per compute unit.
+pub const BLOCK_FEES_TABLE: &str = "block_fees";
+
+/// Priority fee percentiles reported by fee statistics
+pub const FEE_PERCENTILES: [u8; 4] = [50, 75, 90, 99];
+
+/// Fee statistics of a block, or of a range of blocks when `slot` is unset
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeStats {
+    pub slot: Option<Slot>,
+    pub transactions: u64,
+    pub total_fee: u64,
+    pub average_fee: f64,
+    /// Prioritization fee at each of `FEE_PERCENTILES`
+    pub prioritization_fee_percentiles: Vec<u64>,
+    pub max_prioritization_fee: u64,
+}
+
+/// Aggregates over no fees are NULL and read as zero
+impl FromTypedRow for FeeStats {
+    fn from_row(row: &mut RowReader) -> crate::mysql::Result<Self> {
+        let prioritization_fee_percentiles = FEE_PERCENTILES
+            .iter()
+            .map(|percentile| {
+                Ok(row
+                    .get::<Option<u64>>(&format!("p{}", percentile))?
+                    .unwrap_or_default())
+            })
+            .collect::<crate::mysql::Result<_>>()?;
+        Ok(Self {
+            slot: row.get("slot")?,
+            transactions: row.get("transactions")?,
+            total_fee: row.get::<Option<u64>>("total_fee")?.unwrap_or_default(),
+            average_fee: row.get::<Option<f64>>("average_fee")?.unwrap_or_default(),
+            prioritization_fee_percentiles,
+            max_prioritization_fee: row
+                .get::<Option<u64>>("max_prioritization_fee")?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Optional `sol_mainnet_block` column with the number of transactions in the block
+pub const TRANSACTION_COUNT_COLUMN: &str = "transaction_count";
+
+/// Optional `sol_mainnet_block` column with the number of transactions up to and
+/// including the block
+pub const CUMULATIVE_TRANSACTION_COUNT_COLUMN: &str = "cumulative_transaction_count";
+
+/// Where the total transaction count is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransactionCountSource {
+    /// Read the cumulative column of the latest block
+    Cumulative,
+    /// Sum the per-block counts, adding only blocks newer than the last sum
+    PerBlock,
+    Unavailable,
+}
+
+/// Optional `sol_mainnet_block` column with the height of the block
+pub const BLOCK_HEIGHT_COLUMN: &str = "block_height";
+
+/// Table mapping slots to block heights, read when the block table has no height column:
+///
+/// `solana_blocks (id BIGINT UNSIGNED PRIMARY KEY, block_height BIGINT UNSIGNED)`
+pub const BLOCK_HEIGHTS_TABLE: &str = "solana_blocks";
+
+/// Optional `sol_mainnet_block` column with the base58 hash of the block,
+/// `blockhash VARCHAR(44)`, indexed to look blocks up by hash
+pub const BLOCKHASH_COLUMN: &str = "blockhash";
+
+/// Where block heights are read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockHeightSource {
+    /// The height column of the block table
+    Column,
+    /// `BLOCK_HEIGHTS_TABLE`, keyed by the slots of the block table
+    Mapping,
+    Unavailable,
+}
+
+/// Table of a database the service reads, as reported by `get_schema_info`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableInfo {
+    /// `primary`, or the shard holding the table
+    pub database: String,
+    pub name: String,
+    pub columns: Vec<String>,
+    /// Estimate of the server, which may be well off for InnoDB tables
+    pub estimated_rows: Option<u64>,
+}
+
+/// Optional feature of the service and whether the schema enables it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaFeature {
+    pub name: &'static str,
+    pub enabled: bool,
+    /// Table or column the feature is served from, when enabled
+    pub source: Option<String>,
+}
+
+/// Tables of the storage and the optional features they enable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaInfo {
+    pub tables: Vec<TableInfo>,
+    pub features: Vec<SchemaFeature>,
+}
+
+/// MySQL error code for a missing table
+const ER_NO_SUCH_TABLE: u16 = 1146;
+
+/// Read the block time of `key`, decoding the column according to `format`. `None`
+/// when the stored value is out of range.
+async fn read_block_time(
+    mysql: &MySQLClient,
+    table: &str,
+    key: &str,
+    format: BlockTimeFormat,
+) -> crate::mysql::Result<Option<DateTime<Utc>>> {
+    Ok(match format {
+        BlockTimeFormat::DateTime => {
+            let block_time = mysql
+                .get_single_value::<PrimitiveDateTime>(table, "block_time", "id", key)
+                .await?
+                .assume_utc();
+            DateTime::<Utc>::from_timestamp(
+                block_time.unix_timestamp(),
+                block_time.microsecond() * 1000,
+            )
+        }
+        BlockTimeFormat::EpochSeconds => {
+            let seconds = mysql
+                .get_single_value::<i64>(table, "block_time", "id", key)
+                .await?;
+            DateTime::<Utc>::from_timestamp(seconds, 0)
+        }
+        BlockTimeFormat::EpochMillis => {
+            let millis = mysql
+                .get_single_value::<i64>(table, "block_time", "id", key)
+                .await?;
+            DateTime::<Utc>::from_timestamp_millis(millis)
+        }
+    })
+}
+
+/// Read the `(slot, block_time)` rows returned by `query` as they arrive, without
+/// buffering the rows themselves
+async fn read_block_times(
+    mysql: &MySQLClient,
+    table: &str,
+    query: &str,
+) -> Result<Vec<(Slot, i64)>> {
+    Ok(mysql
+        .execute_query_fold(query, Vec::new, |mut block_times, row| {
+            block_times.push(map_row(row, table)?);
+            Ok(block_times)
+        })
+        .await?)
+}
+
 pub fn slot_to_key(slot: Slot) -> String {
     slot.to_string()
 }
@@ -72,7 +493,144 @@ pub fn key_to_slot(key: &str) -> Option<Slot> {
     }
 }
 
-#[derive(Debug)]
+/// Map the error of a read keyed by `slot`, a missing row meaning the block is unknown
+fn block_read_error(slot: Slot, err: crate::mysql::Error) -> Error {
+    match err {
+        crate::mysql::Error::RowNotFound => Error::BlockNotFound(slot),
+        other => other.into(),
+    }
+}
+
+/// Query for the block times of a table range, `block_time` converting the stored time
+fn block_times_query(
+    block_time: &str,
+    table: &str,
+    force_index: &str,
+    range_filter: String,
+    read_filter: Option<&str>,
+) -> String {
+    let filter = match read_filter {
+        Some(read_filter) => format!("{} AND {}", range_filter, read_filter),
+        None => range_filter,
+    };
+    format!(
+        "SELECT id, {} FROM `{}`{} WHERE {} ORDER BY id",
+        block_time, table, force_index, filter
+    )
+}
+
+/// Query for the slot and block time of the first stored block at or after `slot`, or of
+/// the last one if `last` is set, within a table range. Both only seek the primary key.
+fn block_time_at_query(
+    block_time: &str,
+    table: &str,
+    range_filter: &str,
+    slot: Slot,
+    last: bool,
+    read_filter: Option<&str>,
+) -> String {
+    let (filter, order) = if last {
+        (range_filter.to_string(), "DESC")
+    } else {
+        (format!("{} AND id >= {}", range_filter, slot), "ASC")
+    };
+    let filter = match read_filter {
+        Some(read_filter) => format!("{} AND {}", filter, read_filter),
+        None => filter,
+    };
+    format!(
+        "SELECT id, {} FROM `{}` WHERE {} ORDER BY id {} LIMIT 1",
+        block_time, table, filter, order
+    )
+}
+
+/// Append the optional side table condition `orphan_filter` to `condition`
+fn with_orphan_filter(condition: String, orphan_filter: Option<&str>) -> String {
+    match orphan_filter {
+        Some(orphan_filter) => format!("{} AND {}", condition, orphan_filter),
+        None => condition,
+    }
+}
+
+fn block_rewards_query(slot: Slot, orphan_filter: Option<&str>) -> String {
+    format!(
+        "SELECT pubkey, lamports, post_balance, reward_type, commission FROM `{}` \
+         WHERE {} ORDER BY pubkey",
+        BLOCK_REWARDS_TABLE,
+        with_orphan_filter(format!("slot = {}", slot), orphan_filter)
+    )
+}
+
+/// Query for the inflation rewards of `address_count` addresses in one epoch, binding the
+/// epoch and then each address as parameters
+fn inflation_rewards_query(address_count: usize, orphan_filter: Option<&str>) -> String {
+    let placeholders = vec!["?"; address_count].join(", ");
+    format!(
+        "SELECT address, epoch, effective_slot, amount, post_balance, commission FROM `{}` \
+         WHERE {}",
+        INFLATION_REWARDS_TABLE,
+        with_orphan_filter(
+            format!("epoch = ? AND address IN ({})", placeholders),
+            orphan_filter
+        )
+    )
+}
+
+fn fee_stats_query(
+    start_slot: Slot,
+    end_slot: Slot,
+    per_block: bool,
+    orphan_filter: Option<&str>,
+) -> String {
+    let (slot_column, partition, group_by) = if per_block {
+        ("slot", "PARTITION BY slot", "GROUP BY slot ORDER BY slot")
+    } else {
+        ("NULL", "", "")
+    };
+    let percentiles = FEE_PERCENTILES
+        .iter()
+        .map(|percentile| {
+            format!(
+                "MAX(CASE WHEN rn = GREATEST(CEIL(cnt * {} / 100), 1) \
+                 THEN prioritization_fee END) AS p{}",
+                percentile, percentile
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let filter = with_orphan_filter(
+        format!("slot >= {} AND slot <= {}", start_slot, end_slot),
+        orphan_filter,
+    );
+    format!(
+        "SELECT {slot_column} AS slot, COUNT(*) AS transactions, \
+         CAST(SUM(fee) AS UNSIGNED) AS total_fee, CAST(AVG(fee) AS DOUBLE) AS average_fee, \
+         {percentiles}, MAX(prioritization_fee) AS max_prioritization_fee \
+         FROM (SELECT slot, fee, prioritization_fee, \
+         ROW_NUMBER() OVER ({partition} ORDER BY prioritization_fee) AS rn, \
+         COUNT(*) OVER ({partition}) AS cnt \
+         FROM `{table}` WHERE {filter}) ranked \
+         {group_by}",
+        table = BLOCK_FEES_TABLE,
+    )
+}
+
+fn min_prioritization_fees_query(
+    start_slot: Slot,
+    end_slot: Slot,
+    orphan_filter: Option<&str>,
+) -> String {
+    format!(
+        "SELECT slot, MIN(prioritization_fee) FROM `{}` \
+         WHERE {} GROUP BY slot ORDER BY slot",
+        BLOCK_FEES_TABLE,
+        with_orphan_filter(
+            format!("slot >= {} AND slot <= {}", start_slot, end_slot),
+            orphan_filter
+        )
+    )
+}
+
 pub struct MetaStorageConfig {
     pub read_only: bool,
     pub timeout: Option<std::time::Duration>,
@@ -81,6 +639,51 @@ pub struct MetaStorageConfig {
     pub username: String,
     pub password: String,
     pub db_name: String,
+    pub dsn: Option<String>,
+    pub prewarm_connections: usize,
+    /// Gate every connection is established through, shared by every database of the
+    /// process
+    pub reconnect_gate: Arc<ReconnectGate>,
+    pub acquire_timeout: Option<std::time::Duration>,
+    pub replica_dsns: Vec<String>,
+    pub hedge_delay: Option<std::time::Duration>,
+    pub max_replica_lag: Option<std::time::Duration>,
+    pub shards: Vec<SlotShardConfig>,
+    pub block_time_format: BlockTimeFormat,
+    pub block_partitions: Option<BlockPartitionScheme>,
+    pub index_hints: HashMap<String, String>,
+    pub isolation_level: Option<IsolationLevel>,
+    pub max_rows: Option<usize>,
+}
+
+impl std::fmt::Debug for MetaStorageConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("MetaStorageConfig")
+            .field("read_only", &self.read_only)
+            .field("timeout", &self.timeout)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &redact_password(&self.password))
+            .field("db_name", &self.db_name)
+            .field("dsn", &self.dsn.as_deref().map(redact_dsn))
+            .field("prewarm_connections", &self.prewarm_connections)
+            .field("reconnect_gate", &self.reconnect_gate)
+            .field("acquire_timeout", &self.acquire_timeout)
+            .field(
+                "replica_dsns",
+                &self.replica_dsns.iter().map(|dsn| redact_dsn(dsn)).collect::<Vec<_>>(),
+            )
+            .field("hedge_delay", &self.hedge_delay)
+            .field("max_replica_lag", &self.max_replica_lag)
+            .field("shards", &self.shards)
+            .field("block_time_format", &self.block_time_format)
+            .field("block_partitions", &self.block_partitions)
+            .field("index_hints", &self.index_hints)
+            .field("isolation_level", &self.isolation_level)
+            .field("max_rows", &self.max_rows)
+            .finish()
+    }
 }
 
 impl Default for MetaStorageConfig {
@@ -93,13 +696,294 @@ impl Default for MetaStorageConfig {
             username: String::new(),
             password: String::new(),
             db_name: String::new(),
+            dsn: None,
+            prewarm_connections: 0,
+            reconnect_gate: Arc::default(),
+            acquire_timeout: None,
+            replica_dsns: vec![],
+            hedge_delay: None,
+            max_replica_lag: None,
+            shards: vec![],
+            block_time_format: BlockTimeFormat::default(),
+            block_partitions: None,
+            index_hints: HashMap::new(),
+            isolation_level: None,
+            max_rows: None,
         }
     }
 }
 
+/// A database holding the blocks of `start_slot..end_slot`
+#[derive(Clone)]
+struct SlotShard {
+    start_slot: Slot,
+    end_slot: Option<Slot>,
+    connection: MySQLConnection,
+}
+
+impl SlotShard {
+    fn contains(&self, slot: Slot) -> bool {
+        slot >= self.start_slot && self.end_slot.map_or(true, |end_slot| slot < end_slot)
+    }
+}
+
+/// Monthly block table holding the blocks from `start_slot` up to the start of the next
+/// partition
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BlockPartition {
+    table: String,
+    start_slot: Slot,
+}
+
+/// Partition holding `slot`: the last one starting at or before it
+fn partition_for(partitions: &[BlockPartition], slot: Slot) -> Option<&BlockPartition> {
+    let index = partitions.partition_point(|partition| partition.start_slot <= slot);
+    index.checked_sub(1).map(|index| &partitions[index])
+}
+
+/// Parts of `start_slot..end_slot` held by each partition, as `(table, start, end)` in
+/// slot order; partitions outside the range are left out
+fn partition_ranges(
+    partitions: &[BlockPartition],
+    start_slot: Slot,
+    end_slot: Option<Slot>,
+) -> Vec<(String, Slot, Option<Slot>)> {
+    partitions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, partition)| {
+            let partition_end = partitions.get(index + 1).map(|next| next.start_slot);
+            let start = start_slot.max(partition.start_slot);
+            let end = match (end_slot, partition_end) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            end.map_or(true, |end| start < end)
+                .then(|| (partition.table.clone(), start, end))
+        })
+        .collect()
+}
+
+/// Part of a slot range served by one database table
+struct ShardRange {
+    start_slot: Slot,
+    /// Exclusive
+    end_slot: Option<Slot>,
+    connection: MySQLConnection,
+    table: String,
+}
+
+impl ShardRange {
+    /// Inclusive upper bound
+    fn last_slot(&self) -> Option<Slot> {
+        self.end_slot.map(|end_slot| end_slot.saturating_sub(1))
+    }
+
+    fn filter(&self) -> String {
+        match self.end_slot {
+            Some(end_slot) => format!("id >= {} AND id < {}", self.start_slot, end_slot),
+            None => format!("id >= {}", self.start_slot),
+        }
+    }
+}
+
+/// Bounds of the batch size used to scan block keys
+const SCAN_BATCH_MIN: usize = 1_000;
+const SCAN_BATCH_MAX: usize = 50_000;
+/// Scan batches completing faster than this grow, slower ones shrink
+const SCAN_BATCH_TARGET: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Read up to `limit` block keys of `start_slot..=end_slot` in batches sized to keep each
+/// query near `SCAN_BATCH_TARGET`, so large scans never hold one long-running query
+async fn scan_block_keys(
+    mysql: MySQLClient,
+    table: &str,
+    start_slot: Slot,
+    end_slot: Option<Slot>,
+    filter: Option<&str>,
+    index: Option<&str>,
+    limit: usize,
+) -> crate::mysql::Result<Vec<u64>> {
+    let end_key = end_slot.map(slot_to_key);
+    let mut keys = vec![];
+    let mut next_slot = start_slot;
+    let mut batch_size = SCAN_BATCH_MIN.min(limit);
+    while keys.len() < limit && end_slot.map_or(true, |end_slot| next_slot <= end_slot) {
+        let batch_size_used = batch_size.min(limit - keys.len());
+        let start = std::time::Instant::now();
+        let batch: Vec<u64> = mysql
+            .get_row_keys(
+                table,
+                Some(&slot_to_key(next_slot)),
+                end_key.as_deref(),
+                filter,
+                index,
+                batch_size_used as i64,
+            )
+            .await?;
+        let elapsed = start.elapsed();
+        let exhausted = batch.len() < batch_size_used;
+        match batch.last() {
+            Some(&last_slot) => next_slot = last_slot + 1,
+            None => break,
+        }
+        keys.extend(batch);
+        if exhausted {
+            break;
+        }
+        batch_size = if elapsed < SCAN_BATCH_TARGET / 2 {
+            (batch_size * 2).min(SCAN_BATCH_MAX)
+        } else if elapsed > SCAN_BATCH_TARGET {
+            (batch_size / 2).max(SCAN_BATCH_MIN)
+        } else {
+            batch_size
+        };
+    }
+    Ok(keys)
+}
+
+/// Weight of the newest sample in a replica's moving latency average
+const REPLICA_LATENCY_SMOOTHING: f64 = 0.2;
+
+/// Latency and lag measurements of a read replica, updated by `probe_replicas`
+#[derive(Debug)]
+struct ReplicaHealth {
+    /// Exponentially smoothed probe latency, in microseconds; zero until measured
+    latency_us: AtomicU64,
+    /// Whether the last probe succeeded within the allowed replication lag
+    healthy: AtomicBool,
+}
+
+impl ReplicaHealth {
+    fn new() -> Self {
+        Self {
+            latency_us: AtomicU64::new(0),
+            healthy: AtomicBool::new(true),
+        }
+    }
+
+    fn record_latency(&self, latency: std::time::Duration) {
+        let sample = latency.as_micros() as u64;
+        let previous = self.latency_us.load(Ordering::Relaxed);
+        let smoothed = if previous == 0 {
+            sample
+        } else {
+            (previous as f64 * (1.0 - REPLICA_LATENCY_SMOOTHING)
+                + sample as f64 * REPLICA_LATENCY_SMOOTHING) as u64
+        };
+        self.latency_us.store(smoothed, Ordering::Relaxed);
+    }
+}
+
+/// Hedge requests allowed at once, counting losing ones still running on the blocking
+/// pool; reads beyond it wait for the primary alone
+const MAX_IN_FLIGHT_HEDGES: usize = 16;
+
+/// Index of the replica primary reads should be hedged to: the fastest healthy one, or
+/// the fastest overall when every replica is demoted
+fn select_replica(replicas: impl Iterator<Item = (u64, bool)>) -> Option<usize> {
+    replicas
+        .enumerate()
+        .min_by_key(|(_, (latency_us, healthy))| (!healthy, *latency_us))
+        .map(|(index, _)| index)
+}
+
+/// Index of the replica reads should go to: the fastest healthy one, or `None` when every
+/// replica is demoted and reads stay on the primary
+fn select_read_replica(replicas: impl Iterator<Item = (u64, bool)>) -> Option<usize> {
+    replicas
+        .enumerate()
+        .filter(|(_, (_, healthy))| *healthy)
+        .min_by_key(|(_, (latency_us, _))| *latency_us)
+        .map(|(index, _)| index)
+}
+
+/// Check for an optional table, treating lookup failures as absence
+async fn table_exists(connection: &MySQLConnection, table_name: &str) -> bool {
+    connection
+        .client()
+        .table_exists(table_name)
+        .await
+        .unwrap_or_else(|err| {
+            warn!("Failed to look up the {} table: {}", table_name, err);
+            false
+        })
+}
+
+/// Check for an optional column, treating lookup failures as absence
+async fn column_exists(connection: &MySQLConnection, table_name: &str, column_name: &str) -> bool {
+    connection
+        .client()
+        .column_exists(table_name, column_name)
+        .await
+        .unwrap_or_else(|err| {
+            warn!("Failed to look up the {}.{} column: {}", table_name, column_name, err);
+            false
+        })
+}
+
+/// Find the block tables named after `scheme` and the first slot each holds. Tables
+/// created ahead of their month hold no blocks yet and are skipped.
+async fn discover_partitions(
+    connection: &MySQLConnection,
+    scheme: &BlockPartitionScheme,
+) -> Result<Vec<BlockPartition>> {
+    let client = connection.client();
+    let mut partitions = vec![];
+    for table in client.list_tables().await? {
+        if scheme.parse_table_name(&table).is_none() {
+            continue;
+        }
+        if let Some(start_slot) = client.get_first_key::<u64>(&table, "id", None).await? {
+            partitions.push(BlockPartition { table, start_slot });
+        }
+    }
+    partitions.sort_by_key(|partition| partition.start_slot);
+    Ok(partitions)
+}
+
 #[derive(Clone)]
 pub struct MetaStorage {
+    read_only: bool,
+    /// Whether the orphaned slots table exists and reads must filter on it
+    orphan_tracking: bool,
+    /// Whether blocks have a soft-delete flag reads must filter on
+    soft_delete: bool,
+    /// Whether blocks record their last correction time
+    updated_at_available: bool,
+    /// Whether the block metadata versions table exists
+    versions_available: bool,
+    /// Whether the block rewards table exists
+    rewards_available: bool,
+    /// Whether the inflation rewards table exists
+    inflation_rewards_available: bool,
+    /// Whether the block fees table exists
+    fees_available: bool,
+    transaction_count_source: TransactionCountSource,
+    block_height_source: BlockHeightSource,
+    /// Whether blocks record their hash
+    blockhash_available: bool,
+    /// Version of the database server, read at startup
+    server_version: Option<String>,
+    /// Last slot included in the running transaction total, and the total
+    transaction_count_total: Arc<tokio::sync::Mutex<Option<(Slot, u64)>>>,
     connection: MySQLConnection,
+    replicas: Vec<MySQLConnection>,
+    replica_health: Arc<Vec<ReplicaHealth>>,
+    hedge_delay: Option<std::time::Duration>,
+    /// Slots of `MAX_IN_FLIGHT_HEDGES`, each held until its hedge request completes
+    hedge_permits: Arc<Semaphore>,
+    max_replica_lag: Option<std::time::Duration>,
+    /// Databases holding slot ranges of the block table; empty when the primary holds
+    /// every block. Each shard mirrors the primary's block and orphaned slot tables.
+    shards: Arc<Vec<SlotShard>>,
+    block_time_format: BlockTimeFormat,
+    /// Naming scheme of the monthly block tables replacing `BLOCK_TABLE`, if any
+    partition_scheme: Option<BlockPartitionScheme>,
+    /// Non-empty partitions found by the last `refresh_partitions`, in slot order
+    partitions: Arc<RwLock<Arc<Vec<BlockPartition>>>>,
+    /// Index forced on block range scans by RPC method, `*` standing for the others
+    index_hints: Arc<HashMap<String, String>>,
 }
 
 impl MetaStorage {
@@ -124,125 +1008,1670 @@ impl MetaStorage {
             username,
             password,
             db_name,
+            dsn,
+            prewarm_connections,
+            reconnect_gate,
+            acquire_timeout,
+            replica_dsns,
+            hedge_delay,
+            max_replica_lag,
+            shards,
+            block_time_format,
+            block_partitions,
+            index_hints,
+            isolation_level,
+            max_rows,
         } = config;
-        let dsn = format!("mysql://{}:{}@{}:{}/{}", username, password, host, port, db_name);
+        if block_partitions.is_some() && !shards.is_empty() {
+            return Err(crate::mysql::Error::InvalidShard(
+                "slot-range shards cannot be combined with block partitions".to_string(),
+            )
+            .into());
+        }
+        let url = MySQLConfig {
+            host,
+            port,
+            username,
+            password,
+            db_name,
+            timeout,
+            dsn,
+            prewarm_connections,
+            reconnect_gate: reconnect_gate.clone(),
+            acquire_timeout,
+            replica_dsns: vec![],
+            hedge_delay,
+            max_replica_lag,
+            shards: vec![],
+            block_time_format,
+            block_partitions: None,
+            index_hints: HashMap::new(),
+            isolation_level,
+            max_rows,
+        }
+            .url()?;
         let connection = MySQLConnection::new(
-            dsn.as_str(),
+            url.as_str(),
             read_only,
             timeout,
+            acquire_timeout,
+            reconnect_gate.clone(),
+            isolation_level,
+            max_rows,
         )
             .await?;
 
+        if prewarm_connections > 0 {
+            let start = std::time::Instant::now();
+            match connection.prewarm(prewarm_connections) {
+                Ok(count) => info!(
+                    "Pre-warmed {} MySQL connections in {:?}",
+                    count,
+                    start.elapsed()
+                ),
+                Err(err) => warn!("MySQL connection pre-warming failed: {}", err),
+            }
+        }
+
+        let mut replicas = Vec::with_capacity(replica_dsns.len());
+        for replica_dsn in replica_dsns {
+            let replica_url = crate::mysql::normalize_dsn(&replica_dsn)?;
+            replicas.push(
+                MySQLConnection::new(
+                    replica_url.as_str(),
+                    read_only,
+                    timeout,
+                    acquire_timeout,
+                    reconnect_gate.clone(),
+                    isolation_level,
+                    max_rows,
+                )
+                .await?,
+            );
+        }
+        if !replicas.is_empty() {
+            info!("Using {} MySQL read replicas", replicas.len());
+        }
+
+        let mut slot_shards = Vec::with_capacity(shards.len());
+        for shard in shards {
+            info!(
+                "Serving slots {}..{} from a separate MySQL database",
+                shard.start_slot,
+                shard.end_slot.map(|slot| slot.to_string()).unwrap_or_default()
+            );
+            slot_shards.push(SlotShard {
+                start_slot: shard.start_slot,
+                end_slot: shard.end_slot,
+                connection: MySQLConnection::new(
+                    &shard.dsn,
+                    read_only,
+                    timeout,
+                    acquire_timeout,
+                    reconnect_gate.clone(),
+                    isolation_level,
+                    max_rows,
+                )
+                .await?,
+            });
+        }
+
+        let partitions = match &block_partitions {
+            Some(scheme) => {
+                let partitions = discover_partitions(&connection, scheme).await?;
+                info!("Serving blocks from {} monthly partitions", partitions.len());
+                partitions
+            }
+            None => vec![],
+        };
+        // Optional columns are detected on the newest block table
+        let block_table = partitions
+            .last()
+            .map_or(BLOCK_TABLE, |partition| partition.table.as_str());
+
+        let orphan_tracking = table_exists(&connection, ORPHANED_SLOTS_TABLE).await;
+        if orphan_tracking {
+            info!("Excluding slots listed in {} from reads", ORPHANED_SLOTS_TABLE);
+        }
+        let soft_delete = column_exists(&connection, block_table, SOFT_DELETE_COLUMN).await;
+        if soft_delete {
+            info!("Excluding blocks flagged {} from reads", SOFT_DELETE_COLUMN);
+        }
+        let updated_at_available =
+            column_exists(&connection, block_table, UPDATED_AT_COLUMN).await;
+        let versions_available = table_exists(&connection, BLOCK_META_VERSIONS_TABLE).await;
+        if versions_available {
+            info!("Serving block metadata versions from {}", BLOCK_META_VERSIONS_TABLE);
+        }
+        let rewards_available = table_exists(&connection, BLOCK_REWARDS_TABLE).await;
+        if rewards_available {
+            info!("Serving block rewards from {}", BLOCK_REWARDS_TABLE);
+        }
+        let inflation_rewards_available = table_exists(&connection, INFLATION_REWARDS_TABLE).await;
+        if inflation_rewards_available {
+            info!("Serving inflation rewards from {}", INFLATION_REWARDS_TABLE);
+        }
+        let fees_available = table_exists(&connection, BLOCK_FEES_TABLE).await;
+        if fees_available {
+            info!("Serving fee statistics from {}", BLOCK_FEES_TABLE);
+        }
+        let transaction_count_source =
+            if column_exists(&connection, block_table, CUMULATIVE_TRANSACTION_COUNT_COLUMN).await {
+                TransactionCountSource::Cumulative
+            } else if column_exists(&connection, block_table, TRANSACTION_COUNT_COLUMN).await {
+                TransactionCountSource::PerBlock
+            } else {
+                TransactionCountSource::Unavailable
+            };
+        debug!("Transaction count source: {:?}", transaction_count_source);
+        let block_height_source =
+            if column_exists(&connection, block_table, BLOCK_HEIGHT_COLUMN).await {
+                BlockHeightSource::Column
+            } else if table_exists(&connection, BLOCK_HEIGHTS_TABLE).await {
+                BlockHeightSource::Mapping
+            } else {
+                BlockHeightSource::Unavailable
+            };
+        debug!("Block height source: {:?}", block_height_source);
+        let server_version = match connection.client().server_version().await {
+            Ok(server_version) => Some(server_version),
+            Err(err) => {
+                warn!("Failed to read the database server version: {}", err);
+                None
+            }
+        };
+        let blockhash_available = column_exists(&connection, block_table, BLOCKHASH_COLUMN).await;
+        if blockhash_available {
+            info!("Serving blockhash lookups from the {} column", BLOCKHASH_COLUMN);
+        }
+        for (method, index) in &index_hints {
+            info!("Forcing index {} on the block range scans of {}", index, method);
+        }
+
         Ok(Self {
+            read_only,
+            orphan_tracking,
+            soft_delete,
+            updated_at_available,
+            versions_available,
+            rewards_available,
+            inflation_rewards_available,
+            fees_available,
+            transaction_count_source,
+            block_height_source,
+            blockhash_available,
+            server_version,
+            transaction_count_total: Arc::default(),
             connection,
+            replica_health: Arc::new(replicas.iter().map(|_| ReplicaHealth::new()).collect()),
+            replicas,
+            hedge_delay,
+            hedge_permits: Arc::new(Semaphore::new(MAX_IN_FLIGHT_HEDGES)),
+            max_replica_lag,
+            shards: Arc::new(slot_shards),
+            block_time_format,
+            partition_scheme: block_partitions,
+            partitions: Arc::new(RwLock::new(Arc::new(partitions))),
+            index_hints: Arc::new(index_hints),
+        })
+    }
+
+    pub fn has_replicas(&self) -> bool {
+        !self.replicas.is_empty()
+    }
+
+    /// Replicas demoted by their last health probe
+    pub fn unhealthy_replicas(&self) -> usize {
+        self.replica_health
+            .iter()
+            .filter(|health| !health.healthy.load(Ordering::Relaxed))
+            .count()
+    }
+
+    /// Prepared statement counters summed over the primary, replicas and shards
+    pub fn statement_stats(&self) -> StatementStats {
+        std::iter::once(&self.connection)
+            .chain(self.replicas.iter())
+            .chain(self.shards.iter().map(|shard| &shard.connection))
+            .map(MySQLConnection::statement_stats)
+            .fold(StatementStats::default(), |total, stats| StatementStats {
+                executions: total.executions + stats.executions,
+                prepares: total.prepares + stats.prepares,
+            })
+    }
+
+    /// Optional features as detected at startup
+    pub fn schema_features(&self) -> Vec<SchemaFeature> {
+        let feature = |name, source: Option<String>| SchemaFeature {
+            name,
+            enabled: source.is_some(),
+            source,
+        };
+        let table = |available: bool, table: &str| available.then(|| table.to_string());
+        let column = |available: bool, column: &str| {
+            available.then(|| format!("{}.{}", BLOCK_TABLE, column))
+        };
+        let transaction_count_column = match self.transaction_count_source {
+            TransactionCountSource::Cumulative => Some(CUMULATIVE_TRANSACTION_COUNT_COLUMN),
+            TransactionCountSource::PerBlock => Some(TRANSACTION_COUNT_COLUMN),
+            TransactionCountSource::Unavailable => None,
+        };
+        let block_height_source = match self.block_height_source {
+            BlockHeightSource::Column => column(true, BLOCK_HEIGHT_COLUMN),
+            BlockHeightSource::Mapping => table(true, BLOCK_HEIGHTS_TABLE),
+            BlockHeightSource::Unavailable => None,
+        };
+        vec![
+            feature("orphanTracking", table(self.orphan_tracking, ORPHANED_SLOTS_TABLE)),
+            feature("softDelete", column(self.soft_delete, SOFT_DELETE_COLUMN)),
+            feature("correctionTimes", column(self.updated_at_available, UPDATED_AT_COLUMN)),
+            feature(
+                "blockMetaVersions",
+                table(self.versions_available, BLOCK_META_VERSIONS_TABLE),
+            ),
+            feature("rewards", table(self.rewards_available, BLOCK_REWARDS_TABLE)),
+            feature(
+                "inflationRewards",
+                table(self.inflation_rewards_available, INFLATION_REWARDS_TABLE),
+            ),
+            feature("fees", table(self.fees_available, BLOCK_FEES_TABLE)),
+            feature("blockhash", column(self.blockhash_available, BLOCKHASH_COLUMN)),
+            feature(
+                "transactionCount",
+                transaction_count_column.and_then(|name| column(true, name)),
+            ),
+            feature("blockHeight", block_height_source),
+        ]
+    }
+
+    /// Describe the tables of the primary and every shard, and the optional features the
+    /// schema enabled at startup
+    pub async fn get_schema_info(&self) -> Result<SchemaInfo> {
+        let connections = std::iter::once(("primary".to_string(), &self.connection)).chain(
+            self.shards
+                .iter()
+                .map(|shard| (format!("shard {}", shard.start_slot), &shard.connection)),
+        );
+        let mut tables = vec![];
+        for (database, connection) in connections {
+            let client = connection.client();
+            let mut columns: HashMap<String, Vec<String>> = HashMap::new();
+            for (table, column) in client.list_columns().await? {
+                columns.entry(table).or_default().push(column);
+            }
+            for (name, estimated_rows) in client.table_row_estimates().await? {
+                tables.push(TableInfo {
+                    database: database.clone(),
+                    columns: columns.remove(&name).unwrap_or_default(),
+                    name,
+                    estimated_rows,
+                });
+            }
+        }
+        Ok(SchemaInfo {
+            tables,
+            features: self.schema_features(),
         })
     }
 
+    /// Check that the columns every read relies on still exist, on the primary and on
+    /// each shard, returning the missing ones. Optional tables detected at startup are
+    /// not re-detected.
+    pub async fn validate_schema(&self) -> Vec<String> {
+        let mut columns = vec![(BLOCK_TABLE, "id"), (BLOCK_TABLE, "block_time")];
+        match self.block_height_source {
+            BlockHeightSource::Column => columns.push((BLOCK_TABLE, BLOCK_HEIGHT_COLUMN)),
+            BlockHeightSource::Mapping => columns.push((BLOCK_HEIGHTS_TABLE, "block_height")),
+            BlockHeightSource::Unavailable => {}
+        }
+        match self.transaction_count_source {
+            TransactionCountSource::Cumulative => {
+                columns.push((BLOCK_TABLE, CUMULATIVE_TRANSACTION_COUNT_COLUMN))
+            }
+            TransactionCountSource::PerBlock => {
+                columns.push((BLOCK_TABLE, TRANSACTION_COUNT_COLUMN))
+            }
+            TransactionCountSource::Unavailable => {}
+        }
+        if self.soft_delete {
+            columns.push((BLOCK_TABLE, SOFT_DELETE_COLUMN));
+        }
+        if self.blockhash_available {
+            columns.push((BLOCK_TABLE, BLOCKHASH_COLUMN));
+        }
+        // Block columns are checked on every partition instead
+        let block_tables = self.block_tables(0, Slot::MAX);
+
+        let mut missing = vec![];
+        let connections = std::iter::once(("primary".to_string(), &self.connection)).chain(
+            self.shards
+                .iter()
+                .map(|shard| (format!("shard {}", shard.start_slot), &shard.connection)),
+        );
+        for (database, connection) in connections {
+            for (table, column) in &columns {
+                let tables = if *table == BLOCK_TABLE {
+                    block_tables.clone()
+                } else if database == "primary" {
+                    vec![table.to_string()]
+                } else {
+                    // Auxiliary tables are only read from the primary
+                    continue;
+                };
+                for table in &tables {
+                    if !column_exists(connection, table, column).await {
+                        missing.push(format!("{table}.{column} on the {database} database"));
+                    }
+                }
+            }
+        }
+        missing
+    }
+
+    /// Measure the latency and, when the user is allowed to read it, the replication lag
+    /// of every replica. Replicas that fail the probe or lag too far behind are demoted.
+    pub async fn probe_replicas(&self) {
+        let replicas = self.replicas.iter().zip(self.replica_health.iter());
+        for (index, (replica, health)) in replicas.enumerate() {
+            let client = replica.client();
+            let start = std::time::Instant::now();
+            if let Err(err) = client.execute_query_one("SELECT 1").await {
+                warn!("MySQL replica {} failed its health probe: {}", index, err);
+                health.healthy.store(false, Ordering::Relaxed);
+                continue;
+            }
+            health.record_latency(start.elapsed());
+
+            let lag = match self.max_replica_lag {
+                Some(_) => match client.replication_lag().await {
+                    Ok(lag) => lag,
+                    Err(err) => {
+                        debug!("Unable to read replication lag of replica {}: {}", index, err);
+                        None
+                    }
+                },
+                None => None,
+            };
+            let lagging = matches!(
+                (lag, self.max_replica_lag),
+                (Some(lag), Some(max_lag)) if lag > max_lag.as_secs()
+            );
+            if lagging && health.healthy.load(Ordering::Relaxed) {
+                warn!(
+                    "Demoting MySQL replica {}: {}s behind the primary",
+                    index,
+                    lag.unwrap_or_default()
+                );
+            }
+            health.healthy.store(!lagging, Ordering::Relaxed);
+
+            solana_metrics::datapoint_info!(
+                "storage-mysql-replica",
+                ("replica", index as i64, i64),
+                ("latency_us", health.latency_us.load(Ordering::Relaxed) as i64, i64),
+                ("lag_secs", lag.map(|lag| lag.min(i64::MAX as u64) as i64).unwrap_or(-1), i64),
+                ("healthy", !lagging, bool),
+            );
+        }
+    }
+
+    fn replica_stats(&self) -> impl Iterator<Item = (u64, bool)> + '_ {
+        self.replica_health.iter().map(|health| {
+            (
+                health.latency_us.load(Ordering::Relaxed),
+                health.healthy.load(Ordering::Relaxed),
+            )
+        })
+    }
+
+    /// Database unsharded reads go to: the fastest healthy replica, or the primary when
+    /// there is none
+    fn read_connection(&self) -> &MySQLConnection {
+        select_read_replica(self.replica_stats())
+            .map_or(&self.connection, |index| &self.replicas[index])
+    }
+
+    /// Run a read on the fastest healthy replica, falling back to the primary when the
+    /// replica read fails or every replica is demoted.
+    ///
+    /// With hedging enabled, a read still outstanding after the hedge delay is raced
+    /// against the same read on the primary, or, for reads served by the primary, on
+    /// the fastest replica.
+    async fn hedged_read<T, F, Fut>(&self, f: F) -> crate::mysql::Result<T>
+    where
+        F: Fn(MySQLClient) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = crate::mysql::Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let f = Arc::new(f);
+        match select_read_replica(self.replica_stats()) {
+            Some(index) => {
+                let hedge = Some((&self.connection, -1));
+                match self.race_reads(&self.replicas[index], hedge, f.clone()).await {
+                    Err(err) => {
+                        debug!(
+                            "Read on replica {} failed, falling back to primary: {}",
+                            index, err
+                        );
+                        self.race_reads(&self.connection, None, f).await
+                    }
+                    result => result,
+                }
+            }
+            None => {
+                let hedge = select_replica(self.replica_stats())
+                    .map(|index| (&self.replicas[index], index as i64));
+                self.race_reads(&self.connection, hedge, f).await
+            }
+        }
+    }
+
+    /// Run a read on `connection` and, if hedging is enabled and the read is still
+    /// outstanding after the hedge delay, race it against the same read on `hedge`,
+    /// given with its replica index, -1 for the primary.
+    ///
+    /// The first successful response wins. Reads run on the blocking pool, as the
+    /// underlying MySQL client is synchronous. The losing read runs to completion, so
+    /// at most `MAX_IN_FLIGHT_HEDGES` hedges are outstanding; past that, reads are not
+    /// hedged.
+    async fn race_reads<T, F, Fut>(
+        &self,
+        connection: &MySQLConnection,
+        hedge: Option<(&MySQLConnection, i64)>,
+        f: Arc<F>,
+    ) -> crate::mysql::Result<T>
+    where
+        F: Fn(MySQLClient) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = crate::mysql::Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        // A hedge permit is held until the read completes, even when it lost the race
+        let spawn_read = |connection: &MySQLConnection, permit: Option<OwnedSemaphorePermit>| {
+            let client = connection.client();
+            let f = f.clone();
+            let handle = Handle::current();
+            let label = query_label();
+            let budget = query_budget();
+            tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                handle.block_on(with_query_budget(budget, with_query_label(label, f(client))))
+            })
+        };
+        let join = |result: std::result::Result<crate::mysql::Result<T>, JoinError>| {
+            result.unwrap_or_else(|err| {
+                Err(crate::mysql::Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    err.to_string(),
+                )))
+            })
+        };
+
+        let mut read = spawn_read(connection, None);
+        let (delay, (hedge, hedge_index)) = match (self.hedge_delay, hedge) {
+            (Some(delay), Some(hedge)) => (delay, hedge),
+            _ => return join(read.await),
+        };
+        tokio::select! {
+            result = &mut read => return join(result),
+            _ = tokio::time::sleep(delay) => {}
+        }
+        let permit = match self.hedge_permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                debug!("Too many hedged reads in flight, waiting for the first read");
+                return join(read.await);
+            }
+        };
+
+        let mut hedge = spawn_read(hedge, Some(permit));
+        solana_metrics::datapoint_info!(
+            "storage-mysql-hedged-read",
+            ("replica", hedge_index, i64),
+        );
+
+        tokio::select! {
+            result = &mut read => match join(result) {
+                Ok(value) => Ok(value),
+                Err(err) => {
+                    debug!("First read failed, waiting for hedge: {}", err);
+                    join(hedge.await)
+                }
+            },
+            result = &mut hedge => match join(result) {
+                Ok(value) => Ok(value),
+                Err(err) => {
+                    debug!("Hedged read failed, waiting for the first read: {}", err);
+                    join(read.await)
+                }
+            },
+        }
+    }
+
+    pub fn server_version(&self) -> Option<&str> {
+        self.server_version.as_deref()
+    }
+
+    pub fn is_partitioned(&self) -> bool {
+        self.partition_scheme.is_some()
+    }
+
+    fn partitions(&self) -> Arc<Vec<BlockPartition>> {
+        self.partitions.read().unwrap().clone()
+    }
+
+    /// Look the monthly block tables up again, picking up the partition of a new month
+    /// once it holds its first block. Until then, its blocks are not found.
+    pub async fn refresh_partitions(&self) -> Result<()> {
+        let scheme = match &self.partition_scheme {
+            Some(scheme) => scheme,
+            None => return Ok(()),
+        };
+        let partitions = discover_partitions(&self.connection, scheme).await?;
+        if partitions.len() != self.partitions().len() {
+            info!("Serving blocks from {} monthly partitions", partitions.len());
+        }
+        *self.partitions.write().unwrap() = Arc::new(partitions);
+        Ok(())
+    }
+
+    /// Table holding the block at `slot`, or `None` when it precedes every partition
+    fn block_table(&self, slot: Slot) -> Option<String> {
+        if self.partition_scheme.is_none() {
+            return Some(BLOCK_TABLE.to_string());
+        }
+        partition_for(&self.partitions(), slot).map(|partition| partition.table.clone())
+    }
+
+    /// Tables of the primary database holding blocks in `start_slot..=end_slot`
+    fn block_tables(&self, start_slot: Slot, end_slot: Slot) -> Vec<String> {
+        if self.partition_scheme.is_none() {
+            return vec![BLOCK_TABLE.to_string()];
+        }
+        partition_ranges(&self.partitions(), start_slot, end_slot.checked_add(1))
+            .into_iter()
+            .map(|(table, _, _)| table)
+            .collect()
+    }
+
+    /// Database holding the block at `slot` to read it from, or `None` when no shard
+    /// covers it
+    fn block_connection(&self, slot: Slot) -> Option<&MySQLConnection> {
+        self.block_connection_on(self.read_connection(), slot)
+    }
+
+    /// Database holding the block at `slot`, `unsharded` standing for the primary
+    /// database, or `None` when no shard covers it
+    fn block_connection_on<'a>(
+        &'a self,
+        unsharded: &'a MySQLConnection,
+        slot: Slot,
+    ) -> Option<&'a MySQLConnection> {
+        if self.shards.is_empty() {
+            return Some(unsharded);
+        }
+        self.shards
+            .iter()
+            .find(|shard| shard.contains(slot))
+            .map(|shard| &shard.connection)
+    }
+
+    /// Databases and tables to read blocks in `start_slot..end_slot` from, with the part
+    /// of the range each one serves, in slot order
+    fn block_ranges(&self, start_slot: Slot, end_slot: Option<Slot>) -> Vec<ShardRange> {
+        self.block_ranges_on(self.read_connection(), start_slot, end_slot)
+    }
+
+    /// Databases and tables holding blocks in `start_slot..end_slot`, `unsharded`
+    /// standing for the primary database, with the part of the range each one serves,
+    /// in slot order
+    fn block_ranges_on(
+        &self,
+        unsharded: &MySQLConnection,
+        start_slot: Slot,
+        end_slot: Option<Slot>,
+    ) -> Vec<ShardRange> {
+        if self.partition_scheme.is_some() {
+            return partition_ranges(&self.partitions(), start_slot, end_slot)
+                .into_iter()
+                .map(|(table, start_slot, end_slot)| ShardRange {
+                    start_slot,
+                    end_slot,
+                    connection: unsharded.clone(),
+                    table,
+                })
+                .collect();
+        }
+        if self.shards.is_empty() {
+            return vec![ShardRange {
+                start_slot,
+                end_slot,
+                connection: unsharded.clone(),
+                table: BLOCK_TABLE.to_string(),
+            }];
+        }
+        self.shards
+            .iter()
+            .filter_map(|shard| {
+                let start = start_slot.max(shard.start_slot);
+                let end = match (end_slot, shard.end_slot) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+                end.map_or(true, |end| start < end).then_some(ShardRange {
+                    start_slot: start,
+                    end_slot: end,
+                    connection: shard.connection.clone(),
+                    table: BLOCK_TABLE.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Run `f` on every range concurrently, returning the results in slot order.
+    ///
+    /// Each shard query runs on the blocking pool, as the underlying MySQL client is
+    /// synchronous; a single range is queried inline.
+    async fn fan_out<T, F, Fut>(&self, ranges: Vec<ShardRange>, f: F) -> Result<Vec<T>>
+    where
+        F: Fn(ShardRange) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = crate::mysql::Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        if ranges.len() <= 1 {
+            let mut results = Vec::with_capacity(ranges.len());
+            for range in ranges {
+                results.push(f(range).await?);
+            }
+            return Ok(results);
+        }
+
+        let f = Arc::new(f);
+        let tasks: Vec<_> = ranges
+            .into_iter()
+            .map(|range| {
+                let f = f.clone();
+                let handle = Handle::current();
+                let label = query_label();
+                let budget = query_budget();
+                tokio::task::spawn_blocking(move || {
+                    handle.block_on(with_query_budget(budget, with_query_label(label, f(range))))
+                })
+            })
+            .collect();
+        let mut results = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            results.push(task.await.map_err(Error::TokioJoinError)??);
+        }
+        Ok(results)
+    }
+
+    /// Index to force on the block range scans of the RPC method being served
+    fn index_hint(&self) -> Option<String> {
+        query_label()
+            .and_then(|method| self.index_hints.get(method))
+            .or_else(|| self.index_hints.get("*"))
+            .cloned()
+    }
+
+    /// Condition leaving orphaned and soft-deleted blocks out of reads
+    fn read_filter(&self) -> Option<&'static str> {
+        match (self.orphan_tracking, self.soft_delete) {
+            (true, true) => Some(NOT_ORPHANED_OR_DELETED),
+            (true, false) => Some(NOT_ORPHANED),
+            (false, true) => Some(NOT_DELETED),
+            (false, false) => None,
+        }
+    }
+
+    /// Condition leaving orphaned slots out of reads of the side tables keeping the slot
+    /// in `column`
+    fn orphan_filter(&self, column: &str) -> Option<String> {
+        self.orphan_tracking.then(|| not_orphaned_slot(column))
+    }
+
+    /// Mark `slot` as orphaned, or clear the mark, so it is excluded from or restored to
+    /// every read path
+    pub async fn set_slot_orphaned(&self, slot: Slot, orphaned: bool) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        if !self.orphan_tracking {
+            return Err(Error::OrphanTrackingUnavailable);
+        }
+        let query = if orphaned {
+            format!("INSERT IGNORE INTO `{}` (slot) VALUES ({})", ORPHANED_SLOTS_TABLE, slot)
+        } else {
+            format!("DELETE FROM `{}` WHERE slot = {}", ORPHANED_SLOTS_TABLE, slot)
+        };
+        self.block_connection_on(&self.connection, slot)
+            .ok_or(Error::BlockNotFound(slot))?
+            .client()
+            .execute(&query)
+            .await?;
+        Ok(())
+    }
+
+    async fn is_orphaned(&self, slot: Slot) -> Result<bool> {
+        if !self.orphan_tracking {
+            return Ok(false);
+        }
+        let connection = match self.block_connection(slot) {
+            Some(connection) => connection,
+            None => return Ok(false),
+        };
+        let row = connection
+            .client()
+            .get_single_row(ORPHANED_SLOTS_TABLE, "slot", &slot_to_key(slot))
+            .await?;
+        Ok(row.is_some())
+    }
+
+    async fn is_deleted(&self, slot: Slot) -> Result<bool> {
+        if !self.soft_delete {
+            return Ok(false);
+        }
+        let (connection, table) = match (self.block_connection(slot), self.block_table(slot)) {
+            (Some(connection), Some(table)) => (connection, table),
+            _ => return Ok(false),
+        };
+        let deleted = connection
+            .client()
+            .get_single_value::<bool>(
+                &table,
+                SOFT_DELETE_COLUMN,
+                "id",
+                &slot_to_key(slot),
+            )
+            .await;
+        match deleted {
+            Ok(deleted) => Ok(deleted),
+            Err(crate::mysql::Error::RowNotFound) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     /// Return the available slot that contains a block
     pub async fn get_first_available_block(&self) -> Result<Option<Slot>> {
         debug!("MetaStorage::get_first_available_block request received");
 
         // inc_new_counter_debug!("storage-mysql-query", 1);
-        let mysql = self.connection.client();
-
-        // Use `get_first_key` to get the smallest slot
-        let first_block: Option<u64> = mysql
-            .get_first_key("sol_mainnet_block", "id")
-            .await
-            .map_err(|e| Error::StorageBackendError(Box::new(e)))?;
-
-        Ok(first_block.map(|block| block as Slot)) // Convert `u64` to `Slot`
+        for range in self.block_ranges(0, None) {
+            // Use `get_first_key` to get the smallest slot
+            let first_block: Option<u64> = range
+                .connection
+                .client()
+                .get_first_key(&range.table, "id", self.read_filter())
+                .await?;
+            if first_block.is_some() {
+                return Ok(first_block.map(|block| block as Slot)); // Convert `u64` to `Slot`
+            }
+        }
+        Ok(None)
     }
 
     pub async fn get_slot(&self) -> Result<Option<Slot>> {
         debug!("MetaStorage::get_last_available_block request received");
 
         // inc_new_counter_debug!("storage-mysql-query", 1);
-        let mysql = self.connection.client();
-
-        // Use `get_last_key` to get the largest slot
-        let last_block: Option<u64> = mysql
-            .get_last_key("sol_mainnet_block", "id")
-            .await
-            .map_err(|e| Error::StorageBackendError(Box::new(e)))?;
-
-        Ok(last_block.map(|block| block as Slot)) // Convert `u64` to `Slot`
+        for range in self.block_ranges(0, None).into_iter().rev() {
+            // Use `get_last_key` to get the largest slot
+            let last_block: Option<u64> = range
+                .connection
+                .client()
+                .get_last_key(&range.table, "id", self.read_filter())
+                .await?;
+            if last_block.is_some() {
+                return Ok(last_block.map(|block| block as Slot)); // Convert `u64` to `Slot`
+            }
+        }
+        Ok(None)
     }
 
     /// Fetch the next slots after the provided slot that contains a block
     ///
     /// start_slot: slot to start the search from (inclusive)
+    /// end_slot: stop at this slot (inclusive)
     /// limit: stop after this many slots have been found
-    pub async fn get_confirmed_blocks(&self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>> {
+    pub async fn get_confirmed_blocks(
+        &self,
+        start_slot: Slot,
+        end_slot: Option<Slot>,
+        limit: usize,
+    ) -> Result<Vec<Slot>> {
         debug!(
             "MetaStorage::get_confirmed_blocks request received: start_slot = {:?}, limit = {:?}",
             start_slot, limit
         );
 
         // inc_new_counter_debug!("storage-mysql-query", 1);
-        let mysql = self.connection.client();
-        let start_key = slot_to_key(start_slot);
-        // let end_key = slot_to_key(start_slot + limit as u64);
-        let blocks: Vec<u64> = mysql
-            .get_row_keys("sol_mainnet_block", Some(&start_key), None, limit as i64)
+        let read_filter = self.read_filter();
+        let index_hint = self.index_hint();
+        let scan = move |range: ShardRange, limit: usize| {
+            let index_hint = index_hint.clone();
+            async move {
+                scan_block_keys(
+                    range.connection.client(),
+                    &range.table,
+                    range.start_slot,
+                    range.last_slot(),
+                    read_filter,
+                    index_hint.as_deref(),
+                    limit,
+                )
+                .await
+            }
+        };
+        let ranges = self.block_ranges(start_slot, end_slot.and_then(|slot| slot.checked_add(1)));
+
+        // Shard ranges are disjoint and in slot order, so their sorted results merge by
+        // concatenation; the limit applies to the merged list
+        if end_slot.is_some() {
+            let shard_blocks = self
+                .fan_out(ranges, move |range| scan(range, limit))
+                .await?;
+            return Ok(shard_blocks
+                .into_iter()
+                .flatten()
+                .map(|block| block as Slot)
+                .take(limit)
+                .collect());
+        }
+
+        // Without an end slot the range runs to the newest partition, so ranges are read
+        // one at a time and the walk stops as soon as the limit is reached
+        let mut blocks = vec![];
+        for range in ranges {
+            if blocks.len() >= limit {
+                break;
+            }
+            let remaining = limit - blocks.len();
+            let scan = scan.clone();
+            let shard_blocks = self
+                .fan_out(vec![range], move |range| scan(range, remaining))
+                .await?;
+            blocks.extend(shard_blocks.into_iter().flatten().map(|block| block as Slot));
+        }
+        Ok(blocks)
+    }
+
+    /// Count the stored blocks in `start_slot..=end_slot`
+    pub async fn count_blocks(&self, start_slot: Slot, end_slot: Slot) -> Result<u64> {
+        debug!(
+            "MetaStorage::count_blocks request received: {}-{}",
+            start_slot, end_slot
+        );
+        let read_filter = self.read_filter();
+        let force_index = force_index(self.index_hint().as_deref());
+        let counts = self
+            .fan_out(
+                self.block_ranges(start_slot, end_slot.checked_add(1)),
+                move |range| {
+                    let force_index = force_index.clone();
+                    async move {
+                        let mut filter = range.filter();
+                        if let Some(read_filter) = read_filter {
+                            filter.push_str(" AND ");
+                            filter.push_str(read_filter);
+                        }
+                        let query = format!(
+                            "SELECT COUNT(*) FROM `{}`{} WHERE {}",
+                            range.table, force_index, filter
+                        );
+                        Ok(range
+                            .connection
+                            .client()
+                            .execute_query_one(&query)
+                            .await?
+                            .and_then(|mut row| row.take::<u64, _>(0))
+                            .unwrap_or_default())
+                    }
+                },
+            )
             .await?;
-        Ok(blocks.into_iter().map(|block| block as Slot).collect())
+        Ok(counts.into_iter().sum())
     }
 
     pub async fn get_block_time(&self, slot: Slot) -> Result<DateTime<Utc>> {
         info!("get_block_time request received");
 
-        let mysql = self.connection.client();
+        if self.is_orphaned(slot).await? || self.is_deleted(slot).await? {
+            return Err(Error::BlockNotFound(slot));
+        }
+
         let key = slot_to_key(slot);
+        let format = self.block_time_format;
+        let table = self.block_table(slot).ok_or(Error::BlockNotFound(slot))?;
 
-        // Fetch `PrimitiveDateTime` directly from MySQL
-        let block_time_primitive: PrimitiveDateTime = mysql
-            .get_single_value::<PrimitiveDateTime>("sol_mainnet_block", "block_time", "id", &key)
+        let result = if self.shards.is_empty() {
+            self.hedged_read(move |mysql| {
+                let (table, key) = (table.clone(), key.clone());
+                async move { read_block_time(&mysql, &table, &key, format).await }
+            })
             .await
-            .map_err(|e| match e {
-                crate::mysql::Error::RowNotFound => Error::BlockNotFound(slot),
-                other => Error::StorageBackendError(Box::new(other)),
-            })?;
-
-        // Convert to `DateTime<Utc>` using `DateTime::from_timestamp`
-        let block_time = DateTime::<Utc>::from_timestamp(
-            block_time_primitive.assume_utc().unix_timestamp(),
-            block_time_primitive.assume_utc().microsecond() * 1000,
-        )
+        } else {
+            match self.block_connection(slot) {
+                Some(connection) => {
+                    read_block_time(&connection.client(), &table, &key, format).await
+                }
+                None => Err(crate::mysql::Error::RowNotFound),
+            }
+        };
+        result
+            .map_err(|err| block_read_error(slot, err))?
+            .ok_or(Error::BlockNotFound(slot))
+    }
+
+    pub fn block_meta_versions_available(&self) -> bool {
+        self.versions_available
+    }
+
+    /// Fetch the current metadata of the block at `slot`, soft-deleted or not, with the
+    /// versions it superseded
+    pub async fn get_block_meta_versions(&self, slot: Slot) -> Result<BlockMetaVersions> {
+        debug!("MetaStorage::get_block_meta_versions request received: {}", slot);
+        if !self.versions_available {
+            return Err(Error::BlockMetaVersionsUnavailable);
+        }
+
+        let block_time = self.block_time_format.to_unix_seconds("block_time");
+        let deleted = if self.soft_delete { SOFT_DELETE_COLUMN } else { "0" };
+        let updated_at = if self.updated_at_available {
+            format!("CAST(UNIX_TIMESTAMP(`{}`) AS SIGNED)", UPDATED_AT_COLUMN)
+        } else {
+            "NULL".to_string()
+        };
+        let table = self.block_table(slot).ok_or(Error::BlockNotFound(slot))?;
+        let query = format!(
+            "SELECT {}, {}, {} FROM `{}` WHERE id = {}",
+            block_time, deleted, updated_at, table, slot
+        );
+        let current = self
+            .block_connection(slot)
+            .ok_or(Error::BlockNotFound(slot))?
+            .client()
+            .execute_query_one(&query)
+            .await?
             .ok_or(Error::BlockNotFound(slot))?;
+        let current = map_row(current, &table)?;
+
+        let query = format!(
+            "SELECT {}, is_deleted, CAST(UNIX_TIMESTAMP(updated_at) AS SIGNED) FROM `{}` \
+             WHERE slot = {} ORDER BY updated_at",
+            block_time, BLOCK_META_VERSIONS_TABLE, slot
+        );
+        let history = self
+            .connection
+            .client()
+            .execute_query_all(&query)
+            .await?
+            .into_iter()
+            .map(|row| map_row(row, BLOCK_META_VERSIONS_TABLE))
+            .collect::<crate::mysql::Result<_>>()?;
+        Ok(BlockMetaVersions { current, history })
+    }
+
+    /// Fetch the backfill ranges overlapping `start_slot..=end_slot`, ordered by slot.
+    ///
+    /// Deployments without the backfill status table report no ranges.
+    pub async fn get_backfill_status(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<Vec<BackfillRange>> {
+        debug!(
+            "MetaStorage::get_backfill_status request received: {}-{}",
+            start_slot, end_slot
+        );
+
+        let mysql = self.read_connection().client();
+        let query = format!(
+            "SELECT start_slot, end_slot, status FROM `{}` \
+             WHERE end_slot >= {} AND start_slot <= {} ORDER BY start_slot LIMIT {}",
+            BACKFILL_STATUS_TABLE, start_slot, end_slot, MAX_BACKFILL_RANGES
+        );
+        let rows = match mysql.execute_query_all(&query).await {
+            Ok(rows) => rows,
+            Err(crate::mysql::Error::MySQL(mysql::Error::MySqlError(err)))
+                if err.code == ER_NO_SUCH_TABLE =>
+            {
+                return Ok(vec![]);
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(rows
+            .into_iter()
+            .map(|row| map_row(row, BACKFILL_STATUS_TABLE))
+            .collect::<crate::mysql::Result<_>>()?)
+    }
+
+    /// Return the first slot whose block time is at or after `timestamp`. Orphaned and
+    /// soft-deleted blocks are left out.
+    ///
+    /// `block_time` has no index, so rather than scanning it the slot is found by a binary
+    /// search over primary key lookups, relying on block times growing with the slot.
+    pub async fn get_first_slot_since(&self, timestamp: i64) -> Result<Option<Slot>> {
+        let block_time = self.block_time_format.to_unix_seconds("block_time");
+        for range in self.block_ranges(0, None) {
+            // Skip ranges ending before `timestamp`
+            let mut high = match self.block_time_at(&range, &block_time, range.start_slot, true).await? {
+                Some((slot, time)) if time >= timestamp => slot,
+                _ => continue,
+            };
+            let mut low = match self.block_time_at(&range, &block_time, range.start_slot, false).await? {
+                Some((slot, time)) if time < timestamp => slot,
+                first_block => return Ok(first_block.map(|(slot, _)| slot)),
+            };
+
+            // The first block at or after `low` is older than `timestamp`, while `found`, the
+            // first block at or after `high`, is not
+            let mut found = high;
+            while high.saturating_sub(low) > 1 {
+                let mid = low + (high - low) / 2;
+                match self.block_time_at(&range, &block_time, mid, false).await? {
+                    Some((slot, time)) if time >= timestamp => {
+                        high = mid;
+                        found = slot;
+                    }
+                    Some((slot, _)) => low = slot,
+                    None => break,
+                }
+            }
+            return Ok(Some(found));
+        }
+        Ok(None)
+    }
+
+    /// Slot and block time of the first readable block of `range` at or after `slot`, or of
+    /// its last readable block if `last` is set
+    async fn block_time_at(
+        &self,
+        range: &ShardRange,
+        block_time: &str,
+        slot: Slot,
+        last: bool,
+    ) -> Result<Option<(Slot, i64)>> {
+        let query = block_time_at_query(
+            block_time,
+            &range.table,
+            &range.filter(),
+            slot,
+            last,
+            self.read_filter(),
+        );
+        let block_times = read_block_times(&range.connection.client(), &range.table, &query).await?;
+        Ok(block_times.into_iter().next())
+    }
+
+    /// Fetch the block times of every block in `start_slot..end_slot`, ordered by slot.
+    /// Orphaned and soft-deleted blocks are left out.
+    pub async fn get_block_times_in_range(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<Vec<(Slot, i64)>> {
+        let mut block_times = vec![];
+        let force_index = force_index(self.index_hint().as_deref());
+        for range in self.block_ranges(start_slot, Some(end_slot)) {
+            let query = block_times_query(
+                &self.block_time_format.to_unix_seconds("block_time"),
+                &range.table,
+                &force_index,
+                range.filter(),
+                self.read_filter(),
+            );
+            block_times.extend(
+                read_block_times(&range.connection.client(), &range.table, &query).await?,
+            );
+        }
+        Ok(block_times)
+    }
+
+    /// Fetch the block times, in milliseconds, of every block in `start_slot..end_slot`,
+    /// ordered by slot. Orphaned and soft-deleted blocks are left out.
+    pub async fn get_block_times_millis_in_range(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<Vec<(Slot, i64)>> {
+        let mut block_times = vec![];
+        let force_index = force_index(self.index_hint().as_deref());
+        for range in self.block_ranges(start_slot, Some(end_slot)) {
+            let query = block_times_query(
+                &self.block_time_format.to_unix_millis("block_time"),
+                &range.table,
+                &force_index,
+                range.filter(),
+                self.read_filter(),
+            );
+            block_times.extend(
+                read_block_times(&range.connection.client(), &range.table, &query).await?,
+            );
+        }
+        Ok(block_times)
+    }
+
+    /// Fetch the block times, in unix seconds, of the stored blocks among `slots` with one
+    /// query per database and chunk of keys. The result is ordered by slot; missing,
+    /// orphaned and soft-deleted slots are left out.
+    pub async fn get_block_times(&self, slots: &[Slot]) -> Result<Vec<(Slot, i64)>> {
+        let block_time = self.block_time_format.to_unix_seconds("block_time");
+        let mut block_times = vec![];
+        for (connection, table, keys) in self.keys_by_table(slots) {
+            let rows = connection
+                .client()
+                .get_rows_by_keys(
+                    &table,
+                    &["id", block_time.as_str()],
+                    "id",
+                    &keys,
+                    self.read_filter(),
+                )
+                .await?;
+            for row in rows {
+                block_times.push(map_row(row, &table)?);
+            }
+        }
+        block_times.sort_unstable_by_key(|&(slot, _)| slot);
+        block_times.dedup_by_key(|&mut (slot, _)| slot);
+        Ok(block_times)
+    }
+
+    /// Return whether a block is stored at each of `slots`, in order, with one query per
+    /// database and chunk of keys. Orphaned and soft-deleted blocks count as missing.
+    pub async fn blocks_exist(&self, slots: &[Slot]) -> Result<Vec<bool>> {
+        let mut stored = HashSet::new();
+        for (connection, table, keys) in self.keys_by_table(slots) {
+            let rows = connection
+                .client()
+                .get_rows_by_keys(&table, &["id"], "id", &keys, self.read_filter())
+                .await?;
+            for mut row in rows {
+                stored.insert(take_column::<u64>(&mut row, 0, &table)?);
+            }
+        }
+        Ok(slots.iter().map(|slot| stored.contains(slot)).collect())
+    }
+
+    /// Group the keys of `slots` by the database and table holding their blocks
+    fn keys_by_table(&self, slots: &[Slot]) -> Vec<(&MySQLConnection, String, Vec<String>)> {
+        let mut by_table: Vec<(&MySQLConnection, String, Vec<String>)> = vec![];
+        for &slot in slots {
+            let (connection, table) = match (self.block_connection(slot), self.block_table(slot)) {
+                (Some(connection), Some(table)) => (connection, table),
+                _ => continue,
+            };
+            match by_table.iter_mut().find(|(other, other_table, _)| {
+                std::ptr::eq(*other, connection) && *other_table == table
+            }) {
+                Some((_, _, keys)) => keys.push(slot_to_key(slot)),
+                None => by_table.push((connection, table, vec![slot_to_key(slot)])),
+            }
+        }
+        by_table
+    }
+
+    /// Delete up to `batch_size` blocks below `slot`, returning the number of rows deleted
+    pub async fn purge_blocks_below(&self, slot: Slot, batch_size: usize) -> Result<u64> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        let mut deleted = 0;
+        for range in self.block_ranges_on(&self.connection, 0, Some(slot)) {
+            let remaining = batch_size as u64 - deleted;
+            if remaining == 0 {
+                break;
+            }
+            let query = format!(
+                "DELETE FROM `{}` WHERE id < {} ORDER BY id LIMIT {}",
+                range.table, slot, remaining
+            );
+            deleted += range.connection.client().execute(&query).await?;
+        }
+        Ok(deleted)
+    }
+
+    pub fn rewards_available(&self) -> bool {
+        self.rewards_available
+    }
+
+    /// Fetch the rewards paid out in the block at `slot`, none if the slot is orphaned
+    pub async fn get_block_rewards(&self, slot: Slot) -> Result<Vec<BlockReward>> {
+        debug!("MetaStorage::get_block_rewards request received: {}", slot);
+        if !self.rewards_available {
+            return Err(Error::RewardsUnavailable);
+        }
+
+        let query = block_rewards_query(slot, self.orphan_filter("slot").as_deref());
+        let rows = self.read_connection().client().execute_query_all(&query).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| map_row(row, BLOCK_REWARDS_TABLE))
+            .collect::<crate::mysql::Result<_>>()?)
+    }
+
+    /// Fetch the inflation rewards of `addresses` for `epoch`. Addresses without a
+    /// reward, or whose reward was credited in an orphaned slot, are left out of the result.
+    ///
+    /// Callers must pass validated addresses, as they are inlined into the query.
+    pub async fn get_inflation_rewards(
+        &self,
+        addresses: &[String],
+        epoch: u64,
+    ) -> Result<Vec<InflationReward>> {
+        debug!(
+            "MetaStorage::get_inflation_rewards request received: {} addresses, epoch {}",
+            addresses.len(),
+            epoch
+        );
+        if !self.inflation_rewards_available {
+            return Err(Error::InflationRewardsUnavailable);
+        }
+        if addresses.is_empty() {
+            return Ok(vec![]);
+        }
 
-        Ok(block_time)
+        let query =
+            inflation_rewards_query(addresses.len(), self.orphan_filter("effective_slot").as_deref());
+        let params = std::iter::once(epoch.into())
+            .chain(addresses.iter().map(|address| address.as_str().into()))
+            .collect();
+        let rows = self
+            .read_connection()
+            .client()
+            .exec_query_all(&query, Params::Positional(params))
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| map_row(row, INFLATION_REWARDS_TABLE))
+            .collect::<crate::mysql::Result<_>>()?)
     }
 
+    /// Compute fee statistics over `start_slot..=end_slot` in SQL, either per block or
+    /// aggregated over the whole range. Orphaned slots are left out.
+    pub async fn get_fee_stats(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+        per_block: bool,
+    ) -> Result<Vec<FeeStats>> {
+        debug!(
+            "MetaStorage::get_fee_stats request received: {}-{}, per_block = {}",
+            start_slot, end_slot, per_block
+        );
+        if !self.fees_available {
+            return Err(Error::FeesUnavailable);
+        }
+
+        let query = fee_stats_query(
+            start_slot,
+            end_slot,
+            per_block,
+            self.orphan_filter("slot").as_deref(),
+        );
+        let rows = self.read_connection().client().execute_query_all(&query).await?;
+        let stats = rows
+            .into_iter()
+            .map(|row| map_row::<FeeStats>(row, BLOCK_FEES_TABLE))
+            .collect::<crate::mysql::Result<Vec<_>>>()?;
+        // An aggregate over no rows still yields a single row
+        Ok(stats.into_iter().filter(|stats| stats.transactions > 0).collect())
+    }
+
+    /// Fetch the lowest prioritization fee paid in each block of `start_slot..=end_slot`,
+    /// leaving out orphaned slots
+    pub async fn get_min_prioritization_fees(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<Vec<(Slot, u64)>> {
+        if !self.fees_available {
+            return Err(Error::FeesUnavailable);
+        }
+        let query = min_prioritization_fees_query(
+            start_slot,
+            end_slot,
+            self.orphan_filter("slot").as_deref(),
+        );
+        let rows = self.read_connection().client().execute_query_all(&query).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| map_row(row, BLOCK_FEES_TABLE))
+            .collect::<crate::mysql::Result<_>>()?)
+    }
+
+    /// Return the number of transactions processed up to the latest stored block.
+    ///
+    /// Without a cumulative column the per-block counts are summed once and then kept
+    /// as a running total, so slots orphaned after being counted stay included.
+    pub async fn get_transaction_count(&self) -> Result<u64> {
+        debug!("MetaStorage::get_transaction_count request received");
+        let latest_slot = match self.get_slot().await? {
+            Some(latest_slot) => latest_slot,
+            None => return Ok(0),
+        };
+        let mysql = self.read_connection().client();
+
+        match self.transaction_count_source {
+            TransactionCountSource::Cumulative => mysql
+                .get_single_value::<u64>(
+                    &self
+                        .block_table(latest_slot)
+                        .ok_or(Error::BlockNotFound(latest_slot))?,
+                    CUMULATIVE_TRANSACTION_COUNT_COLUMN,
+                    "id",
+                    &slot_to_key(latest_slot),
+                )
+                .await
+                .map_err(|err| block_read_error(latest_slot, err)),
+            TransactionCountSource::PerBlock => {
+                let mut total = self.transaction_count_total.lock().await;
+                let (counted_slot, count) = match *total {
+                    Some((counted_slot, count)) if counted_slot >= latest_slot => {
+                        return Ok(count)
+                    }
+                    Some((counted_slot, count)) => (Some(counted_slot), count),
+                    None => (None, 0),
+                };
+                let mut added = 0;
+                let start_slot = counted_slot.map_or(0, |counted_slot| counted_slot + 1);
+                for table in self.block_tables(start_slot, latest_slot) {
+                    let mut filter = format!("id <= {}", latest_slot);
+                    if let Some(counted_slot) = counted_slot {
+                        filter.push_str(&format!(" AND id > {}", counted_slot));
+                    }
+                    if let Some(read_filter) = self.read_filter() {
+                        filter.push_str(" AND ");
+                        filter.push_str(read_filter);
+                    }
+                    let query = format!(
+                        "SELECT CAST(COALESCE(SUM(`{}`), 0) AS UNSIGNED) FROM `{}`{} WHERE {}",
+                        TRANSACTION_COUNT_COLUMN,
+                        table,
+                        force_index(self.index_hint().as_deref()),
+                        filter
+                    );
+                    added += mysql
+                        .execute_query_one(&query)
+                        .await?
+                        .and_then(|mut row| row.take::<u64, _>(0))
+                        .unwrap_or_default();
+                }
+                let count = count + added;
+                *total = Some((latest_slot, count));
+                Ok(count)
+            }
+            TransactionCountSource::Unavailable => Err(Error::TransactionCountUnavailable),
+        }
+    }
+
+    /// Return the height of the latest block of the block table, the same block
+    /// `get_slot` reports
     pub async fn get_block_height(&self) -> Result<u64> {
-        info!("get_block_height request received");
+        debug!("MetaStorage::get_block_height request received");
+        if self.block_height_source == BlockHeightSource::Unavailable {
+            return Err(Error::BlockHeightUnavailable);
+        }
+        let latest_slot = self.get_slot().await?.ok_or(Error::BlockNotFound(0))?;
 
-        debug!("MetaStorage::get_block_height request received to fetch the latest block height");
+        match self.block_height_source {
+            BlockHeightSource::Column => self.read_block_height(latest_slot).await,
+            BlockHeightSource::Mapping => {
+                // The mapping may trail the block table, the last height it recorded up to
+                // the latest block is reported rather than a newer block of its own
+                let query = format!(
+                    "SELECT block_height FROM `{}` WHERE id <= {} ORDER BY id DESC LIMIT 1",
+                    BLOCK_HEIGHTS_TABLE, latest_slot
+                );
+                let row = self
+                    .connection
+                    .client()
+                    .execute_query_one(&query)
+                    .await?
+                    .ok_or(Error::BlockNotFound(latest_slot))?;
+                Ok(RowReader::new(row, BLOCK_HEIGHTS_TABLE).at(0)?)
+            }
+            BlockHeightSource::Unavailable => Err(Error::BlockHeightUnavailable),
+        }
+    }
 
-        // inc_new_counter_debug!("storage-mysql-query", 1);
+    /// Return the height of the block at `slot`
+    pub async fn get_block_height_for_slot(&self, slot: Slot) -> Result<u64> {
+        debug!("MetaStorage::get_block_height_for_slot request received: {}", slot);
+        if self.block_height_source == BlockHeightSource::Unavailable {
+            return Err(Error::BlockHeightUnavailable);
+        }
+        if self.is_orphaned(slot).await? || self.is_deleted(slot).await? {
+            return Err(Error::BlockNotFound(slot));
+        }
+        self.read_block_height(slot).await
+    }
 
-        let mysql = self.connection.client();
+    /// Return the slot of the block at `block_height`, `None` when no stored block has it.
+    /// Blocks are looked up by height, which wants an index on the height column.
+    pub async fn get_slot_for_block_height(&self, block_height: u64) -> Result<Option<Slot>> {
+        debug!(
+            "MetaStorage::get_slot_for_block_height request received: {}",
+            block_height
+        );
+        match self.block_height_source {
+            BlockHeightSource::Column => {
+                self.find_block(&format!("`{}` = {}", BLOCK_HEIGHT_COLUMN, block_height))
+                    .await
+            }
+            BlockHeightSource::Mapping => {
+                let query = format!(
+                    "SELECT id FROM `{}` WHERE `{}` = {} LIMIT 1",
+                    BLOCK_HEIGHTS_TABLE, BLOCK_HEIGHT_COLUMN, block_height
+                );
+                let row = self.read_connection().client().execute_query_one(&query).await?;
+                let slot: Slot = match row {
+                    Some(row) => RowReader::new(row, BLOCK_HEIGHTS_TABLE).at(0)?,
+                    None => return Ok(None),
+                };
+                // The mapping knows nothing of the marks of the block table
+                if self.is_orphaned(slot).await? || self.is_deleted(slot).await? {
+                    return Ok(None);
+                }
+                Ok(Some(slot))
+            }
+            BlockHeightSource::Unavailable => Err(Error::BlockHeightUnavailable),
+        }
+    }
 
-        // Fetch the ID of the latest block
-        let latest_block_id: u64 = mysql
-            .get_last_key("solana_blocks", "id")
+    /// Return the slot of the block hashed `blockhash`, `None` when no stored block has it
+    pub async fn get_slot_for_blockhash(&self, blockhash: &Hash) -> Result<Option<Slot>> {
+        debug!("MetaStorage::get_slot_for_blockhash request received: {}", blockhash);
+        if !self.blockhash_available {
+            return Err(Error::BlockhashUnavailable);
+        }
+        // The base58 rendering of a `Hash` needs no escaping
+        self.find_block(&format!("`{}` = '{}'", BLOCKHASH_COLUMN, blockhash))
             .await
-            .map_err(|e| Error::StorageBackendError(Box::new(e)))?
-            .ok_or_else(|| Error::BlockNotFound(0))?; // Handle case where no blocks exist
+    }
 
-        debug!("Latest block ID fetched: {}", latest_block_id);
+    /// Slot of a block matching `condition`, searching the newest tables first as lookups
+    /// mostly target recent blocks. The condition should be backed by an index.
+    async fn find_block(&self, condition: &str) -> Result<Option<Slot>> {
+        for range in self.block_ranges(0, None).into_iter().rev() {
+            let mut query = format!("SELECT id FROM `{}` WHERE {}", range.table, condition);
+            if let Some(filter) = self.read_filter() {
+                query.push_str(&format!(" AND {}", filter));
+            }
+            query.push_str(" LIMIT 1");
+            if let Some(row) = range.connection.client().execute_query_one(&query).await? {
+                return Ok(Some(RowReader::new(row, &range.table).at(0)?));
+            }
+        }
+        Ok(None)
+    }
 
-        // Fetch the block height using the latest block ID
-        let block_height: u64 = mysql
-            .get_single_value::<u64>("solana_blocks", "block_height", "id", &latest_block_id.to_string())
+    /// Read the stored height of the block at `slot`, from wherever the schema keeps it
+    async fn read_block_height(&self, slot: Slot) -> Result<u64> {
+        let (connection, table) = match self.block_height_source {
+            BlockHeightSource::Column => {
+                match (self.block_connection(slot), self.block_table(slot)) {
+                    (Some(connection), Some(table)) => (connection, table),
+                    _ => return Err(Error::BlockNotFound(slot)),
+                }
+            }
+            BlockHeightSource::Mapping => (self.read_connection(), BLOCK_HEIGHTS_TABLE.to_string()),
+            BlockHeightSource::Unavailable => return Err(Error::BlockHeightUnavailable),
+        };
+        connection
+            .client()
+            .get_single_value::<u64>(&table, BLOCK_HEIGHT_COLUMN, "id", &slot_to_key(slot))
             .await
-            .map_err(|e| match e {
-                crate::mysql::Error::RowNotFound => Error::BlockNotFound(latest_block_id),
-                other => Error::StorageBackendError(Box::new(other)),
-            })?;
+            .map_err(|err| block_read_error(slot, err))
+    }
+}
 
-        debug!("Latest block Height fetched: {}", block_height);
+#[cfg(test)]
+mod tests {
+    use {super::*, proptest::prelude::*};
 
-        Ok(block_height)
+    proptest! {
+        #[test]
+        fn test_slot_key_round_trip(slot in any::<Slot>()) {
+            prop_assert_eq!(key_to_slot(&slot_to_key(slot)), Some(slot));
+        }
+
+        #[test]
+        fn test_key_to_slot_rejects_non_slots(key in "[^0-9]*|-[0-9]+") {
+            prop_assert_eq!(key_to_slot(&key), None);
+        }
+    }
+
+    #[test]
+    fn test_block_read_error() {
+        assert!(matches!(
+            block_read_error(5, crate::mysql::Error::RowNotFound),
+            Error::BlockNotFound(5)
+        ));
+        assert!(matches!(
+            block_read_error(5, crate::mysql::Error::PoolExhausted),
+            Error::StorageBusy
+        ));
+        assert!(matches!(
+            block_read_error(5, crate::mysql::Error::Timeout),
+            Error::StorageBackendError(_)
+        ));
+    }
+
+    #[test]
+    fn test_partition_routing() {
+        let partition = |table: &str, start_slot| BlockPartition {
+            table: table.to_string(),
+            start_slot,
+        };
+        let partitions = vec![
+            partition("sol_block_2024_05", 100),
+            partition("sol_block_2024_06", 200),
+            partition("sol_block_2024_07", 300),
+        ];
+        let table = |slot| partition_for(&partitions, slot).map(|p| p.table.as_str());
+        assert_eq!(table(99), None);
+        assert_eq!(table(100), Some("sol_block_2024_05"));
+        assert_eq!(table(299), Some("sol_block_2024_06"));
+        assert_eq!(table(u64::MAX), Some("sol_block_2024_07"));
+
+        assert_eq!(
+            partition_ranges(&partitions, 150, Some(250)),
+            vec![
+                ("sol_block_2024_05".to_string(), 150, Some(200)),
+                ("sol_block_2024_06".to_string(), 200, Some(250)),
+            ]
+        );
+        assert_eq!(
+            partition_ranges(&partitions, 0, Some(200)),
+            vec![("sol_block_2024_05".to_string(), 100, Some(200))]
+        );
+        assert_eq!(
+            partition_ranges(&partitions, 350, None),
+            vec![("sol_block_2024_07".to_string(), 350, None)]
+        );
+        assert_eq!(partition_ranges(&partitions, 0, Some(100)), vec![]);
+        assert_eq!(partition_ranges(&[], 0, None), vec![]);
+    }
+
+    #[test]
+    fn test_select_read_replica() {
+        assert_eq!(select_read_replica(vec![].into_iter()), None);
+        assert_eq!(select_read_replica(vec![(900, true), (300, true)].into_iter()), Some(1));
+        assert_eq!(select_read_replica(vec![(900, true), (300, false)].into_iter()), Some(0));
+        // Reads fall back to the primary when every replica is demoted
+        assert_eq!(select_read_replica(vec![(900, false), (300, false)].into_iter()), None);
+    }
+
+    #[test]
+    fn test_select_replica() {
+        assert_eq!(select_replica(vec![].into_iter()), None);
+        assert_eq!(select_replica(vec![(900, true), (300, true)].into_iter()), Some(1));
+        // A lagging replica is skipped even when it is the fastest
+        assert_eq!(select_replica(vec![(900, true), (300, false)].into_iter()), Some(0));
+        assert_eq!(select_replica(vec![(900, false), (300, false)].into_iter()), Some(1));
+    }
+
+    #[test]
+    fn test_inflation_rewards_query_binds_addresses() {
+        assert_eq!(
+            inflation_rewards_query(3, None),
+            "SELECT address, epoch, effective_slot, amount, post_balance, commission \
+             FROM `inflation_rewards` WHERE epoch = ? AND address IN (?, ?, ?)"
+        );
+    }
+
+    #[test]
+    fn test_block_time_at_query() {
+        assert_eq!(
+            block_time_at_query("block_time", "sol_mainnet_block", "id >= 5", 9, false, None),
+            "SELECT id, block_time FROM `sol_mainnet_block` WHERE id >= 5 AND id >= 9 \
+             ORDER BY id ASC LIMIT 1"
+        );
+        assert_eq!(
+            block_time_at_query(
+                "block_time",
+                "sol_mainnet_block",
+                "id >= 5 AND id < 10",
+                9,
+                true,
+                Some(NOT_ORPHANED_OR_DELETED),
+            ),
+            "SELECT id, block_time FROM `sol_mainnet_block` WHERE id >= 5 AND id < 10 \
+             AND id NOT IN (SELECT slot FROM orphaned_slots) AND is_deleted = 0 \
+             ORDER BY id DESC LIMIT 1"
+        );
+    }
+
+    #[test]
+    fn test_range_queries_leave_out_orphaned_slots() {
+        let block_times = |read_filter| {
+            block_times_query("block_time", "sol_mainnet_block", "", "id >= 5".into(), read_filter)
+        };
+        let side_queries = |orphan_filter: Option<&str>| {
+            vec![
+                block_rewards_query(7, orphan_filter),
+                fee_stats_query(5, 9, true, orphan_filter),
+                fee_stats_query(5, 9, false, orphan_filter),
+                min_prioritization_fees_query(5, 9, orphan_filter),
+            ]
+        };
+
+        assert!(!block_times(None).contains(ORPHANED_SLOTS_TABLE));
+        assert!(!inflation_rewards_query(1, None).contains(ORPHANED_SLOTS_TABLE));
+        for query in side_queries(None) {
+            assert!(!query.contains(ORPHANED_SLOTS_TABLE), "{query}");
+        }
+
+        assert!(block_times(Some(NOT_ORPHANED))
+            .contains("AND id NOT IN (SELECT slot FROM orphaned_slots)"));
+        let query = inflation_rewards_query(1, Some(&not_orphaned_slot("effective_slot")));
+        assert!(query.contains("AND `effective_slot` NOT IN (SELECT slot FROM `orphaned_slots`)"));
+        for query in side_queries(Some(&not_orphaned_slot("slot"))) {
+            assert!(
+                query.contains("AND `slot` NOT IN (SELECT slot FROM `orphaned_slots`)"),
+                "{query}"
+            );
+        }
     }
 }